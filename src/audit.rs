@@ -0,0 +1,122 @@
+use std::path::Path;
+
+use poise::serenity_prelude::{GuildId, Timestamp, UserId};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+
+/// Record of a configuration change, made by a command
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AuditEntry {
+    pub user_id: UserId,
+    pub command: String,
+    pub guild: Option<GuildId>,
+    pub args: String,
+    pub timestamp: Timestamp,
+}
+
+/// Append-only audit trail. Each entry is appended as a separate `[[entries]]` table, so the file is never rewritten
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AuditLog {
+    #[serde(default)]
+    entries: Vec<AuditEntry>,
+}
+
+impl AuditEntry {
+    pub async fn append_to_file<T: AsRef<Path>>(&self, audit_path: &T) -> anyhow::Result<()> {
+        let serialized_string = toml::to_string(&AuditLog {
+            entries: vec![self.clone()],
+        })
+        .map_err(|err| {
+            anyhow::Error::msg(format!(
+                "Broken serialization of AuditEntry: got {}, while serializing {:?}",
+                err, self
+            ))
+        })?;
+        let mut audit_file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(audit_path)
+            .await
+            .map_err(|err| {
+                anyhow::Error::msg(format!(
+                    "Failed to open {}: {}",
+                    audit_path.as_ref().to_string_lossy(),
+                    err
+                ))
+            })?;
+        audit_file
+            .write_all(format!("\n{}", serialized_string).as_bytes())
+            .await
+            .map_err(|err| {
+                anyhow::Error::msg(format!(
+                    "failed to write AuditEntry to {}: {}",
+                    audit_path.as_ref().to_string_lossy(),
+                    err
+                ))
+            })?;
+        Ok(())
+    }
+
+    /// Last `count` entries, oldest first. Empty if there is no audit trail yet
+    pub async fn load_recent<T: AsRef<Path>>(
+        audit_path: &T,
+        count: usize,
+    ) -> anyhow::Result<Vec<Self>> {
+        let audit_string = match tokio::fs::read_to_string(audit_path).await {
+            Ok(audit_string) => audit_string,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(anyhow::Error::msg(format!(
+                    "Failed to open {}: {}",
+                    audit_path.as_ref().to_string_lossy(),
+                    err
+                )));
+            }
+        };
+        let mut entries = toml::from_str::<AuditLog>(&audit_string)
+            .map_err(|err| {
+                anyhow::Error::msg(format!(
+                    "Malformed audit trail in {}: {}",
+                    audit_path.as_ref().to_string_lossy(),
+                    err
+                ))
+            })?
+            .entries;
+        let skipped = entries.len().saturating_sub(count);
+        Ok(entries.split_off(skipped))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use poise::serenity_prelude::{GuildId, Timestamp, UserId};
+
+    use crate::audit::AuditEntry;
+
+    #[tokio::test]
+    async fn appended_entries_are_loaded_in_order() {
+        let audit_path = std::env::temp_dir().join(format!(
+            "discord_watchdog_audit_{}.toml",
+            std::process::id()
+        ));
+        let _ = tokio::fs::remove_file(&audit_path).await;
+
+        for (user, command) in [(1, "config name"), (2, "config role"), (3, "server limit")] {
+            AuditEntry {
+                user_id: UserId::new(user),
+                command: command.to_string(),
+                guild: Some(GuildId::new(10)),
+                args: String::new(),
+                timestamp: Timestamp::now(),
+            }
+            .append_to_file(&audit_path)
+            .await
+            .unwrap();
+        }
+        let entries = AuditEntry::load_recent(&audit_path, 2).await.unwrap();
+        tokio::fs::remove_file(&audit_path).await.unwrap();
+
+        let commands: Vec<&str> = entries.iter().map(|entry| entry.command.as_str()).collect();
+        assert_eq!(commands, vec!["config role", "server limit"]);
+    }
+}
@@ -1,16 +1,23 @@
 mod config;
 mod debug;
 mod server;
+mod status;
 
-use std::collections::btree_map::{Entry, OccupiedEntry, VacantEntry};
+use std::{
+    any::Any,
+    collections::btree_map::{Entry, OccupiedEntry, VacantEntry},
+    time::Instant,
+};
 
 use poise::{
     CreateReply, send_reply,
-    serenity_prelude::{CreateAttachment, CreateEmbed, GuildId},
+    serenity_prelude::{CreateAttachment, CreateEmbed, GuildId, Member, ResolvedValue, Timestamp},
 };
-use tokio::sync::RwLockWriteGuard;
+use tokio::sync::{Mutex, RwLockWriteGuard};
 
-use crate::{Config, Context, Data, Error, ServerConfig};
+use crate::{
+    Config, Context, DEFAULT_AUDIT_PATH, Data, Error, ServerConfig, UndoEntry, audit::AuditEntry,
+};
 
 pub fn get_commands() -> Vec<poise::Command<Data, Error>> {
     vec![
@@ -18,6 +25,7 @@ pub fn get_commands() -> Vec<poise::Command<Data, Error>> {
         debug::info(),
         debug::debug(),
         server::server(),
+        status::status(),
     ]
 }
 
@@ -31,6 +39,249 @@ async fn master_check(ctx: Context<'_>) -> bool {
     }
 }
 
+/// Stops commands, which need a server, when they are used in DMs
+async fn guild_check(ctx: Context<'_>) -> Result<bool, Error> {
+    if ctx.guild_id().is_some() {
+        return Ok(true);
+    }
+    simple_reply_text(
+        ctx,
+        true,
+        "This command must be used in a server".to_string(),
+    )
+    .await;
+    Ok(false)
+}
+
+/// Marks the invocation as globally checked in its invocation data
+struct GloballyChecked;
+
+/// Global checks of every command. poise runs them for every parent command and again for the subcommand, all with the
+/// same context, so they are only done on the first pass
+pub async fn command_check(ctx: Context<'_>) -> Result<bool, Error> {
+    if !first_pass(invocation_data(ctx), GloballyChecked).await {
+        return Ok(true);
+    }
+    Ok(dedup_check(ctx).await? && allowlist_check(ctx).await?)
+}
+
+/// Rejects commands from servers, which are not in the guild allowlist
+async fn allowlist_check(ctx: Context<'_>) -> Result<bool, Error> {
+    let Some(server_id) = ctx.guild_id() else {
+        return Ok(true);
+    };
+    if ctx.data().config.read().await.guild_allowed(server_id) {
+        return Ok(true);
+    }
+    log::info!(
+        "[server {}] Rejected command of user {} ({}): server is not in the allowlist",
+        server_id,
+        ctx.author().name,
+        ctx.author().id
+    );
+    simple_reply_text(
+        ctx,
+        true,
+        "This server is not allowed to use the bot. Contact its hoster!".to_string(),
+    )
+    .await;
+    Ok(false)
+}
+
+/// Per-invocation data of the context
+fn invocation_data<'a>(ctx: Context<'a>) -> &'a Mutex<Box<dyn Any + Send + Sync>> {
+    match ctx {
+        Context::Application(ctx) => ctx.invocation_data,
+        Context::Prefix(ctx) => ctx.invocation_data,
+    }
+}
+
+/// Marks the invocation. Returns false, if it was already marked with `T`
+async fn first_pass<T: Any + Send + Sync>(
+    invocation_data: &Mutex<Box<dyn Any + Send + Sync>>,
+    mark: T,
+) -> bool {
+    let mut data_lock = invocation_data.lock().await;
+    if (**data_lock).is::<T>() {
+        return false;
+    }
+    *data_lock = Box::new(mark);
+    true
+}
+
+/// Ignores repeated interactions, so a retried or double-clicked command is not applied twice
+async fn dedup_check(ctx: Context<'_>) -> Result<bool, Error> {
+    if ctx
+        .data()
+        .first_seen_interaction(ctx.id(), Instant::now())
+        .await
+    {
+        return Ok(true);
+    }
+    log::warn!(
+        "Ignored repeated interaction {} of user {} ({})",
+        ctx.id(),
+        ctx.author().name,
+        ctx.author().id
+    );
+    simple_reply_text(ctx, true, "This command was already processed!".to_string()).await;
+    Ok(false)
+}
+
+/// Whether the member has MANAGE_CHANNELS, which is required to change the admin role
+fn can_manage_channels(member: &Member) -> bool {
+    member
+        .permissions
+        .is_some_and(|permissions| permissions.manage_channels() || permissions.administrator())
+}
+
+/// Whether the member has MANAGE_CHANNELS or the server's admin role
+async fn is_admin(ctx: Context<'_>, server_id: GuildId, member: &Member) -> bool {
+    if can_manage_channels(member) {
+        return true;
+    }
+    let admin_role = ctx
+        .data()
+        .config
+        .read()
+        .await
+        .server_configs
+        .get(&server_id)
+        .and_then(|server_config| server_config.admin_role);
+    admin_role.is_some_and(|role| member.roles.contains(&role))
+}
+
+/// Lets members with MANAGE_CHANNELS or the server's admin role change configuration
+async fn admin_check(ctx: Context<'_>) -> Result<bool, Error> {
+    // Outside of a server guild_check has already replied
+    let Some(server_id) = ctx.guild_id() else {
+        return Ok(true);
+    };
+    let Some(member) = ctx.author_member().await else {
+        return Ok(false);
+    };
+    if is_admin(ctx, server_id, &member).await {
+        return Ok(true);
+    }
+    simple_reply_text(
+        ctx,
+        true,
+        "You need MANAGE_CHANNELS permission or the admin role to use this command!".to_string(),
+    )
+    .await;
+    Ok(false)
+}
+
+/// Lets members with MANAGE_CHANNELS, the server's admin role or observer role run read-only commands
+async fn observer_check(ctx: Context<'_>) -> Result<bool, Error> {
+    // Outside of a server guild_check has already replied
+    let Some(server_id) = ctx.guild_id() else {
+        return Ok(true);
+    };
+    let Some(member) = ctx.author_member().await else {
+        return Ok(false);
+    };
+    if is_admin(ctx, server_id, &member).await {
+        return Ok(true);
+    }
+    let observer_role = ctx
+        .data()
+        .config
+        .read()
+        .await
+        .server_configs
+        .get(&server_id)
+        .and_then(|server_config| server_config.observer_role);
+    if observer_role.is_some_and(|role| member.roles.contains(&role)) {
+        return Ok(true);
+    }
+    simple_reply_text(
+        ctx,
+        true,
+        "You need MANAGE_CHANNELS permission, the admin role or the observer role to use this command!"
+            .to_string(),
+    )
+    .await;
+    Ok(false)
+}
+
+/// Remembers master-owned settings before a master command changes them, so they can be reverted with */config undo*
+async fn remember_config(ctx: Context<'_>, config: &Config) {
+    remember_config_and_servers(ctx, config, []).await;
+}
+
+/// Remembers master-owned settings along with the servers, which a master command registers or removes
+async fn remember_config_and_servers(
+    ctx: Context<'_>,
+    config: &Config,
+    server_ids: impl IntoIterator<Item = GuildId>,
+) {
+    if let Some(server_id) = ctx.guild_id() {
+        let servers = server_ids
+            .into_iter()
+            .map(|id| (id, config.server_configs.get(&id).cloned()))
+            .collect();
+        ctx.data()
+            .push_undo(
+                server_id,
+                UndoEntry::Global {
+                    config: config.master_settings(),
+                    servers,
+                },
+            )
+            .await;
+    }
+}
+
+/// Remembers the server's configuration before it is changed, so it can be reverted with */config undo*
+async fn remember_server_config(ctx: Context<'_>, server_config: Option<ServerConfig>) {
+    if let Some(server_id) = ctx.guild_id() {
+        ctx.data()
+            .push_undo(server_id, UndoEntry::Server(server_config))
+            .await;
+    }
+}
+
+/// Records the command with its arguments into the audit trail
+async fn audit(ctx: Context<'_>) {
+    let args = match ctx {
+        Context::Application(ctx) => ctx
+            .args
+            .iter()
+            .map(|option| {
+                let value = match &option.value {
+                    ResolvedValue::Boolean(value) => value.to_string(),
+                    ResolvedValue::Integer(value) => value.to_string(),
+                    ResolvedValue::Number(value) => value.to_string(),
+                    ResolvedValue::String(value) => value.to_string(),
+                    ResolvedValue::Channel(channel) => channel.id.to_string(),
+                    ResolvedValue::Role(role) => role.id.to_string(),
+                    ResolvedValue::User(user, _) => user.id.to_string(),
+                    _ => "?".to_string(),
+                };
+                format!("{}={}", option.name, value)
+            })
+            .collect::<Vec<_>>()
+            .join(" "),
+        Context::Prefix(ctx) => ctx.args.to_string(),
+    };
+    audit_with_args(ctx, args).await;
+}
+
+/// Records the command into the audit trail with arguments, which were not passed as command options
+async fn audit_with_args(ctx: Context<'_>, args: String) {
+    let entry = AuditEntry {
+        user_id: ctx.author().id,
+        command: ctx.command().qualified_name.clone(),
+        guild: ctx.guild_id(),
+        args,
+        timestamp: Timestamp::now(),
+    };
+    if let Err(err) = entry.append_to_file(&DEFAULT_AUDIT_PATH).await {
+        log::error!("Failed to write audit entry {:?}: {}", entry, err);
+    }
+}
+
 async fn simple_reply_text(ctx: Context<'_>, ephemeral: bool, text: String) {
     let server_string = match ctx.guild() {
         Some(server) => {
@@ -133,3 +384,18 @@ fn get_server_config_vacant_entry<'a>(
         )),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn subcommand_is_checked_once() {
+        let invocation_data: Mutex<Box<dyn Any + Send + Sync>> = Mutex::new(Box::new(()));
+        // /config name: checked for config, then for name
+        assert!(first_pass(&invocation_data, GloballyChecked).await);
+        assert!(!first_pass(&invocation_data, GloballyChecked).await);
+        let repeated_invocation: Mutex<Box<dyn Any + Send + Sync>> = Mutex::new(Box::new(()));
+        assert!(first_pass(&repeated_invocation, GloballyChecked).await);
+    }
+}
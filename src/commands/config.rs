@@ -1,35 +1,210 @@
-use std::time::Duration;
+use std::{collections::BTreeMap, num::NonZeroU8, time::Duration};
 
-use poise::serenity_prelude::{Channel, Role};
+use poise::{
+    ChoiceParameter, CreateReply,
+    serenity_prelude::{
+        ButtonStyle, Channel, ComponentInteractionCollector, CreateActionRow, CreateButton,
+        CreateEmbed, CreateInteractionResponse, CreateMessage, ReactionType, Role, Timestamp,
+    },
+};
 
-use super::master_check;
+use super::{admin_check, can_manage_channels, guild_check, master_check, observer_check};
 use crate::{
-    Config, Context, DEFAULT_CONFIG_PATH, Error,
-    commands::{get_server_config_entry, simple_reply_text},
-    ping::resolve_ip,
+    ActiveHours, CheckKind, Config, ConfigSnapshot, Context, DEFAULT_CONFIG_PATH, Error,
+    MAX_CONFIG_SNAPSHOTS, MaintenanceWindow, PingConfig, ServerConfig, TransitionKind, UndoEntry,
+    commands::{
+        audit, get_server_config_entry, remember_config, remember_config_and_servers,
+        remember_server_config, simple_reply_embed, simple_reply_text,
+    },
+    events::{DISCORD_MESSAGE_LIMIT, EVENTS_TARGET},
+    ping::Health,
     save_data,
+    status::{
+        DEFAULT_ADDRESS_LABEL, DEFAULT_LATENCY_LABEL, DEFAULT_SINCE_LABEL,
+        worst_case_message_length,
+    },
 };
 
+/// Most messages, which are listed by */config validate-messages*, so its reply fits into one message
+const MAX_LISTED_LONG_MESSAGES: usize = 20;
+
+/// How long the user has to confirm an address, which doesn't respond
+const ADDRESS_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long the message editor waits to be submitted
+const MESSAGE_MODAL_TIMEOUT: Duration = Duration::from_secs(600);
+
+#[derive(Debug, poise::Modal)]
+#[name = "Edit message"]
+struct MessageModal {
+    #[name = "Message"]
+    #[placeholder = "Use %%RESOURCE%%, %%ROLE%% and %%DOWNTIME%% template variables"]
+    #[paragraph]
+    #[min_length = 1]
+    #[max_length = 300]
+    message: String,
+}
+
 #[derive(poise::ChoiceParameter, Debug, Clone, Copy)]
 enum Status {
     Up,
     Down,
 }
 
+#[derive(poise::ChoiceParameter, Debug, Clone, Copy)]
+enum ServerField {
+    #[name = "Up message"]
+    UpMessage,
+    #[name = "Down message"]
+    DownMessage,
+    #[name = "Maintenance start message"]
+    MaintenanceStartMessage,
+    #[name = "Maintenance end message"]
+    MaintenanceEndMessage,
+    #[name = "Ping on up"]
+    PingOnUp,
+    #[name = "Ping on down"]
+    PingOnDown,
+    #[name = "Mention cooldown"]
+    MentionCooldown,
+    #[name = "Up reactions"]
+    UpReactions,
+    #[name = "Down reactions"]
+    DownReactions,
+    #[name = "Detect edits"]
+    DetectEdits,
+    #[name = "Pin status message"]
+    PinStatusMessage,
+    #[name = "Clean up messages"]
+    CleanupMessages,
+    #[name = "Embed enabled"]
+    EmbedEnabled,
+    #[name = "Embed labels"]
+    EmbedLabels,
+    #[name = "Observer role"]
+    ObserverRole,
+    Inverted,
+}
+
+#[derive(poise::ChoiceParameter, Debug, Clone, Copy)]
+enum PingField {
+    Name,
+    Address,
+    #[name = "Extra probes"]
+    ExtraProbes,
+    Quorum,
+    #[name = "Check kind"]
+    CheckKind,
+    Attempts,
+    Timeout,
+    #[name = "DNS timeout"]
+    DnsTimeout,
+    #[name = "DNS slow threshold"]
+    DnsSlowThreshold,
+    Interval,
+    #[name = "Check retries"]
+    CheckRetries,
+    #[name = "Min stable duration"]
+    MinStableDuration,
+    #[name = "Down check spacing"]
+    DownCheckSpacing,
+    #[name = "Self restart after errors"]
+    SelfRestartAfterErrors,
+    #[name = "Check concurrency"]
+    CheckConcurrency,
+    #[name = "Max outstanding checks"]
+    MaxOutstandingChecks,
+    #[name = "Warmup ping"]
+    WarmupPing,
+    #[name = "Dual stack"]
+    DualStack,
+    #[name = "Resolve SRV"]
+    ResolveSrv,
+    #[name = "Active hours"]
+    ActiveHours,
+    #[name = "RTT smoothing"]
+    RttSmoothing,
+}
+
+#[derive(poise::ChoiceParameter, Debug, Clone, Copy, PartialEq)]
+enum CheckKindChoice {
+    #[name = "ICMP ping"]
+    Icmp,
+    #[name = "TCP connection"]
+    Tcp,
+    #[name = "ICMP ping and TCP connection"]
+    IcmpAndTcp,
+    #[name = "HTTP request"]
+    Http,
+    #[name = "HTTPS request"]
+    Https,
+}
+
 /// Base config command. Can not be called directly.
+///
+/// Changing configuration requires MANAGE_CHANNELS or the admin role, while viewing it is also allowed to the observer
+/// role. Discord only shows the command to the roles without MANAGE_CHANNELS, if the server allows them to use it.
 #[poise::command(
     slash_command,
     default_member_permissions = "MANAGE_CHANNELS",
     subcommands(
-        "reset", "name", "address", "channel", "role", "interval", "timeout", "attempts", "message"
-    )
+        "reset",
+        "default_field",
+        "default_ping",
+        "name",
+        "address",
+        "quorum",
+        "channel",
+        "role",
+        "interval",
+        "timeout",
+        "attempts",
+        "check_retries",
+        "down_spacing",
+        "check_concurrency",
+        "max_outstanding_checks",
+        "dns_slow_threshold",
+        "warmup_ping",
+        "dual_stack",
+        "resolve_srv",
+        "check_kind",
+        "port",
+        "active_hours",
+        "rtt_smoothing",
+        "maintenance",
+        "log_channel",
+        "unknown_to_master",
+        "startup_grace",
+        "unknown_reminder",
+        "validate_messages",
+        "verbose_confirmation",
+        "message",
+        "undo",
+        "snapshot",
+        "snapshots",
+        "restore",
+        "ping_on",
+        "mention_cooldown",
+        "reactions",
+        "detect_edits",
+        "pin",
+        "cleanup_messages",
+        "invert",
+        "embed_enabled",
+        "embed_labels",
+        "observer_role",
+        "admin_role",
+        "show",
+        "list_defaults"
+    ),
+    check = "guild_check"
 )]
 pub async fn config(_: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
 /// [M ONLY] ALL SERVERS WILL BE RESET!!! Loads all configuration from Config.toml or hardcoded defaults
-#[poise::command(slash_command, guild_cooldown = 60)]
+#[poise::command(slash_command, guild_cooldown = 60, check = "admin_check")]
 async fn reset(ctx: Context<'_>) -> Result<(), Error> {
     if let Err(err) = ctx.defer_ephemeral().await {
         log::error!("Failed to defer ephemeral reply: {}", err);
@@ -45,16 +220,29 @@ async fn reset(ctx: Context<'_>) -> Result<(), Error> {
     }
 
     log::info!(
+        target: EVENTS_TARGET,
         "User {} ({}) reset configuration to defaults",
         ctx.author().name,
         ctx.author().id,
     );
+
+    audit(ctx).await;
     let loaded_config_result = Config::load_from_file(&DEFAULT_CONFIG_PATH).await;
 
     // Success
     if let Ok(Some(config)) = loaded_config_result {
         log::info!("Loaded Config");
-        *ctx.data().config.write().await = config;
+        let mut config_lock = ctx.data().config.write().await;
+        let server_ids: Vec<_> = config_lock
+            .server_configs
+            .keys()
+            .chain(config.server_configs.keys())
+            .copied()
+            .collect();
+        remember_config_and_servers(ctx, &config_lock, server_ids).await;
+        config.warn_about_problems();
+        *config_lock = config;
+        drop(config_lock);
 
         save_data(ctx.data()).await;
 
@@ -86,7 +274,11 @@ async fn reset(ctx: Context<'_>) -> Result<(), Error> {
     }
     // No Config
     log::info!("No Config detected. Default values will be used.");
-    *ctx.data().config.write().await = Config::default();
+    let mut config_lock = ctx.data().config.write().await;
+    let server_ids: Vec<_> = config_lock.server_configs.keys().copied().collect();
+    remember_config_and_servers(ctx, &config_lock, server_ids).await;
+    *config_lock = Config::default();
+    drop(config_lock);
     simple_reply_text(
         ctx,
         true,
@@ -97,6 +289,184 @@ async fn reset(ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
+/// Restores a single setting of this server to its default, leaving the rest intact
+#[poise::command(
+    slash_command,
+    guild_cooldown = 30,
+    rename = "default",
+    check = "admin_check"
+)]
+async fn default_field(
+    ctx: Context<'_>,
+    #[description = "Setting, which will be restored to its default"] field: ServerField,
+) -> Result<(), Error> {
+    let server_string = match ctx.guild() {
+        Some(server) => {
+            format!("{} ({})", server.name, server.id)
+        }
+        None => "UNKNOWN".to_string(),
+    };
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!(
+            "[server {}] Failed to defer ephemeral reply: {}",
+            server_string,
+            err,
+        );
+    };
+    let mut config_lock = ctx.data().config.write().await;
+    let mut entry = match get_server_config_entry(ctx.guild_id(), &mut config_lock) {
+        Ok(entry) => entry,
+        Err(err) => {
+            simple_reply_text(ctx, true, err.to_string()).await;
+            return Ok(());
+        }
+    };
+
+    let mut new_server_config = entry.get().clone();
+    remember_server_config(ctx, Some(entry.get().clone())).await;
+    let default = ServerConfig::default();
+    let mut restore_message = |kind: TransitionKind| {
+        new_server_config
+            .messages
+            .insert(kind, kind.default_message().to_string())
+    };
+    match field {
+        ServerField::UpMessage => {
+            restore_message(TransitionKind::Up);
+        }
+        ServerField::DownMessage => {
+            restore_message(TransitionKind::Down);
+        }
+        ServerField::MaintenanceStartMessage => {
+            restore_message(TransitionKind::MaintenanceStart);
+        }
+        ServerField::MaintenanceEndMessage => {
+            restore_message(TransitionKind::MaintenanceEnd);
+        }
+        ServerField::PingOnUp => new_server_config.ping_on_up = default.ping_on_up,
+        ServerField::PingOnDown => new_server_config.ping_on_down = default.ping_on_down,
+        ServerField::MentionCooldown => {
+            new_server_config.mention_cooldown = default.mention_cooldown
+        }
+        ServerField::UpReactions => new_server_config.up_reactions = default.up_reactions,
+        ServerField::DownReactions => new_server_config.down_reactions = default.down_reactions,
+        ServerField::DetectEdits => {
+            new_server_config.detect_manual_edits = default.detect_manual_edits
+        }
+        ServerField::PinStatusMessage => {
+            new_server_config.pin_status_message = default.pin_status_message
+        }
+        ServerField::CleanupMessages => {
+            new_server_config.cleanup_messages = default.cleanup_messages
+        }
+        ServerField::Inverted => new_server_config.inverted = default.inverted,
+        ServerField::EmbedEnabled => new_server_config.embed_enabled = default.embed_enabled,
+        ServerField::EmbedLabels => {
+            new_server_config.since_label = default.since_label;
+            new_server_config.address_label = default.address_label;
+            new_server_config.latency_label = default.latency_label;
+        }
+        ServerField::ObserverRole => new_server_config.observer_role = default.observer_role,
+    }
+    entry.insert(new_server_config);
+
+    log::info!(
+        target: EVENTS_TARGET,
+        "[server {}] User {} ({}) restored {:?} to default",
+        server_string,
+        ctx.author().name,
+        ctx.author().id,
+        field
+    );
+    audit(ctx).await;
+    simple_reply_text(ctx, true, format!("Restored {} to default!", field.name())).await;
+
+    drop(config_lock);
+
+    save_data(ctx.data()).await;
+
+    Ok(())
+}
+
+/// [M ONLY] Restores a single setting of the ping configuration to its default, leaving the rest intact
+#[poise::command(
+    slash_command,
+    guild_cooldown = 30,
+    rename = "default-ping",
+    check = "admin_check"
+)]
+async fn default_ping(
+    ctx: Context<'_>,
+    #[description = "Setting, which will be restored to its default"] field: PingField,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    let ping_config = &mut config_lock.ping_config;
+    let default = PingConfig::default();
+    match field {
+        PingField::Name => ping_config.resource_name = default.resource_name,
+        PingField::Address => ping_config.resource_addr = default.resource_addr,
+        PingField::ExtraProbes => ping_config.reset_extra_probes(),
+        PingField::Quorum => ping_config.quorum = default.quorum,
+        PingField::CheckKind => ping_config.check_kind = default.check_kind,
+        PingField::Attempts => {
+            ping_config.required_attempts_before_notification =
+                default.required_attempts_before_notification
+        }
+        PingField::Timeout => ping_config.timeout = default.timeout,
+        PingField::DnsTimeout => ping_config.dns_timeout = default.dns_timeout,
+        PingField::DnsSlowThreshold => ping_config.dns_slow_threshold = default.dns_slow_threshold,
+        PingField::Interval => {
+            ping_config.interval_between_attempts = default.interval_between_attempts
+        }
+        PingField::CheckRetries => ping_config.check_retries = default.check_retries,
+        PingField::MinStableDuration => {
+            ping_config.min_stable_duration = default.min_stable_duration
+        }
+        PingField::DownCheckSpacing => ping_config.down_check_spacing = default.down_check_spacing,
+        PingField::SelfRestartAfterErrors => {
+            ping_config.self_restart_after_errors = default.self_restart_after_errors;
+            ping_config.self_restart_notify_master = default.self_restart_notify_master;
+        }
+        PingField::CheckConcurrency => ping_config.check_concurrency = default.check_concurrency,
+        PingField::MaxOutstandingChecks => {
+            ping_config.max_outstanding_checks = default.max_outstanding_checks
+        }
+        PingField::WarmupPing => ping_config.warmup_ping = default.warmup_ping,
+        PingField::DualStack => ping_config.dual_stack = default.dual_stack,
+        PingField::ResolveSrv => ping_config.resolve_srv = default.resolve_srv,
+        PingField::ActiveHours => ping_config.active_hours = default.active_hours,
+        PingField::RttSmoothing => ping_config.rtt_smoothing = default.rtt_smoothing,
+    }
+    drop(config_lock);
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) restored {:?} to default",
+        ctx.author().name,
+        ctx.author().id,
+        field
+    );
+    audit(ctx).await;
+    save_data(ctx.data()).await;
+
+    simple_reply_text(ctx, true, format!("Restored {} to default!", field.name())).await;
+
+    Ok(())
+}
+
 //
 //
 //
@@ -106,7 +476,7 @@ async fn reset(ctx: Context<'_>) -> Result<(), Error> {
 //
 
 /// [M ONLY] Changes resource address, which is monitored by the bot
-#[poise::command(slash_command, guild_cooldown = 20)]
+#[poise::command(slash_command, guild_cooldown = 20, check = "admin_check")]
 async fn name(
     ctx: Context<'_>,
     #[description = "Name of the resource. It is used in embeds and messages"]
@@ -126,13 +496,18 @@ async fn name(
         return Ok(());
     }
 
-    ctx.data().config.write().await.ping_config.resource_name = name.clone();
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.ping_config.resource_name = name.clone();
+    drop(config_lock);
     log::info!(
+        target: EVENTS_TARGET,
         "User {} ({}) changed resource name to {}",
         ctx.author().name,
         ctx.author().id,
         name
     );
+    audit(ctx).await;
 
     save_data(ctx.data()).await;
 
@@ -142,13 +517,15 @@ async fn name(
 }
 
 /// [M ONLY] Changes resource address, which is monitored by the bot
-#[poise::command(slash_command, guild_cooldown = 20)]
+#[poise::command(slash_command, guild_cooldown = 20, check = "admin_check")]
 async fn address(
     ctx: Context<'_>,
     #[description = "Resource address, which will be pinged"]
-    #[max_length = 45]
+    #[max_length = 253]
     #[min_length = 1]
     addr: String,
+    #[description = "Also check that the address responds before changing it. Only DNS is checked, if empty"]
+    full_check: Option<bool>,
 ) -> Result<(), Error> {
     if let Err(err) = ctx.defer_ephemeral().await {
         log::error!("Failed to defer ephemeral reply: {}", err);
@@ -163,18 +540,48 @@ async fn address(
         return Ok(());
     }
 
-    if let Err(err) = resolve_ip(&addr).await {
+    let ping_config = ctx.data().config.read().await.ping_config.clone();
+    if let Err(err) = ctx
+        .data()
+        .ping_client
+        .resolve_addr(&addr, &ping_config)
+        .await
+    {
         simple_reply_text(ctx, true, format!("Failed to resolve your addr: {}", err)).await;
         return Ok(());
     }
+    if full_check.unwrap_or(false) {
+        let problem = match ctx.data().ping_client.check_addr(&addr, &ping_config).await {
+            Ok(Health::Up { .. }) => None,
+            Ok(Health::Down) => Some(format!("`{}` resolves, but doesn't respond", addr)),
+            Ok(Health::ServiceDown) => Some(format!(
+                "`{}` responds to ping, but its service doesn't accept connections",
+                addr
+            )),
+            Err(err) => Some(format!(
+                "`{}` resolves, but failed to be checked: {}",
+                addr, err
+            )),
+        };
+        if let Some(problem) = problem
+            && !confirm_address(ctx, &problem).await
+        {
+            return Ok(());
+        }
+    }
 
-    ctx.data().config.write().await.ping_config.resource_addr = addr.clone();
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.ping_config.resource_addr = addr.clone();
+    drop(config_lock);
     log::info!(
+        target: EVENTS_TARGET,
         "User {} ({}) changed resource address to {}",
         ctx.author().name,
         ctx.author().id,
         addr
     );
+    audit(ctx).await;
 
     save_data(ctx.data()).await;
 
@@ -183,8 +590,147 @@ async fn address(
     Ok(())
 }
 
+/// Asks the user, whether the address should be changed despite the failed check
+async fn confirm_address(ctx: Context<'_>, problem: &str) -> bool {
+    let confirm_id = format!("{}-address-confirm", ctx.id());
+    let cancel_id = format!("{}-address-cancel", ctx.id());
+    let reply = CreateReply::default()
+        .ephemeral(true)
+        .content(format!("{}. Change the address anyway?", problem))
+        .components(vec![CreateActionRow::Buttons(vec![
+            CreateButton::new(&confirm_id)
+                .label("Change anyway")
+                .style(ButtonStyle::Danger),
+            CreateButton::new(&cancel_id)
+                .label("Cancel")
+                .style(ButtonStyle::Secondary),
+        ])]);
+    let reply_handle = match ctx.send(reply).await {
+        Ok(handle) => handle,
+        Err(err) => {
+            log::error!("Failed to send address confirmation: {}", err);
+            return false;
+        }
+    };
+
+    let interaction = ComponentInteractionCollector::new(ctx)
+        .author_id(ctx.author().id)
+        .custom_ids(vec![confirm_id.clone(), cancel_id])
+        .timeout(ADDRESS_CONFIRMATION_TIMEOUT)
+        .await;
+    let confirmed = interaction
+        .as_ref()
+        .is_some_and(|interaction| interaction.data.custom_id == confirm_id);
+    if let Some(interaction) = interaction
+        && let Err(err) = interaction
+            .create_response(ctx, CreateInteractionResponse::Acknowledge)
+            .await
+    {
+        log::error!("Failed to acknowledge address confirmation: {}", err);
+    }
+    let text = if confirmed {
+        format!("{}. Changing the address anyway", problem)
+    } else {
+        format!("{}. Address was not changed", problem)
+    };
+    if let Err(err) = reply_handle
+        .edit(ctx, CreateReply::default().content(text).components(vec![]))
+        .await
+    {
+        log::error!("Failed to finish address confirmation: {}", err);
+    }
+    confirmed
+}
+
+/// [M ONLY] Changes extra probes and how many failed probes make the resource down
+#[poise::command(slash_command, guild_cooldown = 20, check = "admin_check")]
+async fn quorum(
+    ctx: Context<'_>,
+    #[description = "How many probes must fail for the resource to be down. Leave empty for majority"]
+    #[min = 1]
+    failing: Option<u8>,
+    #[description = "Space-separated addresses, checked along with the resource. Leave empty to keep current"]
+    probes: Option<String>,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let ping_config = ctx.data().config.read().await.ping_config.clone();
+    let extra_probe_addrs: Vec<String> = match probes {
+        Some(probes) => probes.split_whitespace().map(str::to_string).collect(),
+        None => ping_config.extra_probe_addrs.clone(),
+    };
+    for addr in &extra_probe_addrs {
+        if let Err(err) = ctx
+            .data()
+            .ping_client
+            .resolve_addr(addr, &ping_config)
+            .await
+        {
+            simple_reply_text(ctx, true, format!("Failed to resolve {}: {}", addr, err)).await;
+            return Ok(());
+        }
+    }
+    let probe_count = extra_probe_addrs.len() + 1;
+    if let Some(failing) = failing
+        .map(usize::from)
+        .filter(|failing| *failing > probe_count)
+    {
+        simple_reply_text(
+            ctx,
+            true,
+            format!(
+                "Resource can't be down, if {} of {} probes must fail!",
+                failing, probe_count
+            ),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.ping_config.extra_probe_addrs = extra_probe_addrs.clone();
+    config_lock.ping_config.quorum = failing;
+    drop(config_lock);
+    let quorum_string = match failing {
+        Some(failing) => format!("{} of {} probes", failing, probe_count),
+        None => format!("majority of {} probes", probe_count),
+    };
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) changed quorum to {}, extra probes: {:?}",
+        ctx.author().name,
+        ctx.author().id,
+        quorum_string,
+        extra_probe_addrs
+    );
+    audit(ctx).await;
+
+    save_data(ctx.data()).await;
+
+    simple_reply_text(
+        ctx,
+        true,
+        format!("Resource is now down, when {} fail!", quorum_string),
+    )
+    .await;
+
+    Ok(())
+}
+
 /// [M ONLY] Changes interval between ping attempts
-#[poise::command(slash_command, guild_cooldown = 20)]
+#[poise::command(slash_command, guild_cooldown = 20, check = "admin_check")]
 async fn interval(
     ctx: Context<'_>,
     #[description = "New interval between ping attempts in seconds"]
@@ -206,18 +752,18 @@ async fn interval(
         return Ok(());
     }
 
-    ctx.data()
-        .config
-        .write()
-        .await
-        .ping_config
-        .interval_between_attempts = Duration::from_secs(interval);
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.ping_config.interval_between_attempts = Duration::from_secs(interval);
+    drop(config_lock);
     log::info!(
+        target: EVENTS_TARGET,
         "User {} ({}) changed interval between ping attempts to {} seconds",
         ctx.author().name,
         ctx.author().id,
         interval
     );
+    audit(ctx).await;
 
     save_data(ctx.data()).await;
 
@@ -232,7 +778,7 @@ async fn interval(
 }
 
 /// [M ONLY] Changes timeout of one ping attempt
-#[poise::command(slash_command, guild_cooldown = 20)]
+#[poise::command(slash_command, guild_cooldown = 20, check = "admin_check")]
 async fn timeout(
     ctx: Context<'_>,
     #[description = "New timeout in seconds"]
@@ -254,13 +800,18 @@ async fn timeout(
         return Ok(());
     }
 
-    ctx.data().config.write().await.ping_config.timeout = Duration::from_secs(timeout);
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.ping_config.timeout = Duration::from_secs(timeout);
+    drop(config_lock);
     log::info!(
+        target: EVENTS_TARGET,
         "User {} ({}) changed ping timeout to {} seconds",
         ctx.author().name,
         ctx.author().id,
         timeout
     );
+    audit(ctx).await;
 
     save_data(ctx.data()).await;
 
@@ -270,7 +821,7 @@ async fn timeout(
 }
 
 /// [M ONLY] Changes required amount of consecutive attempts, required for resource to change its state
-#[poise::command(slash_command, guild_cooldown = 20)]
+#[poise::command(slash_command, guild_cooldown = 20, check = "admin_check")]
 async fn attempts(
     ctx: Context<'_>,
     #[description = "Resource's status is up && This value is 3 && Ping failed 3 times -> Status changes to down"]
@@ -292,18 +843,20 @@ async fn attempts(
         return Ok(());
     }
 
-    ctx.data()
-        .config
-        .write()
-        .await
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock
         .ping_config
         .required_attempts_before_notification = attempts;
+    drop(config_lock);
     log::info!(
+        target: EVENTS_TARGET,
         "User {} ({}) changed required attempts to {}",
         ctx.author().name,
         ctx.author().id,
         attempts
     );
+    audit(ctx).await;
     save_data(ctx.data()).await;
 
     simple_reply_text(
@@ -316,87 +869,2178 @@ async fn attempts(
     Ok(())
 }
 
-//
-//
-//
-// server CONFIGURATION
-//
-//
-//
-
-/// Changes channel, where bot will send any updates
-#[poise::command(slash_command, guild_cooldown = 30)]
-async fn channel(
+/// [M ONLY] Changes how many times a failed check is retried within one attempt
+#[poise::command(
+    slash_command,
+    guild_cooldown = 20,
+    rename = "check-retries",
+    check = "admin_check"
+)]
+async fn check_retries(
     ctx: Context<'_>,
-    #[description = "New channel for updates"] channel: Channel,
+    #[description = "Retries share the ping timeout, so a single lost packet doesn't count as a failed attempt"]
+    #[min = 0]
+    #[max = 5]
+    retries: u8,
 ) -> Result<(), Error> {
-    let server_string = match ctx.guild() {
-        Some(server) => {
-            format!("{} ({})", server.name, server.id)
-        }
-        None => "UNKNOWN".to_string(),
-    };
     if let Err(err) = ctx.defer_ephemeral().await {
-        log::error!(
-            "[server {}] Failed to defer ephemeral reply: {}",
-            server_string,
-            err,
-        );
-    };
-    let mut config_lock = ctx.data().config.write().await;
-    let mut entry = match get_server_config_entry(ctx.guild_id(), &mut config_lock) {
-        Ok(entry) => entry,
-        Err(err) => {
-            simple_reply_text(ctx, true, err.to_string()).await;
-            return Ok(());
-        }
+        log::error!("Failed to defer ephemeral reply: {}", err);
     };
-    if channel.clone().category().is_some() {
+    if !master_check(ctx).await {
         simple_reply_text(
             ctx,
             true,
-            format!(
-                "<#{}> is an invalid channel for healthcheck updates!",
-                channel.id()
-            ),
+            "This command can only be executed in the Master server (bot's host)".to_string(),
         )
         .await;
         return Ok(());
     }
 
-    let mut new_server_config = entry.get().clone();
-    new_server_config.channel = Some(channel.id());
-    entry.insert(new_server_config);
-
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.ping_config.check_retries = retries;
+    drop(config_lock);
     log::info!(
-        "[server {}] User {} ({}) changed channel to {} ({})",
-        server_string,
+        target: EVENTS_TARGET,
+        "User {} ({}) changed check retries to {}",
         ctx.author().name,
         ctx.author().id,
-        channel,
-        channel.id()
+        retries
     );
-    simple_reply_text(
-        ctx,
-        true,
-        format!("Changed channel to <#{}>!", channel.id()),
-    )
-    .await;
-
-    drop(config_lock);
-
+    audit(ctx).await;
     save_data(ctx.data()).await;
 
+    simple_reply_text(ctx, true, format!("Changed check retries to {}!", retries)).await;
+
     Ok(())
 }
 
-/// Changes role, which will be pinged by the bot when resource is up
-#[poise::command(slash_command, guild_cooldown = 30)]
-async fn role(
+/// [M ONLY] Changes minimal time between failed checks, which count towards confirming an outage
+#[poise::command(
+    slash_command,
+    guild_cooldown = 30,
+    rename = "down-spacing",
+    check = "admin_check"
+)]
+async fn down_spacing(
     ctx: Context<'_>,
-    #[description = "New role for notifications"] role: Role,
+    #[description = "Spacing, e.g. \"30s\". Failed checks closer to each other count once. \"0s\" counts all"]
+    spacing: String,
 ) -> Result<(), Error> {
-    let server_string = match ctx.guild() {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+    let spacing = match humantime::parse_duration(&spacing) {
+        Ok(spacing) => spacing,
+        Err(err) => {
+            simple_reply_text(ctx, true, format!("Failed to parse {}: {}", spacing, err)).await;
+            return Ok(());
+        }
+    };
+
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.ping_config.down_check_spacing = spacing;
+    drop(config_lock);
+    let spacing = humantime::format_duration(spacing);
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) changed down check spacing to {}",
+        ctx.author().name,
+        ctx.author().id,
+        spacing
+    );
+    audit(ctx).await;
+    save_data(ctx.data()).await;
+
+    simple_reply_text(
+        ctx,
+        true,
+        format!("Changed down check spacing to {}!", spacing),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// [M ONLY] Changes how long DNS resolution may take before it is reported as slow. Empty disables it
+#[poise::command(
+    slash_command,
+    guild_cooldown = 30,
+    rename = "dns-slow-threshold",
+    check = "admin_check"
+)]
+async fn dns_slow_threshold(
+    ctx: Context<'_>,
+    #[description = "Threshold, e.g. \"500ms\". Slow DNS is an early warning of resolver degradation"]
+    threshold: Option<String>,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+    let threshold = match threshold {
+        Some(threshold) => match humantime::parse_duration(&threshold) {
+            Ok(threshold) => Some(threshold),
+            Err(err) => {
+                simple_reply_text(ctx, true, format!("Failed to parse {}: {}", threshold, err))
+                    .await;
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.ping_config.dns_slow_threshold = threshold;
+    drop(config_lock);
+    let threshold = threshold.map_or("disabled".to_string(), |threshold| {
+        humantime::format_duration(threshold).to_string()
+    });
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) changed DNS slow threshold to {}",
+        ctx.author().name,
+        ctx.author().id,
+        threshold
+    );
+    audit(ctx).await;
+    save_data(ctx.data()).await;
+
+    simple_reply_text(
+        ctx,
+        true,
+        format!("Changed DNS slow threshold to {}!", threshold),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// [M ONLY] Changes whether a throwaway probe is sent before the counted one
+#[poise::command(
+    slash_command,
+    guild_cooldown = 30,
+    rename = "warmup-ping",
+    check = "admin_check"
+)]
+async fn warmup_ping(
+    ctx: Context<'_>,
+    #[description = "Helps when middleboxes drop the first packet after idle. Waits for 1/10 of the timeout"]
+    enabled: bool,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.ping_config.warmup_ping = enabled;
+    drop(config_lock);
+    let state = if enabled { "enabled" } else { "disabled" };
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) {} warmup ping",
+        ctx.author().name,
+        ctx.author().id,
+        state
+    );
+    audit(ctx).await;
+    save_data(ctx.data()).await;
+
+    simple_reply_text(ctx, true, format!("Warmup ping is {}!", state)).await;
+
+    Ok(())
+}
+
+/// [M ONLY] Changes whether both IPv4 and IPv6 addresses of the resource are checked
+#[poise::command(
+    slash_command,
+    guild_cooldown = 30,
+    rename = "dual-stack",
+    check = "admin_check"
+)]
+async fn dual_stack(
+    ctx: Context<'_>,
+    #[description = "The resource is up if either family works, but the broken one is reported"]
+    enabled: bool,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.ping_config.dual_stack = enabled;
+    drop(config_lock);
+    let state = if enabled { "enabled" } else { "disabled" };
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) {} dual stack checks",
+        ctx.author().name,
+        ctx.author().id,
+        state
+    );
+    audit(ctx).await;
+    save_data(ctx.data()).await;
+
+    simple_reply_text(ctx, true, format!("Dual stack checks are {}!", state)).await;
+
+    Ok(())
+}
+
+/// [M ONLY] Changes whether probed addresses are looked up as SRV records
+#[poise::command(
+    slash_command,
+    guild_cooldown = 30,
+    rename = "resolve-srv",
+    check = "admin_check"
+)]
+async fn resolve_srv(
+    ctx: Context<'_>,
+    #[description = "Addresses like _minecraft._tcp.example.com. Checks with ports use the advertised one"]
+    enabled: bool,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.ping_config.resolve_srv = enabled;
+    drop(config_lock);
+    let state = if enabled { "enabled" } else { "disabled" };
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) {} SRV resolution",
+        ctx.author().name,
+        ctx.author().id,
+        state
+    );
+    audit(ctx).await;
+    save_data(ctx.data()).await;
+
+    simple_reply_text(ctx, true, format!("SRV resolution is {}!", state)).await;
+
+    Ok(())
+}
+
+/// [M ONLY] Changes how the resource and extra probes are checked
+#[poise::command(
+    slash_command,
+    guild_cooldown = 30,
+    rename = "checkkind",
+    check = "admin_check"
+)]
+async fn check_kind(
+    ctx: Context<'_>,
+    #[description = "Use HTTP(S), if ICMP is blocked. 2xx and 3xx responses count as up"]
+    kind: CheckKindChoice,
+    #[description = "Port of the service. Required for TCP, scheme's default for HTTP(S), if empty"]
+    #[min = 1]
+    port: Option<u16>,
+    #[description = "Requested path of HTTP(S) checks, e.g. /health. Root, if empty"]
+    #[max_length = 256]
+    path: Option<String>,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+    let check_kind = match kind {
+        CheckKindChoice::Icmp => CheckKind::Icmp,
+        CheckKindChoice::Tcp | CheckKindChoice::IcmpAndTcp => match port {
+            Some(port) if kind == CheckKindChoice::Tcp => CheckKind::Tcp { port },
+            Some(port) => CheckKind::IcmpAndTcp { port },
+            None => {
+                simple_reply_text(
+                    ctx,
+                    true,
+                    "You need to provide the port, which TCP connections are made to!".to_string(),
+                )
+                .await;
+                return Ok(());
+            }
+        },
+        CheckKindChoice::Http | CheckKindChoice::Https => CheckKind::Http {
+            https: kind == CheckKindChoice::Https,
+            port,
+            path: path.unwrap_or_default(),
+        },
+    };
+
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.ping_config.check_kind = check_kind.clone();
+    drop(config_lock);
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) changed check kind to {:?}",
+        ctx.author().name,
+        ctx.author().id,
+        check_kind
+    );
+    audit(ctx).await;
+    save_data(ctx.data()).await;
+
+    simple_reply_text(ctx, true, format!("Changed check kind to {}!", kind.name())).await;
+
+    Ok(())
+}
+
+/// [M ONLY] Changes the port, which is checked. Empty goes back to ICMP ping from TCP connections
+#[poise::command(slash_command, guild_cooldown = 30, check = "admin_check")]
+async fn port(
+    ctx: Context<'_>,
+    #[description = "Port of the service, e.g. 27015. Empty checks the resource with ICMP ping"]
+    #[min = 1]
+    port: Option<u16>,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let mut config_lock = ctx.data().config.write().await;
+    let Some(check_kind) = config_lock.ping_config.check_kind.with_port_changed(port) else {
+        drop(config_lock);
+        simple_reply_text(
+            ctx,
+            true,
+            "Current check kind needs a port! Use */config checkkind* to change it".to_string(),
+        )
+        .await;
+        return Ok(());
+    };
+    remember_config(ctx, &config_lock).await;
+    config_lock.ping_config.check_kind = check_kind.clone();
+    drop(config_lock);
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) changed check kind to {:?}",
+        ctx.author().name,
+        ctx.author().id,
+        check_kind
+    );
+    audit(ctx).await;
+    save_data(ctx.data()).await;
+
+    let reply = match check_kind {
+        CheckKind::Icmp => "Resource is now checked with ICMP ping!".to_string(),
+        CheckKind::Tcp { port } => format!(
+            "Resource is now checked with TCP connections to port {}!",
+            port
+        ),
+        check_kind => format!("Resource is now checked with {:?}!", check_kind),
+    };
+    simple_reply_text(ctx, true, reply).await;
+
+    Ok(())
+}
+
+/// [M ONLY] Changes daily hours (UTC), outside of which the resource isn't checked. Empty disables it
+#[poise::command(
+    slash_command,
+    guild_cooldown = 30,
+    rename = "active-hours",
+    check = "admin_check"
+)]
+async fn active_hours(
+    ctx: Context<'_>,
+    #[description = "Start of active hours in UTC, e.g. \"09:00\""] start: Option<String>,
+    #[description = "End of active hours in UTC, e.g. \"18:00\". May be before the start to span midnight"]
+    end: Option<String>,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+    let active_hours = match (start, end) {
+        (Some(start), Some(end)) => match (start.parse(), end.parse()) {
+            (Ok(start), Ok(end)) => Some(ActiveHours { start, end }),
+            (Err(err), _) | (_, Err(err)) => {
+                simple_reply_text(ctx, true, format!("Failed to parse: {}", err)).await;
+                return Ok(());
+            }
+        },
+        (None, None) => None,
+        _ => {
+            simple_reply_text(
+                ctx,
+                true,
+                "Set both start and end, or neither to check the resource all day!".to_string(),
+            )
+            .await;
+            return Ok(());
+        }
+    };
+
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.ping_config.active_hours = active_hours;
+    drop(config_lock);
+    let active_hours = active_hours.map_or("all day".to_string(), |active_hours| {
+        active_hours.to_string()
+    });
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) changed active hours to {}",
+        ctx.author().name,
+        ctx.author().id,
+        active_hours
+    );
+    audit(ctx).await;
+    save_data(ctx.data()).await;
+
+    simple_reply_text(
+        ctx,
+        true,
+        format!(
+            "Changed active hours to {}! The change applies on the next check",
+            active_hours
+        ),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// [M ONLY] Changes how much the newest round-trip time weighs in the smoothed one
+#[poise::command(
+    slash_command,
+    guild_cooldown = 30,
+    rename = "rtt-smoothing",
+    check = "admin_check"
+)]
+async fn rtt_smoothing(
+    ctx: Context<'_>,
+    #[description = "Weight from 0.01 to 1, e.g. 0.3. Lower is smoother, 1 shows the latest round-trip time"]
+    #[min = 0.01]
+    #[max = 1.0]
+    smoothing: f64,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+    if !(smoothing > 0.0 && smoothing <= 1.0) {
+        simple_reply_text(
+            ctx,
+            true,
+            "Smoothing must be above 0 and at most 1!".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.ping_config.rtt_smoothing = smoothing;
+    drop(config_lock);
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) changed RTT smoothing to {}",
+        ctx.author().name,
+        ctx.author().id,
+        smoothing
+    );
+    audit(ctx).await;
+    save_data(ctx.data()).await;
+
+    simple_reply_text(
+        ctx,
+        true,
+        format!("Changed RTT smoothing to {}!", smoothing),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// [M ONLY] Changes how many probe addresses are checked at the same time
+#[poise::command(
+    slash_command,
+    guild_cooldown = 30,
+    rename = "check-concurrency",
+    check = "admin_check"
+)]
+async fn check_concurrency(
+    ctx: Context<'_>,
+    #[description = "Checks over the limit wait for a free slot, so a timing out probe doesn't delay the rest"]
+    #[min = 1]
+    #[max = 32]
+    concurrency: u8,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+    let Some(concurrency) = NonZeroU8::new(concurrency) else {
+        simple_reply_text(ctx, true, "Concurrency must be at least 1!".to_string()).await;
+        return Ok(());
+    };
+
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.ping_config.check_concurrency = concurrency;
+    drop(config_lock);
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) changed check concurrency to {}",
+        ctx.author().name,
+        ctx.author().id,
+        concurrency
+    );
+    audit(ctx).await;
+    save_data(ctx.data()).await;
+
+    simple_reply_text(
+        ctx,
+        true,
+        format!("Changed check concurrency to {}!", concurrency),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// [M ONLY] Changes how many checks may be in progress at once, before further ones are skipped
+#[poise::command(
+    slash_command,
+    guild_cooldown = 30,
+    rename = "max-outstanding-checks",
+    check = "admin_check"
+)]
+async fn max_outstanding_checks(
+    ctx: Context<'_>,
+    #[description = "Counts checks made by commands too. Skipping prevents timing out checks from piling up sockets"]
+    #[min = 1]
+    #[max = 64]
+    limit: u8,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+    let Some(limit) = NonZeroU8::new(limit) else {
+        simple_reply_text(ctx, true, "Limit must be at least 1!".to_string()).await;
+        return Ok(());
+    };
+
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.ping_config.max_outstanding_checks = limit;
+    drop(config_lock);
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) changed max outstanding checks to {}",
+        ctx.author().name,
+        ctx.author().id,
+        limit
+    );
+    audit(ctx).await;
+    save_data(ctx.data()).await;
+
+    simple_reply_text(
+        ctx,
+        true,
+        format!("Changed max outstanding checks to {}!", limit),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// [M ONLY] Schedules a maintenance window. Leave duration empty to cancel all scheduled windows
+#[poise::command(slash_command, guild_cooldown = 20, check = "admin_check")]
+async fn maintenance(
+    ctx: Context<'_>,
+    #[description = "How long the maintenance lasts, e.g. \"2h 30m\""] duration: Option<String>,
+    #[description = "When the maintenance starts, e.g. \"1d\". Starts now if empty"]
+    starts_in: Option<String>,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let Some(duration) = duration else {
+        let mut config_lock = ctx.data().config.write().await;
+        remember_config(ctx, &config_lock).await;
+        config_lock.maintenance_windows.clear();
+        drop(config_lock);
+        log::info!(
+            target: EVENTS_TARGET,
+            "User {} ({}) cancelled scheduled maintenance",
+            ctx.author().name,
+            ctx.author().id
+        );
+        audit(ctx).await;
+        save_data(ctx.data()).await;
+        simple_reply_text(ctx, true, "Cancelled scheduled maintenance!".to_string()).await;
+        return Ok(());
+    };
+    let parse = |duration: &str| {
+        humantime::parse_duration(duration)
+            .map_err(|err| format!("Failed to parse {}: {}", duration, err))
+    };
+    let window = parse(&duration).and_then(|duration| {
+        let starts_in = starts_in.as_deref().map_or(Ok(Duration::ZERO), parse)?;
+        let too_far = || "Maintenance window is too far in the future!".to_string();
+        let offset = |duration: Duration| i64::try_from(duration.as_secs()).map_err(|_| too_far());
+        let start = Timestamp::now()
+            .unix_timestamp()
+            .checked_add(offset(starts_in)?)
+            .ok_or_else(too_far)?;
+        let end = start.checked_add(offset(duration)?).ok_or_else(too_far)?;
+        match (
+            Timestamp::from_unix_timestamp(start),
+            Timestamp::from_unix_timestamp(end),
+        ) {
+            (Ok(start), Ok(end)) => Ok(MaintenanceWindow { start, end }),
+            _ => Err(too_far()),
+        }
+    });
+    let window = match window {
+        Ok(window) => window,
+        Err(err) => {
+            simple_reply_text(ctx, true, err).await;
+            return Ok(());
+        }
+    };
+
+    let status_since = *ctx.data().last_status_change.read().await;
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.prune_maintenance_windows(Timestamp::now(), status_since);
+    config_lock.maintenance_windows.push(window);
+    drop(config_lock);
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) scheduled maintenance from {} to {}",
+        ctx.author().name,
+        ctx.author().id,
+        window.start,
+        window.end
+    );
+    audit(ctx).await;
+    save_data(ctx.data()).await;
+
+    simple_reply_text(
+        ctx,
+        true,
+        format!(
+            "Scheduled maintenance from <t:{}:f> to <t:{}:f>!",
+            window.start.unix_timestamp(),
+            window.end.unix_timestamp()
+        ),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// [M ONLY] Changes the channel for a feed of significant events and errors. Leave empty to disable
+#[poise::command(
+    slash_command,
+    guild_cooldown = 20,
+    rename = "log-channel",
+    check = "admin_check"
+)]
+async fn log_channel(
+    ctx: Context<'_>,
+    #[description = "Channel for the events feed"] channel: Option<Channel>,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+    if let Some(channel) = channel
+        .as_ref()
+        .filter(|channel| (*channel).clone().category().is_some())
+    {
+        simple_reply_text(
+            ctx,
+            true,
+            format!(
+                "<#{}> is an invalid channel for the events feed!",
+                channel.id()
+            ),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let channel_id = channel.map(|channel| channel.id());
+    // Checked before saving, so a channel, where the bot can't post, doesn't silently swallow the feed
+    if let Some(channel_id) = channel_id
+        && let Err(err) = channel_id
+            .send_message(
+                ctx.http(),
+                CreateMessage::new().content(format!(
+                    "This channel now receives the events feed of {}: config changes, status changes, warnings and errors.",
+                    ctx.data().config.read().await.ping_config.resource_name
+                )),
+            )
+            .await
+    {
+        log::warn!(
+            "User {} ({}) tried to set log channel to {}, where the bot can't post: {}",
+            ctx.author().name,
+            ctx.author().id,
+            channel_id,
+            err
+        );
+        simple_reply_text(
+            ctx,
+            true,
+            format!(
+                "Failed to post a test message to <#{}>: {}. Grant the bot Send Messages there!",
+                channel_id, err
+            ),
+        )
+        .await;
+        return Ok(());
+    }
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.master_log_channel = channel_id;
+    drop(config_lock);
+    match channel_id {
+        Some(channel_id) => {
+            log::info!(
+                target: EVENTS_TARGET,
+                "User {} ({}) changed log channel to {}",
+                ctx.author().name,
+                ctx.author().id,
+                channel_id
+            );
+            audit(ctx).await;
+            simple_reply_text(
+                ctx,
+                true,
+                format!(
+                    "Changed log channel to <#{}> and posted a test message there!",
+                    channel_id
+                ),
+            )
+            .await;
+        }
+        None => {
+            log::info!(
+                target: EVENTS_TARGET,
+                "User {} ({}) disabled log channel",
+                ctx.author().name,
+                ctx.author().id
+            );
+            audit(ctx).await;
+            simple_reply_text(ctx, true, "Disabled log channel!".to_string()).await;
+        }
+    }
+    save_data(ctx.data()).await;
+
+    Ok(())
+}
+
+/// [M ONLY] Changes whether Unknown status (a bot-side problem) is shown only in the Master server
+#[poise::command(
+    slash_command,
+    guild_cooldown = 20,
+    rename = "unknown-to-master",
+    check = "admin_check"
+)]
+async fn unknown_to_master(
+    ctx: Context<'_>,
+    #[description = "If enabled, other servers keep showing the last known status"] enabled: bool,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.unknown_to_master_only = enabled;
+    drop(config_lock);
+    let state = if enabled {
+        "only to the Master server"
+    } else {
+        "to all servers"
+    };
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) changed Unknown status to be shown {}",
+        ctx.author().name,
+        ctx.author().id,
+        state
+    );
+    audit(ctx).await;
+    save_data(ctx.data()).await;
+
+    simple_reply_text(ctx, true, format!("Unknown status is now shown {}!", state)).await;
+
+    Ok(())
+}
+
+/// [M ONLY] Changes how long after start Unknown status is only logged. Empty disables it
+#[poise::command(
+    slash_command,
+    guild_cooldown = 20,
+    rename = "startup-grace",
+    check = "admin_check"
+)]
+async fn startup_grace(
+    ctx: Context<'_>,
+    #[description = "Grace, e.g. \"1m\". Unknown status, which lasts longer, is shown after it"]
+    grace: Option<String>,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+    let grace = match grace {
+        Some(grace) => match humantime::parse_duration(&grace) {
+            Ok(grace) => Some(grace),
+            Err(err) => {
+                simple_reply_text(ctx, true, format!("Failed to parse {}: {}", grace, err)).await;
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.startup_grace = grace;
+    drop(config_lock);
+    let grace = grace.map_or("disabled".to_string(), |grace| {
+        humantime::format_duration(grace).to_string()
+    });
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) changed startup grace to {}",
+        ctx.author().name,
+        ctx.author().id,
+        grace
+    );
+    audit(ctx).await;
+    save_data(ctx.data()).await;
+
+    simple_reply_text(ctx, true, format!("Changed startup grace to {}!", grace)).await;
+
+    Ok(())
+}
+
+/// [M ONLY] Changes how often it is reminded, that the status is still unknown. Empty disables it
+#[poise::command(
+    slash_command,
+    guild_cooldown = 20,
+    rename = "unknown-reminder",
+    check = "admin_check"
+)]
+async fn unknown_reminder(
+    ctx: Context<'_>,
+    #[description = "Interval, e.g. \"30m\". Reminders go to the Master server, until the status is up or down"]
+    interval: Option<String>,
+    #[description = "Whether the reminders also go to the other servers. False, if empty"]
+    to_servers: Option<bool>,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+    let interval = match interval {
+        Some(interval) => match humantime::parse_duration(&interval) {
+            Ok(interval) if interval.is_zero() => {
+                simple_reply_text(ctx, true, "Interval can't be zero!".to_string()).await;
+                return Ok(());
+            }
+            Ok(interval) => Some(interval),
+            Err(err) => {
+                simple_reply_text(ctx, true, format!("Failed to parse {}: {}", interval, err))
+                    .await;
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+    let to_servers = to_servers.unwrap_or(false);
+
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.unknown_reminder_interval = interval;
+    config_lock.unknown_reminder_to_servers = to_servers;
+    drop(config_lock);
+    let reply = match interval {
+        Some(interval) => format!(
+            "Unknown status is now reminded about every {}{}!",
+            humantime::format_duration(interval),
+            if to_servers {
+                " in every server"
+            } else {
+                " in the Master server"
+            }
+        ),
+        None => "Unknown status reminders are now disabled!".to_string(),
+    };
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) changed unknown status reminders to {:?}, to servers: {}",
+        ctx.author().name,
+        ctx.author().id,
+        interval,
+        to_servers
+    );
+    audit(ctx).await;
+    save_data(ctx.data()).await;
+
+    simple_reply_text(ctx, true, reply).await;
+
+    Ok(())
+}
+
+/// [M ONLY] Changes whether each check towards confirming a status change is posted to the log channel
+#[poise::command(
+    slash_command,
+    guild_cooldown = 20,
+    rename = "verbose-confirmation",
+    check = "admin_check"
+)]
+async fn verbose_confirmation(
+    ctx: Context<'_>,
+    #[description = "If enabled, progress like \"2/4 checks\" is posted to the Master log channel"]
+    enabled: bool,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.verbose_confirmation = enabled;
+    let log_channel_set = config_lock.master_log_channel.is_some();
+    drop(config_lock);
+    let state = if enabled { "enabled" } else { "disabled" };
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) {} verbose confirmation",
+        ctx.author().name,
+        ctx.author().id,
+        state
+    );
+    audit(ctx).await;
+    save_data(ctx.data()).await;
+
+    let reply = if enabled && !log_channel_set {
+        "Verbose confirmation is now enabled, but there is no log channel to post to! Set it with */config log-channel*".to_string()
+    } else {
+        format!("Verbose confirmation is now {}!", state)
+    };
+    simple_reply_text(ctx, true, reply).await;
+
+    Ok(())
+}
+
+/// [M ONLY] Checks that every server's messages fit into Discord's limit with the longest substitutions
+#[poise::command(
+    slash_command,
+    guild_cooldown = 30,
+    rename = "validate-messages",
+    check = "admin_check"
+)]
+async fn validate_messages(ctx: Context<'_>) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let config_lock = ctx.data().config.read().await;
+    let resource_name = &config_lock.ping_config.resource_name;
+    let mut long_messages = Vec::new();
+    for (server_id, server_config) in &config_lock.server_configs {
+        for kind in TransitionKind::ALL {
+            let length = worst_case_message_length(server_config.message(kind), resource_name);
+            if length > DISCORD_MESSAGE_LIMIT {
+                long_messages.push(format!(
+                    "- {} ({}): {} message can take {} characters",
+                    server_config.name,
+                    server_id,
+                    kind.name(),
+                    length
+                ));
+            }
+        }
+    }
+    let servers = config_lock.server_configs.len();
+    drop(config_lock);
+    log::info!(
+        "User {} ({}) validated messages: {} are too long",
+        ctx.author().name,
+        ctx.author().id,
+        long_messages.len()
+    );
+
+    let reply = if long_messages.is_empty() {
+        format!(
+            "Messages of all {} servers fit into {} characters!",
+            servers, DISCORD_MESSAGE_LIMIT
+        )
+    } else {
+        let more = long_messages.len().saturating_sub(MAX_LISTED_LONG_MESSAGES);
+        long_messages.truncate(MAX_LISTED_LONG_MESSAGES);
+        if more > 0 {
+            long_messages.push(format!("...and {} more", more));
+        }
+        format!(
+            "These messages can exceed {} characters, when %%ROLE%% and %%DOWNTIME%% are substituted, and fail to send:\n{}",
+            DISCORD_MESSAGE_LIMIT,
+            long_messages.join("\n")
+        )
+    };
+    simple_reply_text(ctx, true, reply).await;
+
+    Ok(())
+}
+
+//
+//
+//
+// server CONFIGURATION
+//
+//
+//
+
+/// Changes channel, where bot will send any updates
+#[poise::command(slash_command, guild_cooldown = 30, check = "admin_check")]
+async fn channel(
+    ctx: Context<'_>,
+    #[description = "New channel for updates"] channel: Channel,
+) -> Result<(), Error> {
+    let server_string = match ctx.guild() {
+        Some(server) => {
+            format!("{} ({})", server.name, server.id)
+        }
+        None => "UNKNOWN".to_string(),
+    };
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!(
+            "[server {}] Failed to defer ephemeral reply: {}",
+            server_string,
+            err,
+        );
+    };
+    let mut config_lock = ctx.data().config.write().await;
+    let mut entry = match get_server_config_entry(ctx.guild_id(), &mut config_lock) {
+        Ok(entry) => entry,
+        Err(err) => {
+            simple_reply_text(ctx, true, err.to_string()).await;
+            return Ok(());
+        }
+    };
+    if channel.clone().category().is_some() {
+        simple_reply_text(
+            ctx,
+            true,
+            format!(
+                "<#{}> is an invalid channel for healthcheck updates!",
+                channel.id()
+            ),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let mut new_server_config = entry.get().clone();
+    remember_server_config(ctx, Some(entry.get().clone())).await;
+    new_server_config.channel = Some(channel.id());
+    entry.insert(new_server_config);
+
+    log::info!(
+        target: EVENTS_TARGET,
+        "[server {}] User {} ({}) changed channel to {} ({})",
+        server_string,
+        ctx.author().name,
+        ctx.author().id,
+        channel,
+        channel.id()
+    );
+
+    audit(ctx).await;
+    simple_reply_text(
+        ctx,
+        true,
+        format!("Changed channel to <#{}>!", channel.id()),
+    )
+    .await;
+
+    drop(config_lock);
+
+    save_data(ctx.data()).await;
+
+    Ok(())
+}
+
+/// Changes role, which will be pinged by the bot when resource is up
+#[poise::command(slash_command, guild_cooldown = 30, check = "admin_check")]
+async fn role(
+    ctx: Context<'_>,
+    #[description = "New role for notifications"] role: Role,
+) -> Result<(), Error> {
+    let server_string = match ctx.guild() {
+        Some(server) => {
+            format!("{} ({})", server.name, server.id)
+        }
+        None => "UNKNOWN".to_string(),
+    };
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!(
+            "[server {}] Failed to defer ephemeral reply: {}",
+            server_string,
+            err,
+        );
+    };
+    let mut config_lock = ctx.data().config.write().await;
+    let mut entry = match get_server_config_entry(ctx.guild_id(), &mut config_lock) {
+        Ok(entry) => entry,
+        Err(err) => {
+            simple_reply_text(ctx, true, err.to_string()).await;
+            return Ok(());
+        }
+    };
+    if !role.mentionable {
+        simple_reply_text(ctx, true, format!("{} can not be mentioned!", role.name)).await;
+        return Ok(());
+    }
+
+    let mut new_server_config = entry.get().clone();
+    remember_server_config(ctx, Some(entry.get().clone())).await;
+    new_server_config.role_to_notify = Some(role.id);
+    entry.insert(new_server_config);
+
+    log::info!(
+        target: EVENTS_TARGET,
+        "[server {}] User {} ({}) changed mentionable role to {} ({})",
+        server_string,
+        ctx.author().name,
+        ctx.author().id,
+        role.name,
+        role.id
+    );
+
+    audit(ctx).await;
+    simple_reply_text(
+        ctx,
+        true,
+        format!("Changed mentionable role to <@&{}>!", role.id),
+    )
+    .await;
+
+    drop(config_lock);
+
+    save_data(ctx.data()).await;
+
+    Ok(())
+}
+
+/// Changes the message sent on status change. Without the message, opens an editor with the current one
+#[poise::command(slash_command, guild_cooldown = 30, check = "admin_check")]
+async fn message(
+    ctx: Context<'_>,
+    #[description = "Whether your message will be sent on Up or Down change, or on maintenance start or end"]
+    kind: TransitionKind,
+    #[description = "Message, which will be sent. Remember about %%RESOURCE%%, %%ROLE%% and %%DOWNTIME%% template variables!"]
+    #[max_length = 300]
+    #[min_length = 1]
+    message: Option<String>,
+) -> Result<(), Error> {
+    let server_string = match ctx.guild() {
+        Some(server) => {
+            format!("{} ({})", server.name, server.id)
+        }
+        None => "UNKNOWN".to_string(),
+    };
+    let message = match message {
+        Some(message) => {
+            if let Err(err) = ctx.defer_ephemeral().await {
+                log::error!(
+                    "[server {}] Failed to defer ephemeral reply: {}",
+                    server_string,
+                    err,
+                );
+            };
+            message
+        }
+        // Modal must be the first response, so the reply is not deferred
+        None => match edit_message(ctx, kind).await {
+            Ok(Some(message)) => message,
+            Ok(None) => return Ok(()),
+            Err(err) => {
+                log::error!(
+                    "[server {}] Failed to edit message in a modal: {}",
+                    server_string,
+                    err
+                );
+                return Ok(());
+            }
+        },
+    };
+    let mut config_lock = ctx.data().config.write().await;
+    let mut entry = match get_server_config_entry(ctx.guild_id(), &mut config_lock) {
+        Ok(entry) => entry,
+        Err(err) => {
+            simple_reply_text(ctx, true, err.to_string()).await;
+            return Ok(());
+        }
+    };
+
+    let mut new_server_config = entry.get().clone();
+    remember_server_config(ctx, Some(entry.get().clone())).await;
+
+    new_server_config.messages.insert(kind, message.clone());
+    entry.insert(new_server_config);
+
+    log::info!(
+        target: EVENTS_TARGET,
+        "[server {}] User {} ({}) changed {:?} message to {}",
+        server_string,
+        ctx.author().name,
+        ctx.author().id,
+        kind,
+        message
+    );
+
+    audit(ctx).await;
+    simple_reply_text(
+        ctx,
+        true,
+        format!("Changed {:?} message to {}!", kind, message),
+    )
+    .await;
+
+    drop(config_lock);
+
+    save_data(ctx.data()).await;
+
+    Ok(())
+}
+
+/// Opens a modal with the current message of the kind. Returns the edited one, or `None` if it wasn't submitted in time
+async fn edit_message(ctx: Context<'_>, kind: TransitionKind) -> Result<Option<String>, Error> {
+    let Context::Application(app_ctx) = ctx else {
+        return Ok(None);
+    };
+    let current = match ctx.guild_id() {
+        Some(server_id) => ctx
+            .data()
+            .config
+            .read()
+            .await
+            .server_configs
+            .get(&server_id)
+            .map(|server_config| server_config.message(kind).to_string()),
+        None => None,
+    };
+    let Some(current) = current else {
+        simple_reply_text(
+            ctx,
+            true,
+            "Your server is not registered yet! Use */server register*!".to_string(),
+        )
+        .await;
+        return Ok(None);
+    };
+    let edited = poise::execute_modal(
+        app_ctx,
+        Some(MessageModal { message: current }),
+        Some(MESSAGE_MODAL_TIMEOUT),
+    )
+    .await?;
+    Ok(edited.map(|modal| modal.message))
+}
+
+/// Reverts the last configuration change made in this server
+#[poise::command(slash_command, guild_cooldown = 20, check = "admin_check")]
+async fn undo(ctx: Context<'_>) -> Result<(), Error> {
+    let server_string = match ctx.guild() {
+        Some(server) => {
+            format!("{} ({})", server.name, server.id)
+        }
+        None => "UNKNOWN".to_string(),
+    };
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!(
+            "[server {}] Failed to defer ephemeral reply: {}",
+            server_string,
+            err,
+        );
+    };
+    let Some(server_id) = ctx.guild_id() else {
+        simple_reply_text(
+            ctx,
+            true,
+            "You need to be within a server to execute this command!".to_string(),
+        )
+        .await;
+        return Ok(());
+    };
+
+    match ctx.data().pop_undo(server_id).await {
+        Some(UndoEntry::Global { config, servers }) => {
+            ctx.data()
+                .config
+                .write()
+                .await
+                .restore_master_settings(config, servers);
+            log::info!(
+                target: EVENTS_TARGET,
+                "[server {}] User {} ({}) reverted last master configuration change",
+                server_string,
+                ctx.author().name,
+                ctx.author().id,
+            );
+            audit(ctx).await;
+            simple_reply_text(
+                ctx,
+                true,
+                "Reverted last master configuration change!".to_string(),
+            )
+            .await;
+        }
+        Some(UndoEntry::Server(server_config)) => {
+            let may_change_admin_role = ctx
+                .author_member()
+                .await
+                .is_some_and(|member| can_manage_channels(&member));
+            let mut config_lock = ctx.data().config.write().await;
+            match server_config {
+                Some(server_config) => {
+                    if !config_lock.server_configs.contains_key(&server_id) {
+                        simple_reply_text(
+                            ctx,
+                            true,
+                            "Your server is not registered anymore! Use */server register*!"
+                                .to_string(),
+                        )
+                        .await;
+                        return Ok(());
+                    }
+                    // Like /config admin-role, reverting the admin role requires MANAGE_CHANNELS, so members of
+                    // the admin role can't restore it after it's removed
+                    if server_config.admin_role != config_lock.server_configs[&server_id].admin_role
+                        && !may_change_admin_role
+                    {
+                        drop(config_lock);
+                        ctx.data()
+                            .push_undo(server_id, UndoEntry::Server(Some(server_config)))
+                            .await;
+                        simple_reply_text(
+                            ctx,
+                            true,
+                            "Reverting the last change needs MANAGE_CHANNELS permission, since it changed the admin role!"
+                                .to_string(),
+                        )
+                        .await;
+                        return Ok(());
+                    }
+                    config_lock.server_configs.insert(server_id, server_config);
+                }
+                None => {
+                    config_lock.server_configs.remove(&server_id);
+                }
+            }
+            drop(config_lock);
+            log::info!(
+                target: EVENTS_TARGET,
+                "[server {}] User {} ({}) reverted last server configuration change",
+                server_string,
+                ctx.author().name,
+                ctx.author().id,
+            );
+            audit(ctx).await;
+            simple_reply_text(
+                ctx,
+                true,
+                "Reverted last server configuration change!".to_string(),
+            )
+            .await;
+        }
+        None => {
+            simple_reply_text(ctx, true, "There is nothing to undo!".to_string()).await;
+            return Ok(());
+        }
+    }
+
+    save_data(ctx.data()).await;
+
+    Ok(())
+}
+
+/// [M ONLY] Saves the whole configuration under a name, so it can be restored with */config restore*
+#[poise::command(slash_command, guild_cooldown = 20, check = "admin_check")]
+async fn snapshot(
+    ctx: Context<'_>,
+    #[description = "Name of the snapshot. A snapshot with the same name is replaced"]
+    #[max_length = 32]
+    name: String,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let config = ctx.data().config.read().await.clone();
+    let mut snapshots_lock = ctx.data().config_snapshots.write().await;
+    if !snapshots_lock.contains_key(&name) && snapshots_lock.len() >= MAX_CONFIG_SNAPSHOTS {
+        simple_reply_text(
+            ctx,
+            true,
+            format!(
+                "There are already {} snapshots! Replace one of them, or see */config snapshots*.",
+                MAX_CONFIG_SNAPSHOTS
+            ),
+        )
+        .await;
+        return Ok(());
+    }
+    let replaced = snapshots_lock
+        .insert(
+            name.clone(),
+            ConfigSnapshot {
+                taken_at: Timestamp::now(),
+                taken_by: ctx.author().name.clone(),
+                config,
+            },
+        )
+        .is_some();
+    drop(snapshots_lock);
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) took config snapshot {}",
+        ctx.author().name,
+        ctx.author().id,
+        name
+    );
+    audit(ctx).await;
+    save_data(ctx.data()).await;
+
+    let action = if replaced { "Replaced" } else { "Saved" };
+    simple_reply_text(ctx, true, format!("{} snapshot `{}`!", action, name)).await;
+
+    Ok(())
+}
+
+/// [M ONLY] Lists saved configuration snapshots
+#[poise::command(slash_command, guild_cooldown = 10, check = "observer_check")]
+async fn snapshots(ctx: Context<'_>) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let snapshots_lock = ctx.data().config_snapshots.read().await;
+    if snapshots_lock.is_empty() {
+        simple_reply_text(
+            ctx,
+            true,
+            "There are no snapshots! Take one with */config snapshot*.".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+    let lines: Vec<_> = snapshots_lock
+        .iter()
+        .map(|(name, snapshot)| {
+            format!(
+                "- `{}`, taken <t:{}:R> by {}",
+                name,
+                snapshot.taken_at.unix_timestamp(),
+                snapshot.taken_by
+            )
+        })
+        .collect();
+    drop(snapshots_lock);
+
+    simple_reply_text(
+        ctx,
+        true,
+        format!(
+            "Snapshots ({}/{}):\n{}",
+            lines.len(),
+            MAX_CONFIG_SNAPSHOTS,
+            lines.join("\n")
+        ),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// [M ONLY] Restores master settings from a snapshot. Servers keep their configuration
+#[poise::command(slash_command, guild_cooldown = 20, check = "admin_check")]
+async fn restore(
+    ctx: Context<'_>,
+    #[description = "Name of the snapshot, see */config snapshots*"]
+    #[max_length = 32]
+    name: String,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+    let Some(snapshot) = ctx.data().config_snapshots.read().await.get(&name).cloned() else {
+        simple_reply_text(
+            ctx,
+            true,
+            format!("There is no snapshot `{}`! See */config snapshots*.", name),
+        )
+        .await;
+        return Ok(());
+    };
+
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.restore_master_settings(snapshot.config.master_settings(), BTreeMap::new());
+    drop(config_lock);
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) restored config snapshot {}",
+        ctx.author().name,
+        ctx.author().id,
+        name
+    );
+    audit(ctx).await;
+    save_data(ctx.data()).await;
+
+    simple_reply_text(
+        ctx,
+        true,
+        format!(
+            "Restored master settings from snapshot `{}`, taken <t:{}:f>! Servers keep their configuration.",
+            name,
+            snapshot.taken_at.unix_timestamp()
+        ),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// Changes minimal time between role mentions. Messages within it are sent without the mention
+#[poise::command(
+    slash_command,
+    guild_cooldown = 30,
+    rename = "mention-cooldown",
+    check = "admin_check"
+)]
+async fn mention_cooldown(
+    ctx: Context<'_>,
+    #[description = "Cooldown, e.g. \"15m\". \"0s\" mentions the role every time. Status embed ignores it"]
+    cooldown: String,
+) -> Result<(), Error> {
+    let server_string = match ctx.guild() {
+        Some(server) => {
+            format!("{} ({})", server.name, server.id)
+        }
+        None => "UNKNOWN".to_string(),
+    };
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!(
+            "[server {}] Failed to defer ephemeral reply: {}",
+            server_string,
+            err,
+        );
+    };
+    let cooldown = match humantime::parse_duration(&cooldown) {
+        Ok(cooldown) => cooldown,
+        Err(err) => {
+            simple_reply_text(ctx, true, format!("Failed to parse {}: {}", cooldown, err)).await;
+            return Ok(());
+        }
+    };
+    let mut config_lock = ctx.data().config.write().await;
+    let mut entry = match get_server_config_entry(ctx.guild_id(), &mut config_lock) {
+        Ok(entry) => entry,
+        Err(err) => {
+            simple_reply_text(ctx, true, err.to_string()).await;
+            return Ok(());
+        }
+    };
+
+    let mut new_server_config = entry.get().clone();
+    remember_server_config(ctx, Some(entry.get().clone())).await;
+    new_server_config.mention_cooldown = cooldown;
+    entry.insert(new_server_config);
+
+    let cooldown = humantime::format_duration(cooldown);
+    log::info!(
+        target: EVENTS_TARGET,
+        "[server {}] User {} ({}) changed mention cooldown to {}",
+        server_string,
+        ctx.author().name,
+        ctx.author().id,
+        cooldown
+    );
+    audit(ctx).await;
+    simple_reply_text(
+        ctx,
+        true,
+        format!("Changed mention cooldown to {}!", cooldown),
+    )
+    .await;
+
+    drop(config_lock);
+
+    save_data(ctx.data()).await;
+
+    Ok(())
+}
+
+/// Changes whether the role is mentioned when resource goes up or down
+#[poise::command(
+    slash_command,
+    guild_cooldown = 30,
+    rename = "ping-on",
+    check = "admin_check"
+)]
+async fn ping_on(
+    ctx: Context<'_>,
+    #[description = "Whether this applies to the Up or Down resource's status change"]
+    status: Status,
+    #[description = "Whether %%ROLE%% should mention the role. If not, it is replaced by a plain text"]
+    enabled: bool,
+) -> Result<(), Error> {
+    let server_string = match ctx.guild() {
+        Some(server) => {
+            format!("{} ({})", server.name, server.id)
+        }
+        None => "UNKNOWN".to_string(),
+    };
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!(
+            "[server {}] Failed to defer ephemeral reply: {}",
+            server_string,
+            err,
+        );
+    };
+    let mut config_lock = ctx.data().config.write().await;
+    let mut entry = match get_server_config_entry(ctx.guild_id(), &mut config_lock) {
+        Ok(entry) => entry,
+        Err(err) => {
+            simple_reply_text(ctx, true, err.to_string()).await;
+            return Ok(());
+        }
+    };
+
+    let mut new_server_config = entry.get().clone();
+    remember_server_config(ctx, Some(entry.get().clone())).await;
+
+    match status {
+        Status::Up => new_server_config.ping_on_up = enabled,
+        Status::Down => new_server_config.ping_on_down = enabled,
+    }
+    entry.insert(new_server_config);
+
+    let state = if enabled { "enabled" } else { "disabled" };
+    log::info!(
+        target: EVENTS_TARGET,
+        "[server {}] User {} ({}) {} role mention on {:?}",
+        server_string,
+        ctx.author().name,
+        ctx.author().id,
+        state,
+        status
+    );
+    audit(ctx).await;
+    simple_reply_text(
+        ctx,
+        true,
+        format!("Role mention on {:?} is now {}!", status, state),
+    )
+    .await;
+
+    drop(config_lock);
+
+    save_data(ctx.data()).await;
+
+    Ok(())
+}
+
+/// Changes reactions, which are added to the up or down message. Leave empty to remove them
+#[poise::command(slash_command, guild_cooldown = 30, check = "admin_check")]
+async fn reactions(
+    ctx: Context<'_>,
+    #[description = "Whether these reactions are added on Up or Down resource's status change"]
+    status: Status,
+    #[description = "Space-separated emojis, e.g. \"✅ 🎉\""] emojis: Option<String>,
+) -> Result<(), Error> {
+    let server_string = match ctx.guild() {
+        Some(server) => {
+            format!("{} ({})", server.name, server.id)
+        }
+        None => "UNKNOWN".to_string(),
+    };
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!(
+            "[server {}] Failed to defer ephemeral reply: {}",
+            server_string,
+            err,
+        );
+    };
+    let reactions: Vec<String> = emojis
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect();
+    if let Some(invalid) = reactions
+        .iter()
+        .find(|reaction| ReactionType::try_from(reaction.as_str()).is_err())
+    {
+        simple_reply_text(ctx, true, format!("{} is not a valid emoji!", invalid)).await;
+        return Ok(());
+    }
+
+    let mut config_lock = ctx.data().config.write().await;
+    let mut entry = match get_server_config_entry(ctx.guild_id(), &mut config_lock) {
+        Ok(entry) => entry,
+        Err(err) => {
+            simple_reply_text(ctx, true, err.to_string()).await;
+            return Ok(());
+        }
+    };
+
+    let mut new_server_config = entry.get().clone();
+    remember_server_config(ctx, Some(entry.get().clone())).await;
+    match status {
+        Status::Up => new_server_config.up_reactions = reactions.clone(),
+        Status::Down => new_server_config.down_reactions = reactions.clone(),
+    }
+    entry.insert(new_server_config);
+
+    log::info!(
+        target: EVENTS_TARGET,
+        "[server {}] User {} ({}) changed {:?} reactions to {:?}",
+        server_string,
+        ctx.author().name,
+        ctx.author().id,
+        status,
+        reactions
+    );
+
+    audit(ctx).await;
+    let reply = if reactions.is_empty() {
+        format!("Removed reactions on {:?}!", status)
+    } else {
+        format!(
+            "Changed reactions on {:?} to {}! Make sure the bot has Add Reactions permission.",
+            status,
+            reactions.join(" ")
+        )
+    };
+    simple_reply_text(ctx, true, reply).await;
+
+    drop(config_lock);
+
+    save_data(ctx.data()).await;
+
+    Ok(())
+}
+
+/// Changes whether this server gets the status embed, or only up and down messages
+#[poise::command(
+    slash_command,
+    guild_cooldown = 30,
+    rename = "embed-enabled",
+    check = "admin_check"
+)]
+async fn embed_enabled(
+    ctx: Context<'_>,
+    #[description = "If disabled, the status embed is removed and only up and down messages are sent"]
+    enabled: bool,
+) -> Result<(), Error> {
+    let server_string = match ctx.guild() {
+        Some(server) => {
+            format!("{} ({})", server.name, server.id)
+        }
+        None => "UNKNOWN".to_string(),
+    };
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!(
+            "[server {}] Failed to defer ephemeral reply: {}",
+            server_string,
+            err,
+        );
+    };
+    let mut config_lock = ctx.data().config.write().await;
+    let mut entry = match get_server_config_entry(ctx.guild_id(), &mut config_lock) {
+        Ok(entry) => entry,
+        Err(err) => {
+            simple_reply_text(ctx, true, err.to_string()).await;
+            return Ok(());
+        }
+    };
+    let server_id = *entry.key();
+
+    let mut new_server_config = entry.get().clone();
+    remember_server_config(ctx, Some(entry.get().clone())).await;
+    new_server_config.embed_enabled = enabled;
+    entry.insert(new_server_config);
+    drop(config_lock);
+
+    let state = if enabled { "enabled" } else { "disabled" };
+    log::info!(
+        target: EVENTS_TARGET,
+        "[server {}] User {} ({}) {} the status embed",
+        server_string,
+        ctx.author().name,
+        ctx.author().id,
+        state
+    );
+    audit(ctx).await;
+
+    if !enabled {
+        let status_message = ctx
+            .data()
+            .used_messages
+            .write()
+            .await
+            .get_mut(&server_id)
+            .and_then(|messages| messages.take_status());
+        if let Some((channel_id, message_id)) = status_message {
+            let deletion_result = ctx
+                .http()
+                .delete_message(channel_id, message_id, None)
+                .await;
+            if let Err(err) = deletion_result {
+                log::warn!(
+                    "[server {}] Failed to delete status message: {}",
+                    server_string,
+                    err
+                );
+            }
+        }
+    }
+
+    let reply = if enabled {
+        "Status embed is now enabled! It is posted with the next status change.".to_string()
+    } else {
+        "Status embed is now disabled! The posted one is deleted, only up and down messages are sent."
+            .to_string()
+    };
+    simple_reply_text(ctx, true, reply).await;
+
+    save_data(ctx.data()).await;
+
+    Ok(())
+}
+
+/// Changes whether the resource being up is bad news (e.g. a maintenance page), swapping up and down
+#[poise::command(slash_command, guild_cooldown = 30, check = "admin_check")]
+async fn invert(
+    ctx: Context<'_>,
+    #[description = "If enabled, up message is sent, when the resource goes down, and vice versa"]
+    enabled: bool,
+) -> Result<(), Error> {
+    let server_string = match ctx.guild() {
+        Some(server) => {
+            format!("{} ({})", server.name, server.id)
+        }
+        None => "UNKNOWN".to_string(),
+    };
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!(
+            "[server {}] Failed to defer ephemeral reply: {}",
+            server_string,
+            err,
+        );
+    };
+    let mut config_lock = ctx.data().config.write().await;
+    let mut entry = match get_server_config_entry(ctx.guild_id(), &mut config_lock) {
+        Ok(entry) => entry,
+        Err(err) => {
+            simple_reply_text(ctx, true, err.to_string()).await;
+            return Ok(());
+        }
+    };
+
+    let mut new_server_config = entry.get().clone();
+    remember_server_config(ctx, Some(entry.get().clone())).await;
+    new_server_config.inverted = enabled;
+    entry.insert(new_server_config);
+
+    let state = if enabled { "bad" } else { "good" };
+    log::info!(
+        target: EVENTS_TARGET,
+        "[server {}] User {} ({}) made the resource being up {} news",
+        server_string,
+        ctx.author().name,
+        ctx.author().id,
+        state
+    );
+    audit(ctx).await;
+    simple_reply_text(
+        ctx,
+        true,
+        format!(
+            "The resource being up is now {} news! It applies since the next status change.",
+            state
+        ),
+    )
+    .await;
+
+    drop(config_lock);
+
+    save_data(ctx.data()).await;
+
+    Ok(())
+}
+
+/// Changes whether the bot warns about its status message being edited by someone else
+#[poise::command(
+    slash_command,
+    guild_cooldown = 30,
+    rename = "detect-edits",
+    check = "admin_check"
+)]
+async fn detect_edits(
+    ctx: Context<'_>,
+    #[description = "Whether manual edits of the status message should be detected"] enabled: bool,
+) -> Result<(), Error> {
+    let server_string = match ctx.guild() {
+        Some(server) => {
+            format!("{} ({})", server.name, server.id)
+        }
+        None => "UNKNOWN".to_string(),
+    };
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!(
+            "[server {}] Failed to defer ephemeral reply: {}",
+            server_string,
+            err,
+        );
+    };
+    let mut config_lock = ctx.data().config.write().await;
+    let mut entry = match get_server_config_entry(ctx.guild_id(), &mut config_lock) {
+        Ok(entry) => entry,
+        Err(err) => {
+            simple_reply_text(ctx, true, err.to_string()).await;
+            return Ok(());
+        }
+    };
+
+    let mut new_server_config = entry.get().clone();
+    remember_server_config(ctx, Some(entry.get().clone())).await;
+    new_server_config.detect_manual_edits = enabled;
+    entry.insert(new_server_config);
+
+    let state = if enabled { "enabled" } else { "disabled" };
+    log::info!(
+        target: EVENTS_TARGET,
+        "[server {}] User {} ({}) {} detection of manual edits",
+        server_string,
+        ctx.author().name,
+        ctx.author().id,
+        state
+    );
+    audit(ctx).await;
+    simple_reply_text(
+        ctx,
+        true,
+        format!(
+            "Detection of manual edits is now {}! It applies since the next status message.",
+            state
+        ),
+    )
+    .await;
+
+    drop(config_lock);
+
+    save_data(ctx.data()).await;
+
+    Ok(())
+}
+
+/// Changes whether the status message is pinned in the notification channel
+#[poise::command(slash_command, guild_cooldown = 30, check = "admin_check")]
+async fn pin(
+    ctx: Context<'_>,
+    #[description = "Whether the status message should be pinned. The bot needs Manage Messages"]
+    enabled: bool,
+) -> Result<(), Error> {
+    let server_string = match ctx.guild() {
         Some(server) => {
             format!("{} ({})", server.name, server.id)
         }
@@ -417,27 +3061,29 @@ async fn role(
             return Ok(());
         }
     };
-    if !role.mentionable {
-        simple_reply_text(ctx, true, format!("{} can not be mentioned!", role.name)).await;
-        return Ok(());
-    }
 
     let mut new_server_config = entry.get().clone();
-    new_server_config.role_to_notify = Some(role.id);
+    remember_server_config(ctx, Some(entry.get().clone())).await;
+    new_server_config.pin_status_message = enabled;
     entry.insert(new_server_config);
 
+    let state = if enabled { "enabled" } else { "disabled" };
     log::info!(
-        "[server {}] User {} ({}) changed mentionable role to {} ({})",
+        target: EVENTS_TARGET,
+        "[server {}] User {} ({}) {} pinning of the status message",
         server_string,
         ctx.author().name,
         ctx.author().id,
-        role.name,
-        role.id
+        state
     );
+    audit(ctx).await;
     simple_reply_text(
         ctx,
         true,
-        format!("Changed mentionable role to <@&{}>!", role.id),
+        format!(
+            "Pinning of the status message is now {}! It applies since the next status message.",
+            state
+        ),
     )
     .await;
 
@@ -448,16 +3094,17 @@ async fn role(
     Ok(())
 }
 
-/// Changes required amount of consecutive attempts, after which resource will change its state
-#[poise::command(slash_command, guild_cooldown = 30)]
-async fn message(
+/// Changes whether the previous up/down message is deleted, when a new one is sent
+#[poise::command(
+    slash_command,
+    guild_cooldown = 30,
+    rename = "cleanup-messages",
+    check = "admin_check"
+)]
+async fn cleanup_messages(
     ctx: Context<'_>,
-    #[description = "Whether your message will be sent on Up or Down resource's status change"]
-    status: Status,
-    #[description = "Message, which will be sent. Remember about %%RESOURCE%% and %%ROLE%% template variables!"]
-    #[max_length = 300]
-    #[min_length = 1]
-    message: String,
+    #[description = "If enabled, only the latest up/down message is kept in the channel"]
+    enabled: bool,
 ) -> Result<(), Error> {
     let server_string = match ctx.guild() {
         Some(server) => {
@@ -482,25 +3129,27 @@ async fn message(
     };
 
     let mut new_server_config = entry.get().clone();
-
-    match status {
-        Status::Up => new_server_config.up_message = message.clone(),
-        Status::Down => new_server_config.down_message = message.clone(),
-    }
+    remember_server_config(ctx, Some(entry.get().clone())).await;
+    new_server_config.cleanup_messages = enabled;
     entry.insert(new_server_config);
 
+    let state = if enabled { "enabled" } else { "disabled" };
     log::info!(
-        "[server {}] User {} ({}) changed {:?} message to {}",
+        target: EVENTS_TARGET,
+        "[server {}] User {} ({}) {} cleanup of old up/down messages",
         server_string,
         ctx.author().name,
         ctx.author().id,
-        status,
-        message
+        state
     );
+    audit(ctx).await;
     simple_reply_text(
         ctx,
         true,
-        format!("Changed {:?} message to {}!", status, message),
+        format!(
+            "Cleanup of old up/down messages is now {}! It applies since the next up/down message.",
+            state
+        ),
     )
     .await;
 
@@ -510,3 +3159,641 @@ async fn message(
 
     Ok(())
 }
+
+/// Changes labels of the status embed's fields, e.g. to translate them. Leave empty to reset
+#[poise::command(
+    slash_command,
+    guild_cooldown = 30,
+    rename = "embed-labels",
+    check = "admin_check"
+)]
+async fn embed_labels(
+    ctx: Context<'_>,
+    #[description = "Label of the field with the time of the last status change"]
+    #[max_length = 256]
+    since: Option<String>,
+    #[description = "Label of the field with the resource's address"]
+    #[max_length = 256]
+    address: Option<String>,
+    #[description = "Label of the field with the resource's round-trip time"]
+    #[max_length = 256]
+    latency: Option<String>,
+) -> Result<(), Error> {
+    let server_string = match ctx.guild() {
+        Some(server) => {
+            format!("{} ({})", server.name, server.id)
+        }
+        None => "UNKNOWN".to_string(),
+    };
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!(
+            "[server {}] Failed to defer ephemeral reply: {}",
+            server_string,
+            err,
+        );
+    };
+    let mut config_lock = ctx.data().config.write().await;
+    let mut entry = match get_server_config_entry(ctx.guild_id(), &mut config_lock) {
+        Ok(entry) => entry,
+        Err(err) => {
+            simple_reply_text(ctx, true, err.to_string()).await;
+            return Ok(());
+        }
+    };
+
+    let mut new_server_config = entry.get().clone();
+    remember_server_config(ctx, Some(entry.get().clone())).await;
+    new_server_config.since_label = since.unwrap_or(DEFAULT_SINCE_LABEL.to_string());
+    new_server_config.address_label = address.unwrap_or(DEFAULT_ADDRESS_LABEL.to_string());
+    new_server_config.latency_label = latency.unwrap_or(DEFAULT_LATENCY_LABEL.to_string());
+    let reply = format!(
+        "Embed labels are now {}, {} and {}! They apply since the next status message.",
+        new_server_config.since_label,
+        new_server_config.address_label,
+        new_server_config.latency_label
+    );
+    log::info!(
+        target: EVENTS_TARGET,
+        "[server {}] User {} ({}) changed embed labels to {}, {} and {}",
+        server_string,
+        ctx.author().name,
+        ctx.author().id,
+        new_server_config.since_label,
+        new_server_config.address_label,
+        new_server_config.latency_label
+    );
+    entry.insert(new_server_config);
+
+    audit(ctx).await;
+    simple_reply_text(ctx, true, reply).await;
+
+    drop(config_lock);
+
+    save_data(ctx.data()).await;
+
+    Ok(())
+}
+
+/// Changes the role, which can view configuration without MANAGE_CHANNELS. Leave empty to remove it
+#[poise::command(
+    slash_command,
+    guild_cooldown = 30,
+    rename = "observer-role",
+    check = "admin_check"
+)]
+async fn observer_role(
+    ctx: Context<'_>,
+    #[description = "Role of members, who can run read-only commands"] role: Option<Role>,
+) -> Result<(), Error> {
+    let server_string = match ctx.guild() {
+        Some(server) => {
+            format!("{} ({})", server.name, server.id)
+        }
+        None => "UNKNOWN".to_string(),
+    };
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!(
+            "[server {}] Failed to defer ephemeral reply: {}",
+            server_string,
+            err,
+        );
+    };
+    let mut config_lock = ctx.data().config.write().await;
+    let mut entry = match get_server_config_entry(ctx.guild_id(), &mut config_lock) {
+        Ok(entry) => entry,
+        Err(err) => {
+            simple_reply_text(ctx, true, err.to_string()).await;
+            return Ok(());
+        }
+    };
+
+    let mut new_server_config = entry.get().clone();
+    remember_server_config(ctx, Some(entry.get().clone())).await;
+    new_server_config.observer_role = role.as_ref().map(|role| role.id);
+    entry.insert(new_server_config);
+
+    match &role {
+        Some(role) => {
+            log::info!(
+                target: EVENTS_TARGET,
+                "[server {}] User {} ({}) changed observer role to {} ({})",
+                server_string,
+                ctx.author().name,
+                ctx.author().id,
+                role.name,
+                role.id
+            );
+            audit(ctx).await;
+            simple_reply_text(
+                ctx,
+                true,
+                format!("Changed observer role to <@&{}>!", role.id),
+            )
+            .await;
+        }
+        None => {
+            log::info!(
+                target: EVENTS_TARGET,
+                "[server {}] User {} ({}) removed observer role",
+                server_string,
+                ctx.author().name,
+                ctx.author().id
+            );
+            audit(ctx).await;
+            simple_reply_text(ctx, true, "Removed observer role!".to_string()).await;
+        }
+    }
+
+    drop(config_lock);
+
+    save_data(ctx.data()).await;
+
+    Ok(())
+}
+
+/// Changes the role, which can change configuration without MANAGE_CHANNELS. Leave empty to remove it
+#[poise::command(
+    slash_command,
+    guild_cooldown = 30,
+    rename = "admin-role",
+    required_permissions = "MANAGE_CHANNELS"
+)]
+async fn admin_role(
+    ctx: Context<'_>,
+    #[description = "Role of members, who can configure the bot"] role: Option<Role>,
+) -> Result<(), Error> {
+    let server_string = match ctx.guild() {
+        Some(server) => {
+            format!("{} ({})", server.name, server.id)
+        }
+        None => "UNKNOWN".to_string(),
+    };
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!(
+            "[server {}] Failed to defer ephemeral reply: {}",
+            server_string,
+            err,
+        );
+    };
+    let mut config_lock = ctx.data().config.write().await;
+    let mut entry = match get_server_config_entry(ctx.guild_id(), &mut config_lock) {
+        Ok(entry) => entry,
+        Err(err) => {
+            simple_reply_text(ctx, true, err.to_string()).await;
+            return Ok(());
+        }
+    };
+
+    let mut new_server_config = entry.get().clone();
+    remember_server_config(ctx, Some(entry.get().clone())).await;
+    new_server_config.admin_role = role.as_ref().map(|role| role.id);
+    entry.insert(new_server_config);
+
+    match &role {
+        Some(role) => {
+            log::info!(
+                target: EVENTS_TARGET,
+                "[server {}] User {} ({}) changed admin role to {} ({})",
+                server_string,
+                ctx.author().name,
+                ctx.author().id,
+                role.name,
+                role.id
+            );
+            audit(ctx).await;
+            simple_reply_text(ctx, true, format!("Changed admin role to <@&{}>!", role.id)).await;
+        }
+        None => {
+            log::info!(
+                target: EVENTS_TARGET,
+                "[server {}] User {} ({}) removed admin role",
+                server_string,
+                ctx.author().name,
+                ctx.author().id
+            );
+            audit(ctx).await;
+            simple_reply_text(ctx, true, "Removed admin role!".to_string()).await;
+        }
+    }
+
+    drop(config_lock);
+
+    save_data(ctx.data()).await;
+
+    Ok(())
+}
+
+/// Shows configuration of this server
+#[poise::command(slash_command, guild_cooldown = 10, check = "observer_check")]
+async fn show(ctx: Context<'_>) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    let config_lock = ctx.data().config.read().await;
+    let server_config = match ctx.guild_id() {
+        Some(id) => match config_lock.server_configs.get(&id) {
+            Some(server_config) => server_config.clone(),
+            None => {
+                simple_reply_text(
+                    ctx,
+                    true,
+                    "Your server is not registered yet! Use */server register*!".to_string(),
+                )
+                .await;
+                return Ok(());
+            }
+        },
+        None => {
+            simple_reply_text(
+                ctx,
+                true,
+                "You need to be within a server to execute this command!".to_string(),
+            )
+            .await;
+            return Ok(());
+        }
+    };
+    let colour = config_lock.admin_embed_colour();
+    drop(config_lock);
+
+    let setup = if server_config.is_setup_complete() {
+        "Complete".to_string()
+    } else {
+        format!(
+            "Incomplete: {} not set",
+            server_config.missing_setup().join(" and ")
+        )
+    };
+    let embed = CreateEmbed::new()
+        .colour(colour)
+        .title(format!("Configuration of {}", server_config.name))
+        .fields(vec![
+            ("Setup", setup, false),
+            (
+                "Channel",
+                server_config
+                    .channel
+                    .map_or("Not set".to_string(), |id| format!("<#{}>", id)),
+                true,
+            ),
+            (
+                "Role",
+                server_config
+                    .role_to_notify
+                    .map_or("Not set".to_string(), |id| format!("<@&{}>", id)),
+                true,
+            ),
+            (
+                "Observer role",
+                server_config
+                    .observer_role
+                    .map_or("Not set".to_string(), |id| format!("<@&{}>", id)),
+                true,
+            ),
+            (
+                "Admin role",
+                server_config
+                    .admin_role
+                    .map_or("Not set".to_string(), |id| format!("<@&{}>", id)),
+                true,
+            ),
+            (
+                "Up message",
+                server_config.message(TransitionKind::Up).to_string(),
+                false,
+            ),
+            (
+                "Down message",
+                server_config.message(TransitionKind::Down).to_string(),
+                false,
+            ),
+            (
+                "Mention role on",
+                format!(
+                    "Up: {}, Down: {}",
+                    server_config.ping_on_up, server_config.ping_on_down
+                ),
+                false,
+            ),
+            (
+                "Mention cooldown",
+                humantime::format_duration(server_config.mention_cooldown).to_string(),
+                false,
+            ),
+            ("Up is bad news", server_config.inverted.to_string(), false),
+            (
+                "Pin status message",
+                server_config.pin_status_message.to_string(),
+                false,
+            ),
+            (
+                "Clean up messages",
+                server_config.cleanup_messages.to_string(),
+                false,
+            ),
+        ]);
+
+    simple_reply_embed(ctx, true, embed).await;
+
+    Ok(())
+}
+
+/// Shows hardcoded defaults next to the current values
+#[poise::command(
+    slash_command,
+    guild_cooldown = 10,
+    rename = "list-defaults",
+    check = "observer_check"
+)]
+async fn list_defaults(ctx: Context<'_>) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    let config_lock = ctx.data().config.read().await;
+    let ping_config = config_lock.ping_config.clone();
+    let server_config = ctx
+        .guild_id()
+        .and_then(|id| config_lock.server_configs.get(&id).cloned());
+    let colour = config_lock.admin_embed_colour();
+    drop(config_lock);
+
+    let default_ping_config = PingConfig::default();
+    let default_server_config = ServerConfig::default();
+    let compare = |default: String, current: String| {
+        format!("Default: `{}`\nCurrent: `{}`", default, current)
+    };
+    let duration = |duration: Duration| humantime::format_duration(duration).to_string();
+    let active_hours = |active_hours: Option<ActiveHours>| {
+        active_hours.map_or("Always".to_string(), |active_hours| {
+            active_hours.to_string()
+        })
+    };
+    let compare_message = |default: &ServerConfig, current: &ServerConfig, kind| {
+        compare(
+            default.message(kind).to_string(),
+            current.message(kind).to_string(),
+        )
+    };
+
+    let mut fields = vec![
+        (
+            "Resource name",
+            compare(default_ping_config.resource_name, ping_config.resource_name),
+        ),
+        (
+            "Resource address",
+            compare(default_ping_config.resource_addr, ping_config.resource_addr),
+        ),
+        (
+            "Extra probes",
+            compare(
+                format!("{:?}", default_ping_config.extra_probe_addrs),
+                format!("{:?}", ping_config.extra_probe_addrs),
+            ),
+        ),
+        (
+            "Quorum",
+            compare(
+                format!("{:?}", default_ping_config.quorum),
+                format!("{:?}", ping_config.quorum),
+            ),
+        ),
+        (
+            "Check kind",
+            compare(
+                format!("{:?}", default_ping_config.check_kind),
+                format!("{:?}", ping_config.check_kind),
+            ),
+        ),
+        (
+            "Required attempts",
+            compare(
+                default_ping_config
+                    .required_attempts_before_notification
+                    .to_string(),
+                ping_config
+                    .required_attempts_before_notification
+                    .to_string(),
+            ),
+        ),
+        (
+            "Timeout",
+            compare(
+                duration(default_ping_config.timeout),
+                duration(ping_config.timeout),
+            ),
+        ),
+        (
+            "DNS timeout",
+            compare(
+                duration(default_ping_config.dns_timeout),
+                duration(ping_config.dns_timeout),
+            ),
+        ),
+        (
+            "DNS slow threshold",
+            compare(
+                default_ping_config
+                    .dns_slow_threshold
+                    .map_or("Disabled".to_string(), duration),
+                ping_config
+                    .dns_slow_threshold
+                    .map_or("Disabled".to_string(), duration),
+            ),
+        ),
+        (
+            "Interval",
+            compare(
+                duration(default_ping_config.interval_between_attempts),
+                duration(ping_config.interval_between_attempts),
+            ),
+        ),
+        (
+            "Check retries",
+            compare(
+                default_ping_config.check_retries.to_string(),
+                ping_config.check_retries.to_string(),
+            ),
+        ),
+        (
+            "Check concurrency",
+            compare(
+                default_ping_config.check_concurrency.to_string(),
+                ping_config.check_concurrency.to_string(),
+            ),
+        ),
+        (
+            "Max outstanding checks",
+            compare(
+                default_ping_config.max_outstanding_checks.to_string(),
+                ping_config.max_outstanding_checks.to_string(),
+            ),
+        ),
+        (
+            "Warmup ping",
+            compare(
+                default_ping_config.warmup_ping.to_string(),
+                ping_config.warmup_ping.to_string(),
+            ),
+        ),
+        (
+            "Dual stack",
+            compare(
+                default_ping_config.dual_stack.to_string(),
+                ping_config.dual_stack.to_string(),
+            ),
+        ),
+        (
+            "Resolve SRV",
+            compare(
+                default_ping_config.resolve_srv.to_string(),
+                ping_config.resolve_srv.to_string(),
+            ),
+        ),
+        (
+            "RTT smoothing",
+            compare(
+                default_ping_config.rtt_smoothing.to_string(),
+                ping_config.rtt_smoothing.to_string(),
+            ),
+        ),
+        (
+            "Active hours",
+            compare(
+                active_hours(default_ping_config.active_hours),
+                active_hours(ping_config.active_hours),
+            ),
+        ),
+        (
+            "Min stable duration",
+            compare(
+                duration(default_ping_config.min_stable_duration),
+                duration(ping_config.min_stable_duration),
+            ),
+        ),
+        (
+            "Down check spacing",
+            compare(
+                duration(default_ping_config.down_check_spacing),
+                duration(ping_config.down_check_spacing),
+            ),
+        ),
+        (
+            "Self restart after errors",
+            compare(
+                format!("{:?}", default_ping_config.self_restart_after_errors),
+                format!("{:?}", ping_config.self_restart_after_errors),
+            ),
+        ),
+        (
+            "Notify master before self restart",
+            compare(
+                default_ping_config.self_restart_notify_master.to_string(),
+                ping_config.self_restart_notify_master.to_string(),
+            ),
+        ),
+    ];
+    if let Some(server_config) = server_config {
+        fields.extend([
+            (
+                "Up message",
+                compare_message(&default_server_config, &server_config, TransitionKind::Up),
+            ),
+            (
+                "Down message",
+                compare_message(&default_server_config, &server_config, TransitionKind::Down),
+            ),
+            (
+                "Maintenance start message",
+                compare_message(
+                    &default_server_config,
+                    &server_config,
+                    TransitionKind::MaintenanceStart,
+                ),
+            ),
+            (
+                "Maintenance end message",
+                compare_message(
+                    &default_server_config,
+                    &server_config,
+                    TransitionKind::MaintenanceEnd,
+                ),
+            ),
+            (
+                "Status embed",
+                compare(
+                    default_server_config.embed_enabled.to_string(),
+                    server_config.embed_enabled.to_string(),
+                ),
+            ),
+            (
+                "Embed labels",
+                compare(
+                    format!(
+                        "{}, {}, {}",
+                        default_server_config.since_label,
+                        default_server_config.address_label,
+                        default_server_config.latency_label
+                    ),
+                    format!(
+                        "{}, {}, {}",
+                        server_config.since_label,
+                        server_config.address_label,
+                        server_config.latency_label
+                    ),
+                ),
+            ),
+            (
+                "Mention role on",
+                compare(
+                    format!(
+                        "Up: {}, Down: {}",
+                        default_server_config.ping_on_up, default_server_config.ping_on_down
+                    ),
+                    format!(
+                        "Up: {}, Down: {}",
+                        server_config.ping_on_up, server_config.ping_on_down
+                    ),
+                ),
+            ),
+            (
+                "Mention cooldown",
+                compare(
+                    duration(default_server_config.mention_cooldown),
+                    duration(server_config.mention_cooldown),
+                ),
+            ),
+            (
+                "Up is bad news",
+                compare(
+                    default_server_config.inverted.to_string(),
+                    server_config.inverted.to_string(),
+                ),
+            ),
+            (
+                "Pin status message",
+                compare(
+                    default_server_config.pin_status_message.to_string(),
+                    server_config.pin_status_message.to_string(),
+                ),
+            ),
+            (
+                "Clean up messages",
+                compare(
+                    default_server_config.cleanup_messages.to_string(),
+                    server_config.cleanup_messages.to_string(),
+                ),
+            ),
+        ]);
+    }
+
+    let embed = CreateEmbed::new()
+        .colour(colour)
+        .title("Defaults")
+        .description("Master server's */config reset* loads Config.toml if it exists, falling back to these defaults")
+        .fields(fields.into_iter().map(|(name, value)| (name, value, false)));
+
+    simple_reply_embed(ctx, true, embed).await;
+
+    Ok(())
+}
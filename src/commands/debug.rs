@@ -1,16 +1,39 @@
-use poise::serenity_prelude::{CreateAttachment, CreateEmbed, Timestamp};
+use std::{
+    io::{Cursor, Write},
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+
+use poise::{
+    CreateReply, send_reply,
+    serenity_prelude::{CreateAttachment, CreateEmbed, Timestamp},
+};
+use zip::{ZipWriter, write::SimpleFileOptions};
 
 use crate::{
-    Context, DEFAULT_LOG_PATH, DEFAULT_REPOSITORY, DEFAULT_SAVEDATA_PATH, Error, THIS_RUN_START,
-    commands::{master_check, simple_reply_attachment, simple_reply_embed, simple_reply_text},
+    Context, DEFAULT_AUDIT_PATH, DEFAULT_CONFIG_PATH, DEFAULT_LOG_PATH, DEFAULT_REPOSITORY,
+    DEFAULT_SAVEDATA_PATH, Error, THIS_RUN_START,
+    audit::AuditEntry,
+    commands::{
+        guild_check, master_check, simple_reply_attachment, simple_reply_embed, simple_reply_text,
+    },
+    events::EVENTS_TARGET,
+    ping::{Health, parse_ports, resolve_ip, scan_ports},
+    save_data, status,
 };
 
+const EMBED_DESCRIPTION_LIMIT: usize = 4096;
+/// How much of the log's end goes into the diagnostics bundle, so it fits into Discord's attachment limit
+const BUNDLE_LOG_TAIL_BYTES: usize = 2 * 1024 * 1024;
+const BUNDLE_FILE_NAME: &str = "diagnostics.zip";
+
 /// Displays information about the bot
 #[poise::command(slash_command, user_cooldown = 10)]
 pub async fn info(ctx: Context<'_>) -> Result<(), Error> {
     if let Err(err) = ctx.defer().await {
         log::error!("Failed to defer reply: {}", err);
     };
+    let colour = ctx.data().config.read().await.admin_embed_colour();
     simple_reply_embed(
         ctx,
         false,
@@ -19,7 +42,7 @@ pub async fn info(ctx: Context<'_>) -> Result<(), Error> {
                 "**Discord Watchdog v{}**",
                 env!("CARGO_PKG_VERSION")
             ))
-            .colour((45, 114, 178))
+            .colour(colour)
             .fields(vec![
                 (
                     "Running since",
@@ -47,7 +70,21 @@ pub async fn info(ctx: Context<'_>) -> Result<(), Error> {
 #[poise::command(
     slash_command,
     default_member_permissions = "MANAGE_CHANNELS",
-    subcommands("logs", "data")
+    subcommands(
+        "logs",
+        "data",
+        "bundle",
+        "ping",
+        "audit",
+        "refresh_embeds",
+        "override_address",
+        "cancel_override",
+        "portscan",
+        "selftest",
+        "stats",
+        "state"
+    ),
+    check = "guild_check"
 )]
 pub async fn debug(_: Context<'_>) -> Result<(), Error> {
     Ok(())
@@ -143,3 +180,764 @@ async fn data(ctx: Context<'_>) -> Result<(), Error> {
 
     Ok(())
 }
+
+/// [M ONLY] Sends saved data, config, the end of the log and environment summary in one archive
+#[poise::command(slash_command, guild_cooldown = 60)]
+async fn bundle(ctx: Context<'_>) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    log::info!(
+        "User {} ({}) requested diagnostics bundle",
+        ctx.author().name,
+        ctx.author().id
+    );
+    let mut files = vec![(
+        "environment.txt".to_string(),
+        environment_summary().into_bytes(),
+    )];
+    for path in [DEFAULT_SAVEDATA_PATH, DEFAULT_CONFIG_PATH, DEFAULT_LOG_PATH] {
+        match tokio::fs::read(path).await {
+            Ok(mut contents) => {
+                if path == DEFAULT_LOG_PATH {
+                    contents.drain(..contents.len().saturating_sub(BUNDLE_LOG_TAIL_BYTES));
+                }
+                files.push((path.to_string(), contents));
+            }
+            Err(err) => {
+                log::warn!("Skipped {} in diagnostics bundle: {}", path, err);
+                files.push((format!("{}.missing", path), err.to_string().into_bytes()));
+            }
+        }
+    }
+
+    match zip_files(files) {
+        Ok(archive) => {
+            simple_reply_attachment(
+                ctx,
+                true,
+                CreateAttachment::bytes(archive, BUNDLE_FILE_NAME),
+            )
+            .await
+        }
+        Err(err) => {
+            log::error!("Failed to build diagnostics bundle: {}", err);
+            simple_reply_text(
+                ctx,
+                true,
+                format!("Failed to build diagnostics bundle: {}", err),
+            )
+            .await;
+        }
+    }
+
+    Ok(())
+}
+
+fn environment_summary() -> String {
+    let run_start = THIS_RUN_START.get().copied().unwrap_or_else(Timestamp::now);
+    let uptime = (Timestamp::now().unix_timestamp() - run_start.unix_timestamp()).max(0);
+    format!(
+        "Discord Watchdog v{}\nPlatform: {} {}\nRunning since: {}\nUptime: {}\nGenerated at: {}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        run_start,
+        humantime::format_duration(Duration::from_secs(uptime as u64)),
+        Timestamp::now()
+    )
+}
+
+fn zip_files(files: Vec<(String, Vec<u8>)>) -> zip::result::ZipResult<Vec<u8>> {
+    let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, contents) in files {
+        archive.start_file(name, SimpleFileOptions::default())?;
+        archive.write_all(&contents)?;
+    }
+    Ok(archive.finish()?.into_inner())
+}
+
+/// [M ONLY] Checks an arbitrary address with current settings, without changing the config
+#[poise::command(slash_command, guild_cooldown = 10)]
+async fn ping(
+    ctx: Context<'_>,
+    #[description = "Address (IP or DNS) to check"] address: String,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let ping_config = ctx.data().config.read().await.ping_config.clone();
+    let response = ctx
+        .data()
+        .ping_client
+        .check_addr(&address, &ping_config)
+        .await;
+    log::info!(
+        "User {} ({}) checked {}: {:?}",
+        ctx.author().name,
+        ctx.author().id,
+        address,
+        response
+    );
+
+    let reply = match response {
+        Ok(Health::Up { rtt: Some(rtt) }) => {
+            format!("`{}` is up, RTT is {} ms", address, rtt.as_millis())
+        }
+        Ok(Health::Up { rtt: None }) => {
+            format!("`{}` is up, but didn't respond to the probe", address)
+        }
+        Ok(Health::Down) => format!("`{}` is down", address),
+        Ok(Health::ServiceDown) => format!(
+            "`{}` responds to ping, but its service doesn't accept connections",
+            address
+        ),
+        Err(err) => format!("Failed to check `{}`: {}", address, err),
+    };
+    simple_reply_text(ctx, true, reply).await;
+
+    Ok(())
+}
+
+/// [M ONLY] Checks every stage of notifying without posting anything: check, embeds and each server
+#[poise::command(slash_command, guild_cooldown = 60)]
+async fn selftest(ctx: Context<'_>) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let ping_config = ctx.data().effective_ping_config(Instant::now()).await;
+    let config_lock = ctx.data().config.read().await;
+    let server_configs = config_lock.server_configs.clone();
+    let colour = config_lock.admin_embed_colour();
+    drop(config_lock);
+    let addr = &ping_config.resource_addr;
+    let mut passed = true;
+
+    let resolution = match resolve_ip(addr, ping_config.dns_timeout).await {
+        Ok(ip) => format!("✅ `{}` resolves to `{}`", addr, ip),
+        Err(err) => {
+            passed = false;
+            format!("❌ {}", err)
+        }
+    };
+    let healthcheck = match ctx.data().ping_client.check(&ping_config).await {
+        Ok(Health::Up { rtt: Some(rtt) }) => format!("✅ Up, RTT is {} ms", rtt.as_millis()),
+        Ok(Health::Up { rtt: None }) => "✅ Up, but didn't respond to the probe".to_string(),
+        Ok(Health::Down) => {
+            passed = false;
+            "❌ Down. Either the resource is offline, or probes are blocked".to_string()
+        }
+        Ok(Health::ServiceDown) => {
+            passed = false;
+            "❌ Down. The host responds to ping, but its service doesn't accept connections"
+                .to_string()
+        }
+        Err(err) => {
+            passed = false;
+            format!("❌ {}", err)
+        }
+    };
+    let previews = match ctx
+        .guild_id()
+        .and_then(|server_id| Some((server_id, server_configs.get(&server_id)?)))
+    {
+        Some((server_id, server_config)) => {
+            status::preview_embeds(server_id, server_config, &ping_config.resource_name, addr).await
+        }
+        None => Vec::new(),
+    };
+    let embeds = if previews.is_empty() {
+        "Skipped, this server is not registered".to_string()
+    } else {
+        "✅ Up and down embeds of this server are shown below".to_string()
+    };
+
+    let mut notifiable = 0;
+    let mut subscribers = Vec::new();
+    for (server_id, server_config) in &server_configs {
+        let problems = status::preflight_problems(
+            *server_id,
+            server_config,
+            &ping_config.resource_name,
+            ctx.framework().bot_id,
+            ctx.http(),
+        )
+        .await;
+        if problems.is_empty() {
+            notifiable += 1;
+            subscribers.push(format!(
+                "✅ {} ({}): would be notified in <#{}>\n",
+                server_config.name,
+                server_id,
+                server_config.channel.unwrap_or_default()
+            ));
+        } else {
+            subscribers.push(format!(
+                "❌ {} ({}): {}\n",
+                server_config.name,
+                server_id,
+                problems.join("; ")
+            ));
+        }
+    }
+    passed &= notifiable == server_configs.len();
+    // Failures first, so they survive truncation
+    subscribers.sort_by_key(|line| line.starts_with('✅'));
+    let mut description = format!(
+        "**{}/{} servers can be notified**\n",
+        notifiable,
+        server_configs.len()
+    );
+    for line in subscribers {
+        if description.chars().count() + line.chars().count() > EMBED_DESCRIPTION_LIMIT {
+            break;
+        }
+        description.push_str(&line);
+    }
+    log::info!(
+        "User {} ({}) ran the self-test, which {}",
+        ctx.author().name,
+        ctx.author().id,
+        if passed { "passed" } else { "failed" }
+    );
+
+    let report = CreateEmbed::new()
+        .title(if passed {
+            "Self-test passed"
+        } else {
+            "Self-test failed"
+        })
+        .colour(colour)
+        .description(description)
+        .fields(vec![
+            ("Resolution", resolution, false),
+            ("Healthcheck", healthcheck, false),
+            ("Embeds", embeds, false),
+        ]);
+    let reply = previews.into_iter().fold(
+        CreateReply::default().ephemeral(true).embed(report),
+        |reply, embed| reply.embed(embed),
+    );
+    if let Err(err) = send_reply(ctx, reply).await {
+        log::error!("Failed to send self-test report: {}", err);
+    }
+
+    Ok(())
+}
+
+/// [M ONLY] Checks which of the listed TCP ports accept connections, to find the one worth monitoring
+#[poise::command(slash_command, guild_cooldown = 60)]
+async fn portscan(
+    ctx: Context<'_>,
+    #[description = "Address (IP or DNS) to check"] address: String,
+    #[description = "Ports and ranges, e.g. \"80,443,27015-27017\". At most 16"] ports: String,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+    let ports = match parse_ports(&ports) {
+        Ok(ports) => ports,
+        Err(err) => {
+            simple_reply_text(ctx, true, format!("Failed to parse {}: {}", ports, err)).await;
+            return Ok(());
+        }
+    };
+
+    let ping_config = ctx.data().config.read().await.ping_config.clone();
+    let ip = match resolve_ip(&address, ping_config.dns_timeout).await {
+        Ok(ip) => ip,
+        Err(err) => {
+            simple_reply_text(
+                ctx,
+                true,
+                format!("Failed to resolve `{}`: {}", address, err),
+            )
+            .await;
+            return Ok(());
+        }
+    };
+    let results = scan_ports(ip, &ports, ping_config.timeout).await;
+    let open: Vec<String> = results
+        .iter()
+        .filter(|(_, open)| *open)
+        .map(|(port, _)| port.to_string())
+        .collect();
+    log::info!(
+        "User {} ({}) scanned ports {:?} of {}, open: {:?}",
+        ctx.author().name,
+        ctx.author().id,
+        ports,
+        address,
+        open
+    );
+
+    let reply = if open.is_empty() {
+        format!(
+            "None of {} checked ports of `{}` ({}) accepted a connection within {}",
+            ports.len(),
+            address,
+            ip,
+            humantime::format_duration(ping_config.timeout)
+        )
+    } else {
+        format!(
+            "Open ports of `{}` ({}): {}. Other {} checked ports are closed or filtered",
+            address,
+            ip,
+            open.join(", "),
+            ports.len() - open.len()
+        )
+    };
+    simple_reply_text(ctx, true, reply).await;
+
+    Ok(())
+}
+
+/// [M ONLY] Checks another address instead of the resource's one for a while. Not saved
+#[poise::command(slash_command, guild_cooldown = 10, rename = "override-address")]
+async fn override_address(
+    ctx: Context<'_>,
+    #[description = "Address (IP or DNS) to check instead"] address: String,
+    #[description = "How long to check it, e.g. \"10m\""] duration: String,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+    let duration = match humantime::parse_duration(&duration) {
+        Ok(duration) => duration,
+        Err(err) => {
+            simple_reply_text(ctx, true, format!("Failed to parse {}: {}", duration, err)).await;
+            return Ok(());
+        }
+    };
+    let Some(until) = Instant::now().checked_add(duration) else {
+        simple_reply_text(
+            ctx,
+            true,
+            format!(
+                "{} is too long for an override!",
+                humantime::format_duration(duration)
+            ),
+        )
+        .await;
+        return Ok(());
+    };
+    let ping_config = ctx.data().config.read().await.ping_config.clone();
+    if let Err(err) = ctx
+        .data()
+        .ping_client
+        .resolve_addr(&address, &ping_config)
+        .await
+    {
+        simple_reply_text(
+            ctx,
+            true,
+            format!("Failed to resolve `{}`: {}", address, err),
+        )
+        .await;
+        return Ok(());
+    }
+
+    ctx.data().override_address(address.clone(), until).await;
+    let duration = humantime::format_duration(duration);
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) overrode the resource address with {} for {}",
+        ctx.author().name,
+        ctx.author().id,
+        address,
+        duration
+    );
+
+    simple_reply_text(
+        ctx,
+        true,
+        format!(
+            "Checking `{}` for {}! The configured address is restored afterwards or on restart",
+            address, duration
+        ),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// [M ONLY] Checks the configured address again, before the override expires
+#[poise::command(slash_command, guild_cooldown = 10, rename = "cancel-override")]
+async fn cancel_override(ctx: Context<'_>) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+    let reply = match ctx.data().clear_address_override().await {
+        Some(overriding) => {
+            log::info!(
+                target: EVENTS_TARGET,
+                "User {} ({}) cancelled the override of the resource address with {}",
+                ctx.author().name,
+                ctx.author().id,
+                overriding
+            );
+            format!(
+                "Stopped checking `{}`, the configured address is checked again!",
+                overriding
+            )
+        }
+        None => "The resource address isn't overridden".to_string(),
+    };
+    simple_reply_text(ctx, true, reply).await;
+
+    Ok(())
+}
+
+/// [M ONLY] Reposts the status embed in every server, so styling changes are visible right away
+#[poise::command(slash_command, guild_cooldown = 40, rename = "refresh-embeds")]
+async fn refresh_embeds(ctx: Context<'_>) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    log::info!(
+        "User {} ({}) requested to refresh status embeds",
+        ctx.author().name,
+        ctx.author().id
+    );
+    status::refresh_embeds(ctx.data().clone(), ctx.serenity_context().http.clone()).await;
+    save_data(ctx.data()).await;
+
+    simple_reply_text(
+        ctx,
+        true,
+        "Refreshed status embeds! Servers, which failed to get one, are listed in the logs"
+            .to_string(),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// [M ONLY] Shows recent configuration changes made by commands
+#[poise::command(slash_command, guild_cooldown = 10)]
+async fn audit(
+    ctx: Context<'_>,
+    #[description = "How many recent entries to show"]
+    #[min = 1]
+    #[max = 50]
+    count: usize,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let entries = match AuditEntry::load_recent(&DEFAULT_AUDIT_PATH, count).await {
+        Ok(entries) => entries,
+        Err(err) => {
+            log::error!("Failed to load audit trail: {}", err);
+            simple_reply_text(ctx, true, format!("Failed to load audit trail: {}", err)).await;
+            return Ok(());
+        }
+    };
+    let colour = ctx.data().config.read().await.admin_embed_colour();
+    log::info!(
+        "User {} ({}) requested {} audit entries",
+        ctx.author().name,
+        ctx.author().id,
+        count
+    );
+    if entries.is_empty() {
+        simple_reply_text(ctx, true, "Audit trail is empty".to_string()).await;
+        return Ok(());
+    }
+
+    let mut description = String::new();
+    // Newest first, so the most relevant entries survive truncation
+    for entry in entries.iter().rev() {
+        let line = format!(
+            "<t:{}:f> <@{}> `/{}` {} (server {})\n",
+            entry.timestamp.unix_timestamp(),
+            entry.user_id,
+            entry.command,
+            entry.args,
+            entry
+                .guild
+                .map_or("UNKNOWN".to_string(), |guild| guild.to_string())
+        );
+        if description.chars().count() + line.chars().count() > EMBED_DESCRIPTION_LIMIT {
+            break;
+        }
+        description.push_str(&line);
+    }
+    simple_reply_embed(
+        ctx,
+        true,
+        CreateEmbed::new()
+            .title("Audit trail")
+            .colour(colour)
+            .description(description),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// [M ONLY] Shows how long up/down messages took to be delivered to each server since the bot started
+#[poise::command(slash_command, guild_cooldown = 10)]
+async fn stats(ctx: Context<'_>) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let mut latencies: Vec<_> = ctx.data().delivery_latencies().await.into_iter().collect();
+    if latencies.is_empty() {
+        simple_reply_text(
+            ctx,
+            true,
+            "No up/down messages were delivered since the bot started".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+    // Slowest first, since they are the ones worth looking into
+    latencies.sort_by_key(|(_, latency)| std::cmp::Reverse(latency.average()));
+    let config_lock = ctx.data().config.read().await;
+    let colour = config_lock.admin_embed_colour();
+    let mut description = String::new();
+    for (server_id, latency) in latencies {
+        let name = config_lock
+            .server_configs
+            .get(&server_id)
+            .map_or("Unregistered".to_string(), |server_config| {
+                server_config.name.clone()
+            });
+        let line = format!(
+            "**{}** ({}): average {:0.2?}, last {:0.2?}, worst {:0.2?} over {} messages\n",
+            name,
+            server_id,
+            latency.average(),
+            latency.last,
+            latency.worst,
+            latency.deliveries
+        );
+        if description.chars().count() + line.chars().count() > EMBED_DESCRIPTION_LIMIT {
+            break;
+        }
+        description.push_str(&line);
+    }
+    drop(config_lock);
+
+    simple_reply_embed(
+        ctx,
+        true,
+        CreateEmbed::new()
+            .title("Notification delivery latency")
+            .colour(colour)
+            .description(description),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// [M ONLY] Shows the internal state of the checks: status, counters, last check and active windows
+#[poise::command(slash_command, guild_cooldown = 10)]
+async fn state(ctx: Context<'_>) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let data = ctx.data();
+    let ping_config = data.effective_ping_config(Instant::now()).await;
+    let config_lock = data.config.read().await;
+    let colour = config_lock.admin_embed_colour();
+    let now = Timestamp::now();
+    let in_maintenance = config_lock.in_maintenance(now);
+    let in_startup_grace = data.in_startup_grace(&config_lock);
+    drop(config_lock);
+    let rtt = *data.rtt.read().await;
+    let format_rtt =
+        |rtt: Option<Duration>| rtt.map_or("none".to_string(), |rtt| format!("{:0.2?}", rtt));
+    let last_check = data
+        .last_check
+        .read()
+        .await
+        .map_or("never".to_string(), |last_check| {
+            format!("<t:{}:R>", last_check.unix_timestamp())
+        });
+    let active_hours = match &ping_config.active_hours {
+        Some(active_hours) => format!(
+            "{} ({})",
+            active_hours,
+            if ping_config.is_active(now) {
+                "active"
+            } else {
+                "inactive"
+            }
+        ),
+        None => "always active".to_string(),
+    };
+
+    let mut fields = vec![
+        (
+            "Status",
+            format!(
+                "{} since <t:{}:R>",
+                data.status.read().await,
+                data.last_status_change.read().await.unix_timestamp()
+            ),
+            false,
+        ),
+        ("Address", format!("`{}`", ping_config.resource_addr), false),
+        (
+            "Attempts towards a status change",
+            format!(
+                "{} of {}",
+                data.attempts_before_notification.load(Ordering::Relaxed),
+                ping_config.required_attempts_before_notification
+            ),
+            false,
+        ),
+        ("Last check", last_check, false),
+        (
+            "RTT",
+            format!(
+                "last {}, smoothed {}",
+                format_rtt(rtt.last()),
+                format_rtt(rtt.get())
+            ),
+            false,
+        ),
+        (
+            "Consecutive errors",
+            format!(
+                "{}, last cause: {:?}",
+                data.consecutive_errors.load(Ordering::Relaxed),
+                *data.unknown_cause.read().await
+            ),
+            false,
+        ),
+        (
+            "Windows",
+            format!(
+                "Startup grace: {}\nMaintenance: {}\nActive hours: {}",
+                in_startup_grace, in_maintenance, active_hours
+            ),
+            false,
+        ),
+    ];
+    if let Some(probe) = &ping_config.linked_probe {
+        fields.push((
+            "Linked probe",
+            format!("`{}` is {}", probe.addr, data.linked_status.read().await),
+            false,
+        ));
+    }
+    log::info!(
+        "User {} ({}) checked the check state",
+        ctx.author().name,
+        ctx.author().id,
+    );
+
+    simple_reply_embed(
+        ctx,
+        true,
+        CreateEmbed::new()
+            .title(format!("{} check state", ping_config.resource_name))
+            .colour(colour)
+            .fields(fields),
+    )
+    .await;
+
+    Ok(())
+}
@@ -1,24 +1,79 @@
-use poise::serenity_prelude::{CreateEmbed, GuildId};
+use std::time::Duration;
+
+use poise::{
+    CreateReply,
+    futures_util::StreamExt,
+    serenity_prelude::{
+        ChannelType, ComponentInteractionCollector, ComponentInteractionDataKind, CreateActionRow,
+        CreateEmbed, CreateEmbedFooter, CreateInteractionResponse,
+        CreateInteractionResponseMessage, CreateSelectMenu, CreateSelectMenuKind, GuildId,
+    },
+};
 
 use crate::{
-    Context, Error, ServerConfig,
+    Config, Context, Error, ServerConfig,
     commands::{
-        get_server_config_vacant_entry, master_check, simple_reply_embed, simple_reply_text,
+        audit, audit_with_args, get_server_config_vacant_entry, guild_check, master_check,
+        remember_config, remember_config_and_servers, remember_server_config, simple_reply_embed,
+        simple_reply_text,
     },
+    events::EVENTS_TARGET,
+    guilds::leave_guild,
     save_data,
 };
 
+/// How long the user has to pick channel and role after registration
+const SETUP_PROMPT_TIMEOUT: Duration = Duration::from_secs(120);
+
 #[derive(poise::ChoiceParameter, Debug, Clone, Copy)]
 enum RemovalOptions {
     All,
     One,
 }
 
+#[derive(poise::ChoiceParameter, Debug, Clone, Copy)]
+enum AllowlistAction {
+    Allow,
+    Disallow,
+    #[name = "Allow all servers"]
+    AllowAll,
+}
+
+#[derive(poise::ChoiceParameter, Debug, Clone, Copy)]
+enum ServerOrder {
+    Id,
+    Name,
+    Registration,
+}
+
+#[derive(poise::ChoiceParameter, Debug, Clone, Copy)]
+enum ServerFilter {
+    All,
+    #[name = "Incomplete setup"]
+    IncompleteSetup,
+    #[name = "Missing channel"]
+    MissingChannel,
+    #[name = "Missing role"]
+    MissingRole,
+}
+
+impl ServerFilter {
+    fn matches(self, server_config: &ServerConfig) -> bool {
+        match self {
+            ServerFilter::All => true,
+            ServerFilter::IncompleteSetup => !server_config.missing_setup().is_empty(),
+            ServerFilter::MissingChannel => server_config.channel.is_none(),
+            ServerFilter::MissingRole => server_config.role_to_notify.is_none(),
+        }
+    }
+}
+
 /// Base server command. Can not be called directly.
 #[poise::command(
     slash_command,
     default_member_permissions = "MANAGE_CHANNELS",
-    subcommands("register", "limit", "show", "remove")
+    subcommands("register", "add", "allow", "limit", "show", "remove"),
+    check = "guild_check"
 )]
 pub async fn server(_: Context<'_>) -> Result<(), Error> {
     Ok(())
@@ -59,24 +114,345 @@ async fn register(ctx: Context<'_>) -> Result<(), Error> {
     };
 
     entry.insert(ServerConfig::with_name(server_name));
+    remember_server_config(ctx, None).await;
+    warn_if_nearing_limit(&config_lock);
 
     log::info!(
+        target: EVENTS_TARGET,
         "[server {}] server registered by  {} ({})",
         server_string,
         ctx.author().name,
         ctx.author().id,
     );
+
+    audit(ctx).await;
+
+    drop(config_lock);
+
+    save_data(ctx.data()).await;
+
+    prompt_setup(ctx, &server_string).await;
+
+    Ok(())
+}
+
+/// Asks the user, who registered the server, to pick notification channel and role right away
+async fn prompt_setup(ctx: Context<'_>, server_string: &str) {
+    let Some(server_id) = ctx.guild_id() else {
+        return;
+    };
+    let channel_select_id = format!("{}-setup-channel", ctx.id());
+    let role_select_id = format!("{}-setup-role", ctx.id());
+    let reply = CreateReply::default()
+        .ephemeral(true)
+        .content("Server registered! Pick a channel for notifications and a role to ping. You can change them later with */config channel* and */config role*. Also consider using */config message*")
+        .components(vec![
+            CreateActionRow::SelectMenu(
+                CreateSelectMenu::new(
+                    &channel_select_id,
+                    CreateSelectMenuKind::Channel {
+                        channel_types: Some(vec![ChannelType::Text, ChannelType::News]),
+                        default_channels: None,
+                    },
+                )
+                .placeholder("Notification channel"),
+            ),
+            CreateActionRow::SelectMenu(
+                CreateSelectMenu::new(&role_select_id, CreateSelectMenuKind::Role { default_roles: None })
+                    .placeholder("Role to notify"),
+            ),
+        ]);
+    let reply_handle = match ctx.send(reply).await {
+        Ok(handle) => handle,
+        Err(err) => {
+            log::error!(
+                "[server {}] Failed to send setup prompt: {}",
+                server_string,
+                err
+            );
+            return;
+        }
+    };
+
+    let mut interactions = ComponentInteractionCollector::new(ctx)
+        .author_id(ctx.author().id)
+        .custom_ids(vec![channel_select_id, role_select_id])
+        .timeout(SETUP_PROMPT_TIMEOUT)
+        .stream();
+    let mut setup_complete = false;
+    while let Some(interaction) = interactions.next().await {
+        let mut config_lock = ctx.data().config.write().await;
+        let Some(server_config) = config_lock.server_configs.get_mut(&server_id) else {
+            break;
+        };
+        let mut response = CreateInteractionResponse::Acknowledge;
+        match &interaction.data.kind {
+            ComponentInteractionDataKind::ChannelSelect { values } => {
+                if let Some(channel_id) = values.first() {
+                    remember_server_config(ctx, Some(server_config.clone())).await;
+                    server_config.channel = Some(*channel_id);
+                    log::info!(
+                        target: EVENTS_TARGET,
+                        "[server {}] User {} ({}) changed channel to {} during setup",
+                        server_string,
+                        ctx.author().name,
+                        ctx.author().id,
+                        channel_id
+                    );
+                    audit_with_args(ctx, format!("channel={}", channel_id)).await;
+                }
+            }
+            ComponentInteractionDataKind::RoleSelect { values } => {
+                if let Some(role_id) = values.first() {
+                    let role = ctx.guild().and_then(|guild| {
+                        guild
+                            .roles
+                            .get(role_id)
+                            .map(|role| (role.name.clone(), role.mentionable))
+                    });
+                    if let Some((role_name, false)) = role {
+                        log::info!(
+                            "[server {}] User {} ({}) picked unmentionable role {} during setup",
+                            server_string,
+                            ctx.author().name,
+                            ctx.author().id,
+                            role_id
+                        );
+                        response = CreateInteractionResponse::Message(
+                            CreateInteractionResponseMessage::new()
+                                .ephemeral(true)
+                                .content(format!("{} can not be mentioned!", role_name)),
+                        );
+                    } else {
+                        remember_server_config(ctx, Some(server_config.clone())).await;
+                        server_config.role_to_notify = Some(*role_id);
+                        log::info!(
+                            target: EVENTS_TARGET,
+                            "[server {}] User {} ({}) changed mentionable role to {} during setup",
+                            server_string,
+                            ctx.author().name,
+                            ctx.author().id,
+                            role_id
+                        );
+                        audit_with_args(ctx, format!("role={}", role_id)).await;
+                    }
+                }
+            }
+            _ => {}
+        }
+        setup_complete = server_config.is_setup_complete();
+        drop(config_lock);
+
+        save_data(ctx.data()).await;
+
+        if let Err(err) = interaction.create_response(ctx, response).await {
+            log::error!(
+                "[server {}] Failed to acknowledge setup selection: {}",
+                server_string,
+                err
+            );
+        }
+        if setup_complete {
+            break;
+        }
+    }
+
+    let text = if setup_complete {
+        "Server registered and set up!".to_string()
+    } else {
+        let missing = match ctx
+            .data()
+            .config
+            .read()
+            .await
+            .server_configs
+            .get(&server_id)
+        {
+            Some(server_config) => server_config.missing_setup().join(" and "),
+            None => return,
+        };
+        format!(
+            "Server registered, but setup is incomplete: {} not set! Use */config show* to check it later",
+            missing
+        )
+    };
+    if let Err(err) = reply_handle
+        .edit(ctx, CreateReply::default().content(text).components(vec![]))
+        .await
+    {
+        log::error!(
+            "[server {}] Failed to finish setup prompt: {}",
+            server_string,
+            err
+        );
+    }
+}
+
+/// Warns the master's log channel, so that the limit can be increased before registrations are refused
+fn warn_if_nearing_limit(config: &Config) {
+    if config.servers_nearing_limit() {
+        log::warn!(
+            "{} of {} server slots are used, {} remaining. Consider increasing the limit with /server limit",
+            config.server_configs.len(),
+            config.max_servers,
+            config.remaining_servers()
+        );
+    }
+}
+
+/// [M ONLY] Registers another server ahead of time. It only needs to set its channel and role then
+#[poise::command(slash_command, guild_cooldown = 10)]
+async fn add(
+    ctx: Context<'_>,
+    #[description = "ID of the server, which will be registered"] id: GuildId,
+    #[description = "Name of the server, shown in /server show"] name: String,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let mut config_lock = ctx.data().config.write().await;
+    if config_lock.server_configs.contains_key(&id) {
+        simple_reply_text(ctx, true, format!("Server {} is already registered!", id)).await;
+        return Ok(());
+    }
+    if config_lock.server_configs.len() >= config_lock.max_servers {
+        simple_reply_text(
+            ctx,
+            true,
+            format!(
+                "There are already {} servers registered, while the limit is {}. Increase it with */server limit* first!",
+                config_lock.server_configs.len(),
+                config_lock.max_servers
+            ),
+        )
+        .await;
+        return Ok(());
+    }
+    remember_config_and_servers(ctx, &config_lock, [id]).await;
+    config_lock
+        .server_configs
+        .insert(id, ServerConfig::with_name(name.clone()));
+    warn_if_nearing_limit(&config_lock);
+    drop(config_lock);
+
+    log::info!(
+        target: EVENTS_TARGET,
+        "[server {} ({})] server added by {} ({})",
+        name,
+        id,
+        ctx.author().name,
+        ctx.author().id,
+    );
+    audit(ctx).await;
+
+    save_data(ctx.data()).await;
+
     simple_reply_text(
         ctx,
         true,
-        "server registered! Do not forget to use */config role* and */config channgel*! Also consider using */config message*".to_string()
+        format!(
+            "Server {} ({}) was added! Its admins need to set */config channel* and */config role* there",
+            name, id
+        ),
     )
     .await;
 
+    Ok(())
+}
+
+/// [M ONLY] Manages the allowlist of servers, where the bot can be used
+#[poise::command(slash_command, guild_cooldown = 10)]
+async fn allow(
+    ctx: Context<'_>,
+    #[description = "Allow or disallow the server, or let any server use the bot"]
+    action: AllowlistAction,
+    #[description = "ID of the server"] id: Option<GuildId>,
+) -> Result<(), Error> {
+    if let Err(err) = ctx.defer_ephemeral().await {
+        log::error!("Failed to defer ephemeral reply: {}", err);
+    };
+    if !master_check(ctx).await {
+        simple_reply_text(
+            ctx,
+            true,
+            "This command can only be executed in the Master server (bot's host)".to_string(),
+        )
+        .await;
+        return Ok(());
+    }
+    let id = match (action, id) {
+        (AllowlistAction::AllowAll, _) => None,
+        (_, Some(id)) => Some(id),
+        (_, None) => {
+            simple_reply_text(
+                ctx,
+                true,
+                "You need to provide server ID, if you want to allow or disallow one!".to_string(),
+            )
+            .await;
+            return Ok(());
+        }
+    };
+
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    let reply = match (action, id) {
+        (AllowlistAction::Allow, Some(id)) => {
+            config_lock
+                .guild_allowlist
+                .get_or_insert_default()
+                .insert(id);
+            format!(
+                "Server {} was allowed! Only the master server and allowlisted servers can use the bot",
+                id
+            )
+        }
+        (AllowlistAction::Disallow, Some(id)) => {
+            config_lock
+                .guild_allowlist
+                .get_or_insert_default()
+                .remove(&id);
+            format!("Server {} was disallowed!", id)
+        }
+        _ => {
+            config_lock.guild_allowlist = None;
+            "Any server can use the bot now!".to_string()
+        }
+    };
+    // Master server stays allowed, even if it is disallowed
+    let leave_from =
+        id.filter(|id| config_lock.leave_unallowed_guilds && !config_lock.guild_allowed(*id));
     drop(config_lock);
 
+    log::info!(
+        target: EVENTS_TARGET,
+        "User {} ({}) changed guild allowlist: {:?} {}",
+        ctx.author().name,
+        ctx.author().id,
+        action,
+        id.map_or("all servers".to_string(), |id| id.to_string())
+    );
+    audit(ctx).await;
+
     save_data(ctx.data()).await;
 
+    simple_reply_text(ctx, true, reply).await;
+
+    if let Some(id) = leave_from {
+        let name = id.name(ctx).unwrap_or_else(|| "UNKNOWN".to_string());
+        leave_guild(ctx, id, &name).await;
+    }
+
     Ok(())
 }
 
@@ -88,6 +464,10 @@ async fn limit(
     #[min = 1]
     #[max = 100]
     limit: usize,
+    #[description = "Percent of the limit, at which registrations are warned about. Unchanged, if empty"]
+    #[min = 1]
+    #[max = 100]
+    warn_at: Option<u8>,
 ) -> Result<(), Error> {
     if let Err(err) = ctx.defer_ephemeral().await {
         log::error!("Failed to defer ephemeral reply: {}", err);
@@ -102,24 +482,46 @@ async fn limit(
         return Ok(());
     }
 
-    ctx.data().config.write().await.max_servers = limit;
+    let mut config_lock = ctx.data().config.write().await;
+    remember_config(ctx, &config_lock).await;
+    config_lock.max_servers = limit;
+    if let Some(warn_at) = warn_at {
+        config_lock.servers_warning_percent = Some(warn_at);
+    }
+    let warn_at = config_lock.servers_warning_percent();
+    drop(config_lock);
     log::info!(
-        "User {} ({}) changed servers limit to {}",
+        target: EVENTS_TARGET,
+        "User {} ({}) changed servers limit to {}, warning at {}%",
         ctx.author().name,
         ctx.author().id,
-        limit
+        limit,
+        warn_at
     );
+    audit(ctx).await;
 
     save_data(ctx.data()).await;
 
-    simple_reply_text(ctx, true, format!("Changed servers limit to {}!", limit)).await;
+    simple_reply_text(
+        ctx,
+        true,
+        format!(
+            "Changed servers limit to {}! Registrations are warned about at {}% of it",
+            limit, warn_at
+        ),
+    )
+    .await;
 
     Ok(())
 }
 
 /// [M ONLY] Shows all registraded servers
 #[poise::command(slash_command, guild_cooldown = 20)]
-async fn show(ctx: Context<'_>) -> Result<(), Error> {
+async fn show(
+    ctx: Context<'_>,
+    #[description = "Order of the servers. By ID, if empty"] order: Option<ServerOrder>,
+    #[description = "Show only servers with problems. All, if empty"] filter: Option<ServerFilter>,
+) -> Result<(), Error> {
     if let Err(err) = ctx.defer_ephemeral().await {
         log::error!("Failed to defer ephemeral reply: {}", err);
     };
@@ -133,12 +535,43 @@ async fn show(ctx: Context<'_>) -> Result<(), Error> {
         return Ok(());
     }
 
+    let filter = filter.unwrap_or(ServerFilter::All);
+    let config_lock = ctx.data().config.read().await;
+    let mut servers: Vec<_> = config_lock
+        .server_configs
+        .iter()
+        .filter(|(_, server_config)| filter.matches(server_config))
+        .collect();
+    match order.unwrap_or(ServerOrder::Id) {
+        ServerOrder::Id => {}
+        ServerOrder::Name => servers.sort_by(|(_, a), (_, b)| a.name.cmp(&b.name)),
+        // Servers registered before it was tracked come first
+        ServerOrder::Registration => {
+            servers.sort_by_key(|(_, server_config)| server_config.registered_at)
+        }
+    }
     let mut result_embed = CreateEmbed::new()
-        .colour((45, 114, 178))
-        .title("Registered servers");
-    for (server_id, server_config) in &ctx.data().config.read().await.server_configs {
-        result_embed = result_embed.field(server_config.name.clone(), server_id.to_string(), false);
+        .colour(config_lock.admin_embed_colour())
+        .title("Registered servers")
+        .footer(CreateEmbedFooter::new(format!(
+            "{} of {} slots used, {} remaining",
+            config_lock.server_configs.len(),
+            config_lock.max_servers,
+            config_lock.remaining_servers()
+        )));
+    if servers.is_empty() {
+        result_embed = result_embed.description("No servers match the filter");
+    }
+    for (server_id, server_config) in servers {
+        let missing = server_config.missing_setup();
+        let value = if missing.is_empty() {
+            server_id.to_string()
+        } else {
+            format!("{}\nMissing: {}", server_id, missing.join(" and "))
+        };
+        result_embed = result_embed.field(server_config.name.clone(), value, false);
     }
+    drop(config_lock);
     log::info!(
         "User {} ({}) checked servers list",
         ctx.author().name,
@@ -177,11 +610,17 @@ async fn remove(
     );
     match options {
         RemovalOptions::All => {
+            let config_lock = ctx.data().config.read().await;
+            let server_ids: Vec<_> = config_lock.server_configs.keys().copied().collect();
+            remember_config_and_servers(ctx, &config_lock, server_ids).await;
+            drop(config_lock);
             log::info!(
+                target: EVENTS_TARGET,
                 "User {} ({}) unregistered all servers.",
                 ctx.author().name,
                 ctx.author().id,
             );
+            audit(ctx).await;
             let config_lock = ctx.data().config.read().await;
             let master_config = config_lock.server_configs.get(&master_server_id);
             match master_config {
@@ -215,21 +654,20 @@ async fn remove(
                         .await;
                     return Ok(());
                 }
-                let removed_entry = ctx
-                    .data()
-                    .config
-                    .write()
-                    .await
-                    .server_configs
-                    .remove_entry(&id);
+                let mut config_lock = ctx.data().config.write().await;
+                remember_config_and_servers(ctx, &config_lock, [id]).await;
+                let removed_entry = config_lock.server_configs.remove_entry(&id);
+                drop(config_lock);
                 if let Some((removed_id, removed_config)) = removed_entry {
                     log::info!(
+                        target: EVENTS_TARGET,
                         "User {} ({}) unregistered server {} ({}).",
                         ctx.author().name,
                         ctx.author().id,
                         removed_config.name,
                         removed_id
                     );
+                    audit(ctx).await;
                     simple_reply_text(
                         ctx,
                         true,
@@ -0,0 +1,59 @@
+use crate::{
+    Context, Error,
+    commands::simple_reply_embed,
+    status::{DEFAULT_ADDRESS_LABEL, DEFAULT_SINCE_LABEL, fit_resource_name, generate_embed},
+};
+
+/// Shows the current status of the resource
+#[poise::command(slash_command, user_cooldown = 10)]
+pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
+    if let Err(err) = ctx.defer().await {
+        log::error!("Failed to defer reply: {}", err);
+    };
+
+    let data = ctx.data();
+    let status = *data.status.read().await;
+    let since = *data.last_status_change.read().await;
+    let unknown_cause = *data.unknown_cause.read().await;
+    let linked_status = *data.linked_status.read().await;
+    let config_lock = data.config.read().await;
+    let labels = ctx
+        .guild_id()
+        .and_then(|server_id| config_lock.server_configs.get(&server_id))
+        .map_or(
+            (
+                DEFAULT_SINCE_LABEL.to_string(),
+                DEFAULT_ADDRESS_LABEL.to_string(),
+            ),
+            |server_config| {
+                (
+                    server_config.since_label.clone(),
+                    server_config.address_label.clone(),
+                )
+            },
+        );
+    let linked = config_lock
+        .ping_config
+        .linked_probe
+        .as_ref()
+        .map(|probe| (probe.name.as_str(), linked_status));
+    let embed = generate_embed(
+        &fit_resource_name(&config_lock.ping_config.resource_name),
+        status,
+        config_lock.ping_config.resource_addr.clone(),
+        since,
+        (&labels.0, &labels.1),
+        unknown_cause,
+        linked,
+    );
+    drop(config_lock);
+    log::info!(
+        "User {} ({}) checked the status",
+        ctx.author().name,
+        ctx.author().id,
+    );
+
+    simple_reply_embed(ctx, false, embed).await;
+
+    Ok(())
+}
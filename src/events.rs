@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use poise::serenity_prelude::{CreateAllowedMentions, CreateMessage, Http};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::Data;
+
+/// Log target of significant events (config changes, registrations, status transitions), which are
+/// mirrored into the master's log channel along with warnings and errors
+pub const EVENTS_TARGET: &str = "discord_watchdog::events";
+/// Failures of the mirroring itself are not mirrored, so they can't feed themselves
+const FEED_TARGET: &str = "discord_watchdog::events::feed";
+
+pub const DISCORD_MESSAGE_LIMIT: usize = 2000;
+
+/// Whether the log record should be mirrored into the master's log channel
+pub fn is_significant(metadata: &log::Metadata) -> bool {
+    let target = metadata.target();
+    target == EVENTS_TARGET
+        || (metadata.level() <= log::Level::Warn
+            && target.starts_with("discord_watchdog")
+            && target != FEED_TARGET)
+}
+
+pub fn format_event(record: &log::Record) -> String {
+    format!("`{}` {}", record.level(), record.args())
+}
+
+/// Posts events to the master's log channel. Events, which piled up while posting, are sent in one message
+pub async fn events_task(mut events: UnboundedReceiver<String>, data: Data, http: Arc<Http>) {
+    while let Some(event) = events.recv().await {
+        let mut batch = vec![event];
+        while let Ok(event) = events.try_recv() {
+            batch.push(event);
+        }
+        let Some(channel) = data.config.read().await.master_log_channel else {
+            continue;
+        };
+        for message in join_events(&batch) {
+            // Events quote user-controlled text, e.g. server names and messages, which must not ping anyone
+            let message = CreateMessage::new()
+                .content(message)
+                .allowed_mentions(CreateAllowedMentions::new());
+            if let Err(err) = channel.send_message(http.clone(), message).await {
+                log::warn!(
+                    target: FEED_TARGET,
+                    "Failed to post events to the log channel {}: {}",
+                    channel,
+                    err
+                );
+            }
+        }
+    }
+}
+
+/// Joins events into as few messages as Discord's length limit allows
+fn join_events(events: &[String]) -> Vec<String> {
+    let mut messages = Vec::new();
+    let mut message = String::new();
+    for event in events {
+        let event: String = event.chars().take(DISCORD_MESSAGE_LIMIT).collect();
+        if !message.is_empty()
+            && message.chars().count() + event.chars().count() + 1 > DISCORD_MESSAGE_LIMIT
+        {
+            messages.push(std::mem::take(&mut message));
+        }
+        if !message.is_empty() {
+            message.push('\n');
+        }
+        message.push_str(&event);
+    }
+    if !message.is_empty() {
+        messages.push(message);
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::events::{DISCORD_MESSAGE_LIMIT, join_events};
+
+    #[test]
+    fn events_are_split_by_message_limit() {
+        let long_event = "a".repeat(DISCORD_MESSAGE_LIMIT - 10);
+        let events = vec![
+            "first".to_string(),
+            "second".to_string(),
+            long_event.clone(),
+            "b".repeat(DISCORD_MESSAGE_LIMIT + 5),
+        ];
+
+        let messages = join_events(&events);
+
+        assert_eq!(
+            messages,
+            vec![
+                "first\nsecond".to_string(),
+                long_event,
+                "b".repeat(DISCORD_MESSAGE_LIMIT)
+            ]
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use std::{sync::Arc, time::Duration};
+
+use poise::serenity_prelude::{
+    self as serenity, CacheHttp, CreateMessage, FullEvent, GuildId, Http, RoleId, Timestamp,
+};
+
+use crate::{Data, Error, events::EVENTS_TARGET, save_data};
+
+/// How often servers, which nobody registered, are checked
+const GUILD_SWEEP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Handles gateway events, which are not commands
+pub async fn handle_event(
+    ctx: &serenity::Context,
+    event: &FullEvent,
+    data: &Data,
+) -> Result<(), Error> {
+    match event {
+        FullEvent::GuildCreate { guild, .. } => {
+            let config_lock = data.config.read().await;
+            if !config_lock.guild_allowed(guild.id) {
+                let leave = config_lock.leave_unallowed_guilds;
+                drop(config_lock);
+                log::warn!(
+                    target: EVENTS_TARGET,
+                    "[server {} ({})] Bot is in a server, which is not in the allowlist",
+                    guild.name,
+                    guild.id
+                );
+                if leave {
+                    leave_guild(ctx, guild.id, &guild.name).await;
+                    return Ok(());
+                }
+            } else {
+                drop(config_lock);
+            }
+            data.guild_joins
+                .write()
+                .await
+                .insert(guild.id, (guild.name.clone(), guild.joined_at));
+            // Roles could be deleted, while the bot was offline
+            let configured_roles =
+                data.config
+                    .read()
+                    .await
+                    .server_configs
+                    .get(&guild.id)
+                    .map(|server_config| {
+                        [
+                            server_config.role_to_notify,
+                            server_config.observer_role,
+                            server_config.admin_role,
+                        ]
+                    });
+            for role_id in configured_roles.into_iter().flatten().flatten() {
+                if !guild.roles.contains_key(&role_id) {
+                    forget_deleted_role(ctx, data, guild.id, role_id).await;
+                }
+            }
+        }
+        FullEvent::GuildRoleDelete {
+            guild_id,
+            removed_role_id,
+            ..
+        } => forget_deleted_role(ctx, data, *guild_id, *removed_role_id).await,
+        // Unavailable servers are still joined, they are just having an outage
+        FullEvent::GuildDelete { incomplete, .. } if !incomplete.unavailable => {
+            data.guild_joins.write().await.remove(&incomplete.id);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Clears the deleted role from the server's config, so mentions fall back to plain text instead of a broken one,
+/// and asks the server's admins to set a new one
+async fn forget_deleted_role(
+    cache_http: impl CacheHttp,
+    data: &Data,
+    server_id: GuildId,
+    role_id: RoleId,
+) {
+    let mut config_lock = data.config.write().await;
+    let Some(server_config) = config_lock.server_configs.get_mut(&server_id) else {
+        return;
+    };
+    let mut forgotten = Vec::new();
+    if server_config.role_to_notify == Some(role_id) {
+        server_config.role_to_notify = None;
+        forgotten.push("role to notify (*/config role*)");
+    }
+    if server_config.observer_role == Some(role_id) {
+        server_config.observer_role = None;
+        forgotten.push("observer role (*/config observer-role*)");
+    }
+    if server_config.admin_role == Some(role_id) {
+        server_config.admin_role = None;
+        forgotten.push("admin role (*/config admin-role*)");
+    }
+    if forgotten.is_empty() {
+        return;
+    }
+    let server_name = server_config.name.clone();
+    let channel = server_config.channel;
+    drop(config_lock);
+
+    log::warn!(
+        target: EVENTS_TARGET,
+        "[server {} ({})] Configured role {} was deleted, forgot it as {}",
+        server_name,
+        server_id,
+        role_id,
+        forgotten.join(" and ")
+    );
+    save_data(data).await;
+
+    let Some(channel) = channel else {
+        return;
+    };
+    let notice = format!(
+        "The {} was deleted. Please set a new one!",
+        forgotten.join(" and ")
+    );
+    if let Err(err) = channel
+        .send_message(cache_http, CreateMessage::new().content(notice))
+        .await
+    {
+        log::error!(
+            "[server {} ({})] Failed to tell about the deleted role: {}",
+            server_name,
+            server_id,
+            err
+        );
+    }
+}
+
+/// Leaves servers, which nobody registered within `leave_unregistered_after` since the bot joined them
+pub async fn guild_sweep_task(data: Data, http: Arc<Http>) {
+    loop {
+        tokio::time::sleep(GUILD_SWEEP_INTERVAL).await;
+        for (server_id, server_name) in data.stale_unregistered_guilds(Timestamp::now()).await {
+            log::info!(
+                target: EVENTS_TARGET,
+                "[server {} ({})] Nobody registered the server in time",
+                server_name,
+                server_id
+            );
+            leave_guild(&http, server_id, &server_name).await;
+            data.guild_joins.write().await.remove(&server_id);
+        }
+    }
+}
+
+/// Makes the bot leave the server, logging the outcome
+pub async fn leave_guild(http: impl AsRef<Http>, server_id: GuildId, server_name: &str) {
+    match server_id.leave(http).await {
+        Ok(()) => log::info!(
+            target: EVENTS_TARGET,
+            "[server {} ({})] Bot left the server",
+            server_name,
+            server_id
+        ),
+        Err(err) => log::error!(
+            "[server {} ({})] Failed to leave the server: {}",
+            server_name,
+            server_id,
+            err
+        ),
+    }
+}
@@ -1,33 +1,91 @@
+mod audit;
 pub mod commands;
+pub mod events;
+pub mod guilds;
 pub mod ping;
 mod status;
+mod template;
+mod webhooks;
+
+pub use status::update_presence;
 
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet, VecDeque},
     fmt::Display,
-    path::Path,
+    num::{NonZeroU8, NonZeroU32},
+    path::{Path, PathBuf},
+    str::FromStr,
     sync::{
         Arc,
-        atomic::{AtomicU8, Ordering},
+        atomic::{AtomicBool, AtomicU8, AtomicU32, Ordering},
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use poise::serenity_prelude::{ChannelId, GuildId, MessageId, RoleId, Timestamp};
+use poise::serenity_prelude::{ChannelId, GuildId, MessageId, RoleId, ShardManager, Timestamp};
 use serde::{Deserialize, Serialize};
-use tokio::sync::{OnceCell, RwLock};
+use tokio::sync::{Notify, OnceCell, RwLock};
+use toml_edit::{DocumentMut, Item};
 
-use crate::status::{DEFAULT_DOWN_MESSAGE, DEFAULT_UP_MESSAGE};
+use crate::{
+    events::EVENTS_TARGET,
+    ping::{PingClient, UnknownCause},
+    status::{
+        DEFAULT_ADDRESS_LABEL, DEFAULT_DOWN_MESSAGE, DEFAULT_LATENCY_LABEL,
+        DEFAULT_MAINTENANCE_END_MESSAGE, DEFAULT_MAINTENANCE_START_MESSAGE, DEFAULT_SINCE_LABEL,
+        DEFAULT_UP_MESSAGE,
+    },
+    webhooks::{WebhookConfig, webhook_problems},
+};
 
 pub const DEFAULT_RESOURCE_NAME: &str = "BYOND";
 pub const DEFAULT_RESOURCE_ADDR: &str = "hub.byond.com";
+/// Longest resource name, which */config name* accepts. Longer ones from the config file are truncated when shown
+pub const MAX_RESOURCE_NAME_LENGTH: usize = 25;
 pub const DEFAULT_ATTEMPTS_BEFORE_NOTIFICATION: u8 = 3;
 pub const DEFAULT_TIMEOUT_SECS: u64 = 5;
+pub const DEFAULT_DNS_TIMEOUT_SECS: u64 = 3;
 pub const DEFAULT_INTERVAL_BETWEEN_ATTEMPTS_SECS: u64 = 10;
+pub const DEFAULT_CHECK_CONCURRENCY: NonZeroU8 = NonZeroU8::new(4).unwrap();
+pub const DEFAULT_MAX_OUTSTANDING_CHECKS: NonZeroU8 = NonZeroU8::new(16).unwrap();
+/// Weight of the newest round-trip time in the smoothed one
+pub const DEFAULT_RTT_SMOOTHING: f64 = 0.3;
+/// How many configuration changes can be reverted with */config undo* in each server
+pub const DEFAULT_UNDO_DEPTH: usize = 10;
+/// How many named snapshots */config snapshot* keeps, since each one is a whole configuration in the saved data
+pub const MAX_CONFIG_SNAPSHOTS: usize = 10;
+/// How long a handled interaction is remembered, so its repetition is ignored
+pub const INTERACTION_DEDUP_WINDOW: Duration = Duration::from_secs(60);
+/// Share of `max_servers` in percent, at which new registrations are warned about, unless overridden in the config
+pub const DEFAULT_SERVERS_WARNING_PERCENT: u8 = 80;
+/// How many sent notifications are remembered for each server, unless overridden in the config
+pub const DEFAULT_HISTORY_LIMIT: usize = 20;
+/// Colour of the bot's own embeds, e.g. */server show*, unless overridden in the config
+pub const DEFAULT_ADMIN_EMBED_COLOUR: (u8, u8, u8) = (45, 114, 178);
 
 pub const DEFAULT_SAVEDATA_PATH: &str = "Data.toml";
+/// Where saved data goes as JSON, if it fails to serialize into TOML and `save_fallback_json` is set
+pub const DEFAULT_SAVEDATA_FALLBACK_PATH: &str = "Data.fallback.json";
+/// Consecutive failures to serialize saved data, after which the master is alerted, since changes aren't persisted
+pub const SAVE_FAILURES_BEFORE_ALERT: u32 = 3;
+/// Current layout of saved data. Bump it along with adding a migration to `SAVEDATA_MIGRATIONS`
+pub const SAVEDATA_VERSION: u32 = 2;
+/// Description of the migration, which is logged when it is applied, and the migration itself
+type SavedDataMigration = (&'static str, fn(&mut toml::Value));
+/// Migration at index N upgrades saved data from vN to vN+1
+const SAVEDATA_MIGRATIONS: [SavedDataMigration; SAVEDATA_VERSION as usize] = [
+    (
+        "paired status message ids with their channels",
+        migrate_used_messages,
+    ),
+    (
+        "moved servers' messages into tables keyed by transition kind",
+        migrate_saved_server_messages,
+    ),
+];
 pub const DEFAULT_CONFIG_PATH: &str = "Config.toml";
 pub const DEFAULT_LOG_PATH: &str = "debug.log";
+pub const DEFAULT_AUDIT_PATH: &str = "audit.toml";
 
 // Yeah, it's hardcoded. Change it there, if you fork.
 pub const DEFAULT_REPOSITORY: &str = "https://github.com/VladOS-0/discord-watchdog";
@@ -38,8 +96,13 @@ pub static THIS_RUN_START: OnceCell<Timestamp> = OnceCell::const_new();
 pub enum ResourceStatus {
     Up,
     Down,
-    #[default]
+    /// Checks are failing with errors, so the actual status can't be determined
     Unknown,
+    /// No check has completed since the bot was set up
+    #[default]
+    Pending,
+    /// Outside of the resource's active hours, so it isn't checked
+    Inactive,
 }
 
 impl Display for ResourceStatus {
@@ -48,6 +111,8 @@ impl Display for ResourceStatus {
             ResourceStatus::Up => write!(f, "Up"),
             ResourceStatus::Down => write!(f, "Down"),
             ResourceStatus::Unknown => write!(f, "Unknown"),
+            ResourceStatus::Pending => write!(f, "Pending"),
+            ResourceStatus::Inactive => write!(f, "Inactive"),
         }
     }
 }
@@ -61,30 +126,285 @@ pub struct AppData {
     attempts_before_notification: AtomicU8,
     last_status_change: RwLock<Timestamp>,
     config: RwLock<Config>,
+    undo_history: RwLock<BTreeMap<GuildId, VecDeque<UndoEntry>>>,
+    /// Named configurations, which can be restored with */config restore*
+    config_snapshots: RwLock<BTreeMap<String, ConfigSnapshot>>,
+    /// Since when the resource is responding without interruption
+    responding_since: RwLock<Option<Instant>>,
+    /// When the last failed check, which counted towards confirming an outage, was made
+    last_counted_failure: RwLock<Option<Instant>>,
+    /// Why the latest failed checks didn't determine the status
+    unknown_cause: RwLock<UnknownCause>,
+    /// Shared by the ping task and commands, so raw sockets are opened only once
+    ping_client: PingClient,
+    /// Ids of recently handled interactions with the time they were first seen, oldest first
+    recent_interactions: RwLock<VecDeque<(u64, Instant)>>,
+    /// Whether resolving the addresses was slow during the last tick
+    dns_slow: AtomicBool,
+    /// Address families, which failed while the other one worked during the last tick
+    unreachable_families: RwLock<BTreeSet<&'static str>>,
+    /// Last status of the linked probe. It isn't announced, but changes are still confirmed
+    linked_status: RwLock<ResourceStatus>,
+    /// Checks in a row, which disagreed with `linked_status`
+    linked_attempts: AtomicU8,
+    /// Address checked instead of the configured one for a while. It is never saved, so a restart drops it
+    address_override: RwLock<Option<AddressOverride>>,
+    /// Shards of the running Discord client, whose presence reflects the status
+    shard_manager: RwLock<Option<Arc<ShardManager>>>,
+    /// Servers the bot is in, with their names and when the bot joined them
+    guild_joins: RwLock<BTreeMap<GuildId, (String, Timestamp)>>,
+    /// Notified, when the Discord client connects
+    client_ready: Notify,
+    /// Whether slash commands were registered, so rebuilt Discord clients don't register them again
+    commands_registered: AtomicBool,
+    started_at: StartedAt,
+    /// Smoothed round-trip time of the resource's address
+    rtt: RwLock<RttAverage>,
+    /// Whether the resource's host responded during the last check, while its service didn't
+    service_down: AtomicBool,
+    /// When the probed addresses were last checked
+    last_check: RwLock<Option<Timestamp>>,
+    /// Checks in a row, which all failed with errors other than timeouts. They mean that something is wrong with the
+    /// host running the bot
+    consecutive_errors: AtomicU32,
+    /// How long up/down messages took to be delivered to each server since the bot started
+    delivery_latencies: RwLock<BTreeMap<GuildId, DeliveryLatency>>,
+    /// Whether Unknown status was kept from the servers during the startup grace, so it is shown once it ends
+    unknown_suppressed: AtomicBool,
+    /// When the last reminder, that the status is still unknown, was posted
+    last_unknown_reminder: RwLock<Option<Timestamp>>,
+    /// When the latency in the status embeds was last refreshed
+    last_latency_refresh: RwLock<Option<Timestamp>>,
+    /// Consecutive failures to serialize saved data
+    save_serialization_failures: AtomicU32,
+}
+
+/// Moment the bot started, i.e. when `AppData` was created
+#[derive(Debug, Clone, Copy)]
+struct StartedAt(Instant);
+
+impl Default for StartedAt {
+    fn default() -> Self {
+        Self(Instant::now())
+    }
 }
 
-#[derive(Default, Debug, Serialize, Deserialize)]
+impl AppData {
+    /// Remembers the state before a change made in the server, dropping the oldest one if history is full
+    pub async fn push_undo(&self, server_id: GuildId, entry: UndoEntry) {
+        let mut history_lock = self.undo_history.write().await;
+        let history = history_lock.entry(server_id).or_default();
+        if history.len() >= DEFAULT_UNDO_DEPTH {
+            history.pop_front();
+        }
+        history.push_back(entry);
+    }
+    pub async fn pop_undo(&self, server_id: GuildId) -> Option<UndoEntry> {
+        self.undo_history
+            .write()
+            .await
+            .get_mut(&server_id)
+            .and_then(|history| history.pop_back())
+    }
+    /// Marks the Discord client as connected, e.g. once it receives the Ready event
+    pub fn notify_client_ready(&self) {
+        self.client_ready.notify_one();
+    }
+    /// Waits until the Discord client connects
+    pub async fn client_ready(&self) {
+        self.client_ready.notified().await;
+    }
+    pub fn commands_registered(&self) -> bool {
+        self.commands_registered.load(Ordering::Relaxed)
+    }
+    pub fn mark_commands_registered(&self) {
+        self.commands_registered.store(true, Ordering::Relaxed);
+    }
+    /// Address families, which failed while the other one worked during the last tick, e.g. "IPv6"
+    pub async fn unreachable_families(&self) -> Option<String> {
+        let families_lock = self.unreachable_families.read().await;
+        (!families_lock.is_empty()).then(|| {
+            families_lock
+                .iter()
+                .copied()
+                .collect::<Vec<_>>()
+                .join(" and ")
+        })
+    }
+    /// Whether the bot started less than `startup_grace` ago, so Unknown status likely means the network isn't ready
+    pub fn in_startup_grace(&self, config: &Config) -> bool {
+        config
+            .startup_grace
+            .is_some_and(|grace| self.started_at.0.elapsed() < grace)
+    }
+    /// Smoothed round-trip time of the resource, if it responded since the bot started
+    pub async fn rtt(&self) -> Option<Duration> {
+        self.rtt.read().await.get()
+    }
+    pub async fn record_delivery(&self, server_id: GuildId, latency: Duration) {
+        self.delivery_latencies
+            .write()
+            .await
+            .entry(server_id)
+            .or_default()
+            .record(latency);
+    }
+    pub async fn delivery_latencies(&self) -> BTreeMap<GuildId, DeliveryLatency> {
+        self.delivery_latencies.read().await.clone()
+    }
+    /// Replaces the shards, e.g. after the Discord client is rebuilt
+    pub async fn set_shard_manager(&self, shard_manager: Arc<ShardManager>) {
+        *self.shard_manager.write().await = Some(shard_manager);
+    }
+    /// Servers, which nobody registered within `leave_unregistered_after` since the bot joined them
+    pub async fn stale_unregistered_guilds(&self, now: Timestamp) -> Vec<(GuildId, String)> {
+        let config_lock = self.config.read().await;
+        let Some(leave_after) = config_lock.leave_unregistered_after else {
+            return Vec::new();
+        };
+        self.guild_joins
+            .read()
+            .await
+            .iter()
+            .filter(|(server_id, (_, joined_at))| {
+                config_lock.master_server != Some(**server_id)
+                    && !config_lock.server_configs.contains_key(server_id)
+                    && now.unix_timestamp() - joined_at.unix_timestamp()
+                        >= leave_after.as_secs() as i64
+            })
+            .map(|(server_id, (name, _))| (*server_id, name.clone()))
+            .collect()
+    }
+    /// Remembers the interaction. Returns false, if it was already seen within `INTERACTION_DEDUP_WINDOW`,
+    /// e.g. because Discord retried it or the user double-clicked
+    pub async fn first_seen_interaction(&self, interaction_id: u64, now: Instant) -> bool {
+        let mut recent_lock = self.recent_interactions.write().await;
+        while recent_lock.front().is_some_and(|(_, seen)| {
+            now.saturating_duration_since(*seen) > INTERACTION_DEDUP_WINDOW
+        }) {
+            recent_lock.pop_front();
+        }
+        if recent_lock.iter().any(|(id, _)| *id == interaction_id) {
+            return false;
+        }
+        recent_lock.push_back((interaction_id, now));
+        true
+    }
+    /// Checks `addr` instead of the configured address until `until`
+    pub async fn override_address(&self, addr: String, until: Instant) {
+        *self.address_override.write().await = Some(AddressOverride { addr, until });
+    }
+    /// Checks the configured address again right away. Returns the overriding address, if there was one
+    pub async fn clear_address_override(&self) -> Option<String> {
+        self.address_override
+            .write()
+            .await
+            .take()
+            .map(|address_override| address_override.addr)
+    }
+    /// Ping config in effect: the configured one, with the address replaced while it is overridden.
+    /// An expired override is dropped here
+    pub async fn effective_ping_config(&self, now: Instant) -> PingConfig {
+        let mut ping_config = self.config.read().await.ping_config.clone();
+        let mut override_lock = self.address_override.write().await;
+        match override_lock.as_ref() {
+            Some(address_override) if now < address_override.until => {
+                ping_config.resource_addr = address_override.addr.clone();
+            }
+            Some(address_override) => {
+                log::info!(
+                    target: EVENTS_TARGET,
+                    "Override of the resource address with {} expired, checking {} again",
+                    address_override.addr,
+                    ping_config.resource_addr
+                );
+                *override_lock = None;
+            }
+            None => {}
+        }
+        ping_config
+    }
+}
+
+#[derive(Debug, Clone)]
+struct AddressOverride {
+    addr: String,
+    until: Instant,
+}
+
+/// State of the configuration before a change, used by */config undo*
+#[derive(Debug, Clone)]
+// History is short, so whole configurations aren't worth boxing
+#[allow(clippy::large_enum_variant)]
+pub enum UndoEntry {
+    /// Master-owned configuration, changed by a master command, with the servers it registered or removed.
+    /// Configurations of the other servers are kept, when it is restored
+    Global {
+        config: Config,
+        servers: BTreeMap<GuildId, Option<ServerConfig>>,
+    },
+    /// Configuration of one server. `None` if the server was not registered before the change
+    Server(Option<ServerConfig>),
+}
+
+/// Whole configuration, saved under a name by */config snapshot*, so it can be restored after risky changes
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConfigSnapshot {
+    pub taken_at: Timestamp,
+    /// Name of the user, who took it
+    pub taken_by: String,
+    pub config: Config,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SavedData {
+    /// Layout version. Older layouts are migrated on load
+    #[serde(default)]
+    version: u32,
     status: ResourceStatus,
     used_messages: BTreeMap<GuildId, ServerUsedMessages>,
+    /// Saved whenever it changes, so confirming a status change continues after restart instead of starting over
     attempts_before_notification: u8,
     last_status_change: Timestamp,
     pub config: Config,
+    #[serde(default)]
+    config_snapshots: BTreeMap<String, ConfigSnapshot>,
+}
+
+impl Default for SavedData {
+    fn default() -> Self {
+        Self {
+            version: SAVEDATA_VERSION,
+            status: ResourceStatus::default(),
+            used_messages: BTreeMap::default(),
+            attempts_before_notification: 0,
+            last_status_change: Timestamp::default(),
+            config: Config::default(),
+            config_snapshots: BTreeMap::default(),
+        }
+    }
 }
 
 impl SavedData {
     pub async fn load_from_file<T: AsRef<Path>>(data_path: &T) -> anyhow::Result<Option<Self>> {
         let data_file = tokio::fs::read_to_string(data_path).await;
         if let Ok(data_string) = data_file {
-            Ok(Some(toml::from_str::<SavedData>(&data_string).map_err(
-                |err| {
-                    anyhow::Error::msg(format!(
-                        "Malformed saved data in {}: {}",
-                        data_path.as_ref().to_string_lossy(),
-                        err
-                    ))
-                },
-            )?))
+            let malformed = |err: toml::de::Error| {
+                anyhow::Error::msg(format!(
+                    "Malformed saved data in {}: {}",
+                    data_path.as_ref().to_string_lossy(),
+                    err
+                ))
+            };
+            let mut saved_data = toml::from_str::<toml::Value>(&data_string).map_err(malformed)?;
+            migrate_saved_data(&mut saved_data).map_err(|err| {
+                anyhow::Error::msg(format!(
+                    "Failed to migrate saved data in {}: {}",
+                    data_path.as_ref().to_string_lossy(),
+                    err
+                ))
+            })?;
+            Ok(Some(saved_data.try_into::<SavedData>().map_err(malformed)?))
         } else {
             let err = data_file.unwrap_err();
             if let std::io::ErrorKind::NotFound = err.kind() {
@@ -99,12 +419,16 @@ impl SavedData {
         }
     }
     pub async fn save_to_file<T: AsRef<Path>>(&self, config_path: &T) -> anyhow::Result<()> {
-        let serialized_string = toml::to_string_pretty(self).map_err(|err| {
-            anyhow::Error::msg(format!(
-                "Broken serialization of SaveData: got {}, while serializing {:?}",
-                err, self
-            ))
-        })?;
+        let serialized_string = toml::to_string_pretty(self).map_err(SerializationError)?;
+        if let Some(parent) = config_path.as_ref().parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|err| {
+                anyhow::Error::msg(format!(
+                    "failed to create directory {} for SaveData: {}",
+                    parent.to_string_lossy(),
+                    err
+                ))
+            })?;
+        }
         tokio::fs::write(config_path, serialized_string.as_bytes())
             .await
             .map_err(|err| {
@@ -116,6 +440,23 @@ impl SavedData {
             })?;
         Ok(())
     }
+    /// Writes the data as JSON, which is a last resort, when it fails to serialize into TOML
+    pub async fn save_json_fallback<T: AsRef<Path>>(
+        &self,
+        fallback_path: &T,
+    ) -> anyhow::Result<()> {
+        let serialized_string = serde_json::to_string_pretty(self)?;
+        tokio::fs::write(fallback_path, serialized_string.as_bytes())
+            .await
+            .map_err(|err| {
+                anyhow::Error::msg(format!(
+                    "failed to write SaveData to {}: {}",
+                    fallback_path.as_ref().to_string_lossy(),
+                    err
+                ))
+            })?;
+        Ok(())
+    }
     pub async fn load_into(&self, data: &AppData) {
         *data.status.write().await = self.status;
         *data.used_messages.write().await = self.used_messages.clone();
@@ -123,51 +464,461 @@ impl SavedData {
             .store(self.attempts_before_notification, Ordering::Relaxed);
         *data.last_status_change.write().await = self.last_status_change;
         *data.config.write().await = self.config.clone();
+        *data.config_snapshots.write().await = self.config_snapshots.clone();
     }
     pub async fn load_from(data: &AppData) -> Self {
         Self {
+            version: SAVEDATA_VERSION,
             status: (*data.status.read().await),
             used_messages: (*data.used_messages.read().await).clone(),
             attempts_before_notification: data.attempts_before_notification.load(Ordering::Relaxed),
             last_status_change: (*data.last_status_change.read().await),
             config: (*data.config.read().await).clone(),
+            config_snapshots: (*data.config_snapshots.read().await).clone(),
+        }
+    }
+}
+
+/// Saved data couldn't be serialized into TOML, so it isn't persisted until the state changes
+#[derive(Debug)]
+pub struct SerializationError(toml::ser::Error);
+
+impl Display for SerializationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Broken serialization of SaveData: {}", self.0)
+    }
+}
+
+impl std::error::Error for SerializationError {}
+
+/// Upgrades saved data of an older layout to the current one. Data without a version is considered v0
+fn migrate_saved_data(saved_data: &mut toml::Value) -> anyhow::Result<()> {
+    let version = match saved_data.get("version") {
+        Some(version) => version
+            .as_integer()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| anyhow::Error::msg(format!("invalid version {}", version)))?,
+        None => 0,
+    };
+    if version > SAVEDATA_VERSION {
+        return Err(anyhow::Error::msg(format!(
+            "version {} is newer than supported version {}",
+            version, SAVEDATA_VERSION
+        )));
+    }
+    for (from, (description, migration)) in SAVEDATA_MIGRATIONS
+        .iter()
+        .enumerate()
+        .skip(version as usize)
+    {
+        migration(saved_data);
+        log::info!(
+            "Migrated saved data from v{} to v{}: {}",
+            from,
+            from + 1,
+            description
+        );
+    }
+    if let Some(saved_data) = saved_data.as_table_mut() {
+        saved_data.insert(
+            "version".to_string(),
+            toml::Value::Integer(SAVEDATA_VERSION.into()),
+        );
+    }
+    Ok(())
+}
+
+/// Pairs status message ids, saved before their channels were stored along with them, with the currently configured channel.
+/// Ids of servers without a channel are dropped, since there is no way to find these messages anyway
+fn migrate_used_messages(saved_data: &mut toml::Value) {
+    let channels: BTreeMap<String, toml::Value> = saved_data
+        .get("config")
+        .and_then(|config| config.get("server_configs"))
+        .and_then(|server_configs| server_configs.as_table())
+        .map(|server_configs| {
+            server_configs
+                .iter()
+                .filter_map(|(server_id, server_config)| {
+                    Some((server_id.clone(), server_config.get("channel")?.clone()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let Some(used_messages) = saved_data
+        .get_mut("used_messages")
+        .and_then(|used_messages| used_messages.as_table_mut())
+    else {
+        return;
+    };
+    for (server_id, messages) in used_messages.iter_mut() {
+        let Some(messages) = messages.as_table_mut() else {
+            continue;
+        };
+        if !matches!(
+            messages.get("status"),
+            Some(toml::Value::String(_) | toml::Value::Integer(_))
+        ) {
+            continue;
+        }
+        if let (Some(status), Some(channel)) = (messages.remove("status"), channels.get(server_id))
+        {
+            messages.insert(
+                "status".to_string(),
+                toml::Value::Array(vec![channel.clone(), status]),
+            );
+        }
+    }
+}
+
+fn migrate_saved_server_messages(saved_data: &mut toml::Value) {
+    if let Some(config) = saved_data.get_mut("config") {
+        migrate_server_messages(config);
+    }
+}
+
+/// Moves messages from separate fields of older server configs into `messages`. Config.toml isn't versioned, so it
+/// goes through this on every load. A message, which is already in `messages`, wins over the old field
+fn migrate_server_messages(config: &mut toml::Value) {
+    let Some(server_configs) = config
+        .get_mut("server_configs")
+        .and_then(|server_configs| server_configs.as_table_mut())
+    else {
+        return;
+    };
+    for (_, server_config) in server_configs.iter_mut() {
+        let Some(server_config) = server_config.as_table_mut() else {
+            continue;
+        };
+        for kind in TransitionKind::ALL {
+            let Some(message) = server_config.remove(&format!("{}_message", kind.key())) else {
+                continue;
+            };
+            let messages = server_config
+                .entry("messages")
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            if let Some(messages) = messages.as_table_mut() {
+                messages.entry(kind.key()).or_insert(message);
+            }
         }
     }
 }
 
+/// Exponentially weighted moving average of round-trip times, which is less jumpy than the latest one
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct RttAverage {
+    average: Option<Duration>,
+    last: Option<Duration>,
+}
+
+impl RttAverage {
+    /// Mixes the new round-trip time in. `smoothing` is its weight: 1 shows the latest one, closer to 0 is smoother
+    pub fn update(&mut self, rtt: Duration, smoothing: f64) {
+        let smoothing = smoothing.clamp(f64::EPSILON, 1.0);
+        self.last = Some(rtt);
+        self.average = Some(match self.average {
+            Some(average) => average.mul_f64(1.0 - smoothing) + rtt.mul_f64(smoothing),
+            None => rtt,
+        });
+    }
+    pub fn get(&self) -> Option<Duration> {
+        self.average
+    }
+    /// Latest round-trip time, which isn't smoothed
+    pub fn last(&self) -> Option<Duration> {
+        self.last
+    }
+}
+
+/// Time between confirming status changes and delivering their messages to a server. Slow delivery usually means rate
+/// limits or an overloaded channel
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct DeliveryLatency {
+    pub last: Duration,
+    pub worst: Duration,
+    total: Duration,
+    pub deliveries: u32,
+}
+
+impl DeliveryLatency {
+    pub fn record(&mut self, latency: Duration) {
+        self.last = latency;
+        self.worst = self.worst.max(latency);
+        self.total += latency;
+        self.deliveries += 1;
+    }
+    pub fn average(&self) -> Duration {
+        self.total.checked_div(self.deliveries).unwrap_or_default()
+    }
+}
+
 /// IDs of messages that were created by the bot to inform users about resource status changes
 #[derive(Default, Debug, Serialize, Deserialize, Clone)]
 pub struct ServerUsedMessages {
-    status: Option<MessageId>,
+    /// Message is stored with its channel, so it is still found after the notification channel changes
+    status: Option<(ChannelId, MessageId)>,
+    /// Fingerprint of the status embed the bot sent. Only stored if the server detects manual edits
+    #[serde(default)]
+    status_hash: Option<String>,
+    /// Up and down messages, oldest first. Bounded by `Config::history_limit`
+    #[serde(default)]
+    notifications: VecDeque<SentNotification>,
+    /// When the role was mentioned last time
+    #[serde(default)]
+    last_mention: Option<Timestamp>,
 }
 
 impl ServerUsedMessages {
-    pub fn new(status: Option<MessageId>) -> Self {
-        Self { status }
+    pub fn new(status: Option<(ChannelId, MessageId)>, status_hash: Option<String>) -> Self {
+        Self {
+            status,
+            status_hash,
+            ..Default::default()
+        }
+    }
+    /// Whether the role can be mentioned again, i.e. `cooldown` has passed since the last mention
+    pub fn mention_allowed(&self, now: Timestamp, cooldown: Duration) -> bool {
+        self.last_mention.is_none_or(|last_mention| {
+            now.unix_timestamp() - last_mention.unix_timestamp() >= cooldown.as_secs() as i64
+        })
+    }
+    pub fn set_last_mention(&mut self, moment: Timestamp) {
+        self.last_mention = Some(moment);
+    }
+    /// Stops tracking the status message, returning it
+    pub fn take_status(&mut self) -> Option<(ChannelId, MessageId)> {
+        self.status_hash = None;
+        self.status.take()
+    }
+    /// Replaces the status message, keeping the notification history
+    pub fn set_status(&mut self, status: (ChannelId, MessageId), status_hash: Option<String>) {
+        self.status = Some(status);
+        self.status_hash = status_hash;
+    }
+    /// Remembers a sent notification, evicting the oldest ones beyond the limit
+    pub fn push_notification(&mut self, notification: SentNotification, limit: usize) {
+        self.notifications.push_back(notification);
+        self.retain_history(limit);
+    }
+    /// Stops tracking the latest notification, returning it
+    pub fn pop_notification(&mut self) -> Option<SentNotification> {
+        self.notifications.pop_back()
     }
+    /// Evicts the oldest notifications beyond the limit. The limit may have been lowered since they were sent
+    pub fn retain_history(&mut self, limit: usize) {
+        let excess = self.notifications.len().saturating_sub(limit);
+        self.notifications.drain(..excess);
+    }
+}
+
+/// Notification message, which was sent to a server on status change
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SentNotification {
+    pub channel: ChannelId,
+    pub message: MessageId,
+    pub sent_at: Timestamp,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Config {
     master_server: Option<GuildId>,
+    /// Channel, which receives a feed of significant events and errors
+    #[serde(default)]
+    master_log_channel: Option<ChannelId>,
+    /// Unknown status means a problem on the bot's side, so only the master server shows it.
+    /// Other servers keep showing the last known status
+    #[serde(default)]
+    unknown_to_master_only: bool,
+    /// Whether each check, which counts towards confirming a status change, is posted to the master's log channel
+    #[serde(default)]
+    verbose_confirmation: bool,
+    /// Whether saved data, which fails to serialize into TOML, is written as JSON to `DEFAULT_SAVEDATA_FALLBACK_PATH`
+    #[serde(default)]
+    save_fallback_json: bool,
+    /// Servers, where the bot can be used. If not set, any server can use it. Master server is always allowed
+    #[serde(default)]
+    guild_allowlist: Option<BTreeSet<GuildId>>,
+    /// Whether the bot leaves servers, which are not in the allowlist
+    #[serde(default)]
+    leave_unallowed_guilds: bool,
     max_servers: usize,
+    /// Share of `max_servers` in percent, at which registrations are warned about in the log channel.
+    /// Defaults to `DEFAULT_SERVERS_WARNING_PERCENT`
+    #[serde(default)]
+    servers_warning_percent: Option<u8>,
     ping_config: PingConfig,
     server_configs: BTreeMap<GuildId, ServerConfig>,
+    /// Scheduled periods of planned work, during which outages use maintenance messages
+    #[serde(default)]
+    maintenance_windows: Vec<MaintenanceWindow>,
+    /// How many sent notifications are remembered for each server. Defaults to `DEFAULT_HISTORY_LIMIT`
+    #[serde(default)]
+    history_limit: Option<usize>,
+    /// Optional time, after which the bot leaves a server, where nobody registered it
+    #[serde(default)]
+    leave_unregistered_after: Option<Duration>,
+    /// Optional time after start, during which Unknown status is only logged, since the network may not be ready yet
+    #[serde(default)]
+    startup_grace: Option<Duration>,
+    /// Optional interval of reminders, that the status is still unknown. They are posted to the master server
+    #[serde(default)]
+    unknown_reminder_interval: Option<Duration>,
+    /// Whether the reminders are also posted to the other servers, which show Unknown status
+    #[serde(default)]
+    unknown_reminder_to_servers: bool,
+    /// Colour of the bot's own embeds. Defaults to `DEFAULT_ADMIN_EMBED_COLOUR`
+    #[serde(default)]
+    admin_embed_colour: Option<(u8, u8, u8)>,
+    /// Outbound webhooks, which receive status transitions along with the servers
+    #[serde(default)]
+    webhooks: Vec<WebhookConfig>,
 }
 
 impl Config {
+    /// Default config, which monitors the given resource instead of BYOND
+    pub fn with_resource(resource_name: String, resource_addr: String) -> Self {
+        Self {
+            ping_config: PingConfig {
+                resource_name,
+                resource_addr,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+    /// Copy of the master-owned settings, without the servers' configurations
+    pub fn master_settings(&self) -> Self {
+        Self {
+            server_configs: BTreeMap::new(),
+            ..self.clone()
+        }
+    }
+    /// Restores master-owned settings and the given servers' configurations, keeping the other servers as they are.
+    /// A server without configuration is unregistered
+    pub fn restore_master_settings(
+        &mut self,
+        config: Config,
+        servers: BTreeMap<GuildId, Option<ServerConfig>>,
+    ) {
+        let mut server_configs = std::mem::take(&mut self.server_configs);
+        for (server_id, server_config) in servers {
+            match server_config {
+                Some(server_config) => {
+                    server_configs.insert(server_id, server_config);
+                }
+                None => {
+                    server_configs.remove(&server_id);
+                }
+            }
+        }
+        *self = Self {
+            server_configs,
+            ..config
+        };
+    }
+    pub fn servers_warning_percent(&self) -> u8 {
+        self.servers_warning_percent
+            .unwrap_or(DEFAULT_SERVERS_WARNING_PERCENT)
+    }
+    /// How many more servers can be registered
+    pub fn remaining_servers(&self) -> usize {
+        self.max_servers.saturating_sub(self.server_configs.len())
+    }
+    /// Whether registrations reached the warning share of `max_servers`
+    pub fn servers_nearing_limit(&self) -> bool {
+        self.server_configs.len() * 100
+            >= self.max_servers * self.servers_warning_percent() as usize
+    }
+    pub fn history_limit(&self) -> usize {
+        self.history_limit.unwrap_or(DEFAULT_HISTORY_LIMIT)
+    }
+    pub fn admin_embed_colour(&self) -> (u8, u8, u8) {
+        self.admin_embed_colour
+            .unwrap_or(DEFAULT_ADMIN_EMBED_COLOUR)
+    }
+    /// Whether the server's status embed switches to the Unknown status
+    pub fn shows_unknown(&self, server_id: GuildId) -> bool {
+        !self.unknown_to_master_only || self.master_server == Some(server_id)
+    }
+    /// Whether the bot can be used in the server
+    pub fn guild_allowed(&self, server_id: GuildId) -> bool {
+        self.master_server == Some(server_id)
+            || self
+                .guild_allowlist
+                .as_ref()
+                .is_none_or(|allowlist| allowlist.contains(&server_id))
+    }
+    /// Whether the moment falls into any scheduled maintenance window
+    pub fn in_maintenance(&self, moment: Timestamp) -> bool {
+        self.maintenance_windows
+            .iter()
+            .any(|window| window.contains(moment))
+    }
+    /// Drops maintenance windows, which are over. The window, in which the current status started, is kept, since the
+    /// message about its end depends on it
+    pub fn prune_maintenance_windows(&mut self, now: Timestamp, status_since: Timestamp) {
+        self.maintenance_windows
+            .retain(|window| window.end > now || window.contains(status_since));
+    }
+    /// Warns about the config's problems once it is loaded, so code relying on it can handle them quietly
+    pub fn warn_about_problems(&self) {
+        for problem in self.problems() {
+            log::warn!("Config problem: {}", problem);
+        }
+    }
+    /// Human-readable problems, which don't prevent the config from loading, but break the bot's usage
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        match self.master_server {
+            Some(master_server) if !self.server_configs.contains_key(&master_server) => {
+                problems.push(format!("master server {} is not registered", master_server))
+            }
+            Some(_) => {}
+            None => problems.push("master_server is not set".to_string()),
+        }
+        if self.ping_config.resource_name.chars().count() > MAX_RESOURCE_NAME_LENGTH {
+            problems.push(format!(
+                "resource_name is longer than {} characters, so it is truncated. Change it with /config name",
+                MAX_RESOURCE_NAME_LENGTH
+            ));
+        }
+        if self.server_configs.len() > self.max_servers {
+            problems.push(format!(
+                "{} servers are registered, but max_servers is {}",
+                self.server_configs.len(),
+                self.max_servers
+            ));
+        }
+        // The role is optional, without it nobody is mentioned
+        for (server_id, server_config) in &self.server_configs {
+            if server_config.channel.is_none() {
+                problems.push(format!(
+                    "server {} ({}): channel not set",
+                    server_config.name, server_id
+                ));
+            }
+        }
+        if !(self.ping_config.rtt_smoothing > 0.0 && self.ping_config.rtt_smoothing <= 1.0) {
+            problems.push(format!(
+                "rtt_smoothing is {}, but it must be above 0 and at most 1",
+                self.ping_config.rtt_smoothing
+            ));
+        }
+        problems.extend(webhook_problems(&self.webhooks));
+        problems
+    }
     pub async fn load_from_file<T: AsRef<Path>>(config_path: &T) -> anyhow::Result<Option<Self>> {
         let config_file = tokio::fs::read_to_string(config_path).await;
         if let Ok(config_string) = config_file {
-            Ok(Some(toml::from_str::<Config>(&config_string).map_err(
-                |err| {
-                    anyhow::Error::msg(format!(
-                        "Malformed config data in {}: {}",
-                        config_path.as_ref().to_string_lossy(),
-                        err
-                    ))
-                },
-            )?))
+            let malformed = |err: toml::de::Error| {
+                anyhow::Error::msg(format!(
+                    "Malformed config data in {}: {}",
+                    config_path.as_ref().to_string_lossy(),
+                    err
+                ))
+            };
+            let mut config = toml::from_str::<toml::Value>(&config_string).map_err(malformed)?;
+            migrate_server_messages(&mut config);
+            Ok(Some(config.try_into::<Config>().map_err(malformed)?))
         } else {
             let err = config_file.unwrap_err();
             if let std::io::ErrorKind::NotFound = err.kind() {
@@ -181,15 +932,309 @@ impl Config {
             }
         }
     }
+    /// Writes the config. If the file already exists, its comments and layout are kept for settings, which are still
+    /// there
+    pub async fn save_to_file<T: AsRef<Path>>(&self, config_path: &T) -> anyhow::Result<()> {
+        let serialized_string = toml::to_string_pretty(self).map_err(|err| {
+            anyhow::Error::msg(format!(
+                "Broken serialization of Config: got {}, while serializing {:?}",
+                err, self
+            ))
+        })?;
+        let serialized_string = match tokio::fs::read_to_string(config_path).await {
+            Ok(existing_string) => {
+                let mut existing = existing_string.parse::<DocumentMut>().map_err(|err| {
+                    anyhow::Error::msg(format!(
+                        "Malformed config data in {}: {}",
+                        config_path.as_ref().to_string_lossy(),
+                        err
+                    ))
+                })?;
+                let serialized = serialized_string.parse::<DocumentMut>().map_err(|err| {
+                    anyhow::Error::msg(format!("Broken serialization of Config: {}", err))
+                })?;
+                merge_preserving_comments(existing.as_table_mut(), serialized.as_table());
+                existing.to_string()
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => serialized_string,
+            Err(err) => {
+                return Err(anyhow::Error::msg(format!(
+                    "Failed to open {}: {}",
+                    config_path.as_ref().to_string_lossy(),
+                    err
+                )));
+            }
+        };
+        tokio::fs::write(config_path, serialized_string.as_bytes())
+            .await
+            .map_err(|err| {
+                anyhow::Error::msg(format!(
+                    "failed to write Config to {}: {}",
+                    config_path.as_ref().to_string_lossy(),
+                    err
+                ))
+            })?;
+        Ok(())
+    }
+}
+
+/// Copies values from `source` into `target`, keeping comments and formatting of the keys present in both. Keys, which
+/// are absent from `source`, are removed
+fn merge_preserving_comments(target: &mut toml_edit::Table, source: &toml_edit::Table) {
+    target.retain(|key, _| source.contains_key(key));
+    for (key, source_item) in source.iter() {
+        match (target.get_mut(key), source_item) {
+            (Some(Item::Table(target_table)), Item::Table(source_table)) => {
+                merge_preserving_comments(target_table, source_table)
+            }
+            (Some(Item::Value(target_value)), Item::Value(source_value)) => {
+                let decor = target_value.decor().clone();
+                *target_value = source_value.clone();
+                *target_value.decor_mut() = decor;
+            }
+            _ => {
+                target.insert(key, source_item.clone());
+            }
+        }
+    }
+}
+
+/// Time of day in UTC with minute precision, written as "HH:MM"
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Clone, Copy)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeOfDay {
+    minutes: u16,
+}
+
+impl TimeOfDay {
+    pub fn of(moment: Timestamp) -> Self {
+        let seconds_of_day = moment.unix_timestamp().rem_euclid(86400);
+        Self {
+            minutes: (seconds_of_day / 60) as u16,
+        }
+    }
+}
+
+impl FromStr for TimeOfDay {
+    type Err = anyhow::Error;
+
+    fn from_str(time: &str) -> Result<Self, Self::Err> {
+        let invalid = || anyhow::Error::msg(format!("{} is not a time like \"09:30\"", time));
+        let (hours, minutes) = time.trim().split_once(':').ok_or_else(invalid)?;
+        let hours: u16 = hours.parse().map_err(|_| invalid())?;
+        let minutes: u16 = minutes.parse().map_err(|_| invalid())?;
+        if hours >= 24 || minutes >= 60 {
+            return Err(invalid());
+        }
+        Ok(Self {
+            minutes: hours * 60 + minutes,
+        })
+    }
 }
 
+impl TryFrom<String> for TimeOfDay {
+    type Error = anyhow::Error;
+
+    fn try_from(time: String) -> Result<Self, Self::Error> {
+        time.parse()
+    }
+}
+
+impl From<TimeOfDay> for String {
+    fn from(time: TimeOfDay) -> Self {
+        time.to_string()
+    }
+}
+
+impl Display for TimeOfDay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:02}:{:02}", self.minutes / 60, self.minutes % 60)
+    }
+}
+
+/// Daily period in UTC, during which the resource is checked, e.g. business hours. It may span midnight. If `start`
+/// equals `end`, the resource is checked all day
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+pub struct ActiveHours {
+    pub start: TimeOfDay,
+    pub end: TimeOfDay,
+}
+
+impl ActiveHours {
+    pub fn contains(&self, moment: Timestamp) -> bool {
+        let time = TimeOfDay::of(moment);
+        match self.start.cmp(&self.end) {
+            std::cmp::Ordering::Less => self.start <= time && time < self.end,
+            std::cmp::Ordering::Greater => self.start <= time || time < self.end,
+            std::cmp::Ordering::Equal => true,
+        }
+    }
+}
+
+impl Display for ActiveHours {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{} UTC", self.start, self.end)
+    }
+}
+
+/// Scheduled period of planned work on the resource
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+pub struct MaintenanceWindow {
+    pub start: Timestamp,
+    pub end: Timestamp,
+}
+
+impl MaintenanceWindow {
+    pub fn contains(&self, moment: Timestamp) -> bool {
+        self.start <= moment && moment < self.end
+    }
+}
+
+/// The way resource's health is determined
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone)]
+pub enum CheckKind {
+    /// ICMP echo request
+    #[default]
+    Icmp,
+    /// UDP probe sent to the port. If `expect_response` is set, only a reply counts as up.
+    /// Otherwise silence is fine too and only ICMP port-unreachable counts as down
+    Udp {
+        port: u16,
+        probe: Vec<u8>,
+        expect_response: bool,
+    },
+    /// TCP connection to the service's port, e.g. of a game server or a database. A handshake counts as up, refused
+    /// connections and timeouts as down
+    Tcp { port: u16 },
+    /// ICMP echo request along with a TCP connection to the service's port. Both must succeed for the resource to be
+    /// up, so a host, whose service is down, is told apart from a host, which is down
+    IcmpAndTcp { port: u16 },
+    /// HTTP GET request to the resolved address, e.g. when ICMP is blocked by a firewall. 2xx and 3xx responses count
+    /// as up, other responses and failed connections as down
+    Http {
+        #[serde(default)]
+        https: bool,
+        /// Port, if it isn't the default one of the scheme
+        #[serde(default)]
+        port: Option<u16>,
+        /// Requested path. Empty means "/"
+        #[serde(default)]
+        path: String,
+    },
+}
+
+impl CheckKind {
+    /// The same check, sent to another port, e.g. the one advertised by an SRV record. ICMP has no ports
+    pub fn with_port(&self, port: u16) -> Self {
+        match self {
+            CheckKind::Icmp => CheckKind::Icmp,
+            CheckKind::Udp {
+                probe,
+                expect_response,
+                ..
+            } => CheckKind::Udp {
+                port,
+                probe: probe.clone(),
+                expect_response: *expect_response,
+            },
+            CheckKind::Tcp { .. } => CheckKind::Tcp { port },
+            CheckKind::IcmpAndTcp { .. } => CheckKind::IcmpAndTcp { port },
+            CheckKind::Http { https, path, .. } => CheckKind::Http {
+                https: *https,
+                port: Some(port),
+                path: path.clone(),
+            },
+        }
+    }
+    /// The check with its port changed. ICMP ping turns into TCP connections to the port, and TCP connections without
+    /// a port turn back into ICMP ping. HTTP goes back to the scheme's default port. `None`, if the check can't go
+    /// without a port
+    pub fn with_port_changed(&self, port: Option<u16>) -> Option<Self> {
+        match (self, port) {
+            (CheckKind::Icmp, Some(port)) => Some(CheckKind::Tcp { port }),
+            (_, Some(port)) => Some(self.with_port(port)),
+            (CheckKind::Icmp | CheckKind::Tcp { .. }, None) => Some(CheckKind::Icmp),
+            (CheckKind::Http { https, path, .. }, None) => Some(CheckKind::Http {
+                https: *https,
+                port: None,
+                path: path.clone(),
+            }),
+            (CheckKind::Udp { .. } | CheckKind::IcmpAndTcp { .. }, None) => None,
+        }
+    }
+}
+
+/// Another resource, checked along with the monitored one. Its status is only shown in the status embed, e.g. BYOND hub
+/// next to a game server, since the hub being up doesn't mean the server is joinable
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LinkedProbe {
+    name: String,
+    addr: String,
+    check_kind: CheckKind,
+    /// Checks in a row, which must disagree with the shown status before it changes, like
+    /// `required_attempts_before_notification`, so a flapping probe doesn't repost the embeds on every flip
+    required_attempts_before_change: u8,
+}
+
+impl Default for LinkedProbe {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            addr: String::new(),
+            check_kind: CheckKind::default(),
+            required_attempts_before_change: DEFAULT_ATTEMPTS_BEFORE_NOTIFICATION,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct PingConfig {
     resource_name: String,
     resource_addr: String,
+    /// Additional addresses, which are checked along with `resource_addr` to determine the status together
+    extra_probe_addrs: Vec<String>,
+    /// How many probes must fail for the resource to be down. `None` means that the majority decides
+    quorum: Option<u8>,
+    check_kind: CheckKind,
     required_attempts_before_notification: u8,
     timeout: Duration,
+    /// Resolving the address is bounded separately, so a hung resolver doesn't stall the check
+    dns_timeout: Duration,
+    /// Successful resolution, which takes longer, is reported as slow. It's an early warning of resolver degradation
+    dns_slow_threshold: Option<Duration>,
     interval_between_attempts: Duration,
+    /// How many times a failed check is retried within one tick, before it counts as failed
+    check_retries: u8,
+    /// How long the resource must be responding without interruption before it is declared up
+    min_stable_duration: Duration,
+    /// Minimal time between failed checks, which count towards confirming an outage, so bursty loss isn't one
+    down_check_spacing: Duration,
+    /// Consecutive healthcheck errors, after which the bot exits with nonzero code to be restarted by a supervisor
+    self_restart_after_errors: Option<NonZeroU32>,
+    /// Whether the master server is notified before the bot exits because of `self_restart_after_errors`
+    self_restart_notify_master: bool,
+    /// How many probes are checked at the same time, so a timing out one doesn't delay the rest
+    check_concurrency: NonZeroU8,
+    /// How many checks may be in progress at the same time, counting commands. Further ones are skipped, so timing
+    /// out checks can't pile up sockets
+    max_outstanding_checks: NonZeroU8,
+    /// Whether a throwaway probe is sent before the counted one, since some stateful middleboxes drop the first
+    /// packet after idle
+    warmup_ping: bool,
+    /// Whether both IPv4 and IPv6 addresses of the resource are checked. The resource is up if either works, but the
+    /// broken family is reported
+    dual_stack: bool,
+    /// Whether probed addresses are SRV names, e.g. "_minecraft._tcp.example.com". The most preferred target is checked,
+    /// on the advertised port for UDP checks
+    resolve_srv: bool,
+    /// Weight of the newest round-trip time in the smoothed one, shown in the status embed. 1 shows the latest one
+    rtt_smoothing: f64,
+    /// Daily period, outside of which the resource isn't checked and its status is Inactive. Checked all the time if
+    /// not set
+    active_hours: Option<ActiveHours>,
+    linked_probe: Option<LinkedProbe>,
 }
 
 impl Default for PingConfig {
@@ -197,30 +1242,183 @@ impl Default for PingConfig {
         Self {
             resource_name: DEFAULT_RESOURCE_NAME.to_string(),
             resource_addr: DEFAULT_RESOURCE_ADDR.to_string(),
+            extra_probe_addrs: Vec::new(),
+            quorum: None,
+            check_kind: CheckKind::default(),
             required_attempts_before_notification: DEFAULT_ATTEMPTS_BEFORE_NOTIFICATION,
             timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            dns_timeout: Duration::from_secs(DEFAULT_DNS_TIMEOUT_SECS),
+            dns_slow_threshold: None,
             interval_between_attempts: Duration::from_secs(DEFAULT_INTERVAL_BETWEEN_ATTEMPTS_SECS),
+            check_retries: 0,
+            min_stable_duration: Duration::ZERO,
+            down_check_spacing: Duration::ZERO,
+            self_restart_after_errors: None,
+            self_restart_notify_master: false,
+            check_concurrency: DEFAULT_CHECK_CONCURRENCY,
+            max_outstanding_checks: DEFAULT_MAX_OUTSTANDING_CHECKS,
+            warmup_ping: false,
+            dual_stack: false,
+            resolve_srv: false,
+            rtt_smoothing: DEFAULT_RTT_SMOOTHING,
+            active_hours: None,
+            linked_probe: None,
+        }
+    }
+}
+
+impl PingConfig {
+    /// Whether the resource is checked at the moment, i.e. it falls into `active_hours`
+    pub fn is_active(&self, moment: Timestamp) -> bool {
+        self.active_hours
+            .is_none_or(|active_hours| active_hours.contains(moment))
+    }
+    /// Addresses of all probes, starting with the resource's one
+    pub fn probe_addrs(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.resource_addr.as_str())
+            .chain(self.extra_probe_addrs.iter().map(String::as_str))
+    }
+    /// Removes extra probes along with the quorum, which can't be reached by the resource's probe alone
+    pub fn reset_extra_probes(&mut self) {
+        let default = PingConfig::default();
+        self.extra_probe_addrs = default.extra_probe_addrs;
+        self.quorum = default.quorum;
+    }
+}
+
+/// Kind of status transition, which has its own message
+#[derive(
+    poise::ChoiceParameter,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TransitionKind {
+    Up,
+    Down,
+    /// Resource goes down during a scheduled maintenance window
+    #[name = "Maintenance start"]
+    MaintenanceStart,
+    /// Resource recovers from an outage, which began during scheduled maintenance
+    #[name = "Maintenance end"]
+    MaintenanceEnd,
+}
+
+impl TransitionKind {
+    pub const ALL: [TransitionKind; 4] = [
+        TransitionKind::Up,
+        TransitionKind::Down,
+        TransitionKind::MaintenanceStart,
+        TransitionKind::MaintenanceEnd,
+    ];
+    /// Key of the message in `messages` of the server config
+    pub fn key(self) -> &'static str {
+        match self {
+            TransitionKind::Up => "up",
+            TransitionKind::Down => "down",
+            TransitionKind::MaintenanceStart => "maintenance_start",
+            TransitionKind::MaintenanceEnd => "maintenance_end",
+        }
+    }
+    pub fn default_message(self) -> &'static str {
+        match self {
+            TransitionKind::Up => DEFAULT_UP_MESSAGE,
+            TransitionKind::Down => DEFAULT_DOWN_MESSAGE,
+            TransitionKind::MaintenanceStart => DEFAULT_MAINTENANCE_START_MESSAGE,
+            TransitionKind::MaintenanceEnd => DEFAULT_MAINTENANCE_END_MESSAGE,
+        }
+    }
+    /// Transition of the opposite direction, for servers where the resource being up is bad news
+    pub fn inverted(self) -> Self {
+        match self {
+            TransitionKind::Up => TransitionKind::Down,
+            TransitionKind::Down => TransitionKind::Up,
+            TransitionKind::MaintenanceStart => TransitionKind::MaintenanceEnd,
+            TransitionKind::MaintenanceEnd => TransitionKind::MaintenanceStart,
         }
     }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct ServerConfig {
     name: String,
+    /// When the server was registered. Not known for servers registered before it was tracked
+    registered_at: Option<Timestamp>,
     channel: Option<ChannelId>,
     role_to_notify: Option<RoleId>,
-    up_message: String,
-    down_message: String,
+    /// Messages sent on status transitions. Kinds without one use the default message
+    messages: BTreeMap<TransitionKind, String>,
+    /// Whether %%ROLE%% mentions the role in the up message
+    ping_on_up: bool,
+    /// Whether %%ROLE%% mentions the role in the down message
+    ping_on_down: bool,
+    /// Minimal time between role mentions. Messages within it are still sent, but without the mention.
+    /// The status embed is updated regardless of it
+    mention_cooldown: Duration,
+    /// Whether the server gets the persistent status embed. If not, only up and down messages are sent
+    embed_enabled: bool,
+    /// Path to a file with custom layout of the status embed
+    embed_template: Option<PathBuf>,
+    /// Label of the default embed's field with the time of the last status change
+    since_label: String,
+    /// Label of the default embed's field with the resource's address
+    address_label: String,
+    /// Label of the default embed's field with the smoothed round-trip time
+    latency_label: String,
+    /// Members with this role can run read-only commands without MANAGE_CHANNELS
+    observer_role: Option<RoleId>,
+    /// Members with this role can change configuration without MANAGE_CHANNELS
+    admin_role: Option<RoleId>,
+    /// Whether to warn, when the status message turns out to be edited by someone else
+    detect_manual_edits: bool,
+    /// Whether the status message is pinned in the notification channel. Requires Manage Messages permission
+    pin_status_message: bool,
+    /// Whether the previous up/down message is deleted, when a new one is sent, so only the latest one is kept
+    cleanup_messages: bool,
+    /// Reactions added to the up message
+    up_reactions: Vec<String>,
+    /// Reactions added to the down message
+    down_reactions: Vec<String>,
+    /// Whether the resource being up is bad news, e.g. a maintenance page. The up message, its mention and reactions
+    /// are then used, when the resource goes down, and vice versa. Colours of the default embed are swapped too
+    inverted: bool,
 }
 
 impl Default for ServerConfig {
     fn default() -> Self {
         Self {
             name: "Noname server".to_string(),
+            registered_at: None,
             channel: None,
             role_to_notify: None,
-            up_message: DEFAULT_UP_MESSAGE.to_string(),
-            down_message: DEFAULT_DOWN_MESSAGE.to_string(),
+            messages: TransitionKind::ALL
+                .into_iter()
+                .map(|kind| (kind, kind.default_message().to_string()))
+                .collect(),
+            ping_on_up: true,
+            ping_on_down: true,
+            mention_cooldown: Duration::ZERO,
+            embed_enabled: true,
+            embed_template: None,
+            since_label: DEFAULT_SINCE_LABEL.to_string(),
+            address_label: DEFAULT_ADDRESS_LABEL.to_string(),
+            latency_label: DEFAULT_LATENCY_LABEL.to_string(),
+            observer_role: None,
+            admin_role: None,
+            detect_manual_edits: false,
+            pin_status_message: false,
+            cleanup_messages: false,
+            up_reactions: Vec::new(),
+            down_reactions: Vec::new(),
+            inverted: false,
         }
     }
 }
@@ -229,23 +1427,678 @@ impl ServerConfig {
     fn with_name(name: String) -> Self {
         Self {
             name,
+            registered_at: Some(Timestamp::now()),
             ..Default::default()
         }
     }
+    fn message(&self, kind: TransitionKind) -> &str {
+        self.messages
+            .get(&kind)
+            .map_or(kind.default_message(), String::as_str)
+    }
+    /// Names of settings, which must be configured before the server receives notifications
+    pub fn missing_setup(&self) -> Vec<&'static str> {
+        let mut missing = Vec::new();
+        if self.channel.is_none() {
+            missing.push("channel");
+        }
+        if self.role_to_notify.is_none() {
+            missing.push("role");
+        }
+        missing
+    }
+    pub fn is_setup_complete(&self) -> bool {
+        self.missing_setup().is_empty()
+    }
 }
 
 pub async fn save_data<T: AsRef<AppData>>(data: T) {
-    let new_saved_data = SavedData::load_from(data.as_ref()).await;
-    if let Err(err) = new_saved_data.save_to_file(&DEFAULT_SAVEDATA_PATH).await {
-        log::error!(
-            "Failed to save SaveData to {}: {}",
-            &DEFAULT_SAVEDATA_PATH,
-            err
-        )
-    } else {
+    let data = data.as_ref();
+    let new_saved_data = SavedData::load_from(data).await;
+    let Err(err) = new_saved_data.save_to_file(&DEFAULT_SAVEDATA_PATH).await else {
+        data.save_serialization_failures.store(0, Ordering::Relaxed);
         log::info!("Saved SaveData to {}", DEFAULT_SAVEDATA_PATH);
+        return;
+    };
+    log::error!(
+        "Failed to save SaveData to {}: {}",
+        &DEFAULT_SAVEDATA_PATH,
+        err
+    );
+    if !err.is::<SerializationError>() {
+        return;
+    }
+    log::debug!("SaveData, which failed to serialize: {:?}", new_saved_data);
+    let failures = data
+        .save_serialization_failures
+        .fetch_add(1, Ordering::Relaxed)
+        + 1;
+    if failures == SAVE_FAILURES_BEFORE_ALERT {
+        log::error!(
+            target: EVENTS_TARGET,
+            "SaveData failed to serialize {} times in a row, changes are not persisted! See the log for details",
+            failures
+        );
+    }
+    if data.config.read().await.save_fallback_json {
+        match new_saved_data
+            .save_json_fallback(&DEFAULT_SAVEDATA_FALLBACK_PATH)
+            .await
+        {
+            Ok(()) => log::warn!(
+                "Saved SaveData as JSON to {} instead",
+                DEFAULT_SAVEDATA_FALLBACK_PATH
+            ),
+            Err(err) => log::error!(
+                "Failed to save SaveData as JSON to {}: {}",
+                DEFAULT_SAVEDATA_FALLBACK_PATH,
+                err
+            ),
+        }
     }
 }
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 pub type Context<'a> = poise::Context<'a, Data, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_config_has_no_problems() {
+        let config: Config = toml::from_str(include_str!("../Config.example.toml")).unwrap();
+        assert_eq!(config.problems(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn changing_port_keeps_check_kind() {
+        let udp = CheckKind::Udp {
+            port: 27015,
+            probe: b"ping".to_vec(),
+            expect_response: true,
+        };
+        assert_eq!(
+            udp.with_port_changed(Some(27016)),
+            Some(CheckKind::Udp {
+                port: 27016,
+                probe: b"ping".to_vec(),
+                expect_response: true,
+            })
+        );
+        assert_eq!(udp.with_port_changed(None), None);
+        assert_eq!(
+            CheckKind::Icmp.with_port_changed(Some(80)),
+            Some(CheckKind::Tcp { port: 80 })
+        );
+        assert_eq!(
+            CheckKind::Tcp { port: 80 }.with_port_changed(None),
+            Some(CheckKind::Icmp)
+        );
+    }
+
+    #[test]
+    fn resetting_extra_probes_resets_quorum() {
+        let mut ping_config = PingConfig {
+            extra_probe_addrs: vec!["127.0.0.2".to_string(), "127.0.0.3".to_string()],
+            quorum: Some(3),
+            ..Default::default()
+        };
+        ping_config.reset_extra_probes();
+        assert_eq!(ping_config.probe_addrs().count(), 1);
+        assert_eq!(ping_config.quorum, None);
+    }
+
+    #[test]
+    fn undo_keeps_other_servers() {
+        let mut config = Config {
+            max_servers: 5,
+            ..Default::default()
+        };
+        config.server_configs.insert(
+            GuildId::new(1),
+            ServerConfig::with_name("First".to_string()),
+        );
+        let master_settings = config.master_settings();
+        assert!(master_settings.server_configs.is_empty());
+        let servers = BTreeMap::from([(GuildId::new(2), None)]);
+
+        // Master registers server 2 and raises the limit, then another server changes its own configuration
+        config.max_servers = 10;
+        config.server_configs.insert(
+            GuildId::new(2),
+            ServerConfig::with_name("Second".to_string()),
+        );
+        config
+            .server_configs
+            .get_mut(&GuildId::new(1))
+            .unwrap()
+            .name = "Renamed".to_string();
+
+        config.restore_master_settings(master_settings, servers);
+        assert_eq!(config.max_servers, 5);
+        assert!(!config.server_configs.contains_key(&GuildId::new(2)));
+        assert_eq!(config.server_configs[&GuildId::new(1)].name, "Renamed");
+    }
+
+    #[test]
+    fn config_problems_are_reported() {
+        let mut config = Config {
+            master_server: Some(GuildId::new(1)),
+            max_servers: 1,
+            ..Default::default()
+        };
+        assert_eq!(config.problems(), vec!["master server 1 is not registered"]);
+
+        config.server_configs.insert(
+            GuildId::new(1),
+            ServerConfig {
+                channel: Some(ChannelId::new(100)),
+                ..Default::default()
+            },
+        );
+        // Role to notify is optional
+        assert!(config.problems().is_empty());
+
+        config.server_configs.insert(
+            GuildId::new(2),
+            ServerConfig::with_name("Second".to_string()),
+        );
+        assert_eq!(
+            config.problems(),
+            vec![
+                "2 servers are registered, but max_servers is 1",
+                "server Second (2): channel not set"
+            ]
+        );
+    }
+
+    #[test]
+    fn servers_limit_is_warned_about() {
+        let mut config = Config {
+            max_servers: 5,
+            ..Default::default()
+        };
+        for id in 1..=3 {
+            config
+                .server_configs
+                .insert(GuildId::new(id), ServerConfig::default());
+        }
+        assert_eq!(config.remaining_servers(), 2);
+        assert!(!config.servers_nearing_limit());
+
+        config
+            .server_configs
+            .insert(GuildId::new(4), ServerConfig::default());
+        assert!(config.servers_nearing_limit());
+
+        config.servers_warning_percent = Some(100);
+        assert!(!config.servers_nearing_limit());
+        config
+            .server_configs
+            .insert(GuildId::new(5), ServerConfig::default());
+        assert!(config.servers_nearing_limit());
+        assert_eq!(config.remaining_servers(), 0);
+    }
+
+    #[test]
+    fn unknown_can_be_shown_only_to_master() {
+        let mut config = Config {
+            master_server: Some(GuildId::new(1)),
+            ..Default::default()
+        };
+        assert!(config.shows_unknown(GuildId::new(2)));
+
+        config.unknown_to_master_only = true;
+        assert!(config.shows_unknown(GuildId::new(1)));
+        assert!(!config.shows_unknown(GuildId::new(2)));
+    }
+
+    #[test]
+    fn startup_grace_ends() {
+        let mut data = AppData::default();
+        let mut config = Config::default();
+        assert!(!data.in_startup_grace(&config));
+
+        config.startup_grace = Some(Duration::from_secs(60));
+        assert!(data.in_startup_grace(&config));
+
+        data.started_at = StartedAt(Instant::now() - Duration::from_secs(61));
+        assert!(!data.in_startup_grace(&config));
+    }
+
+    #[test]
+    fn delivery_latency_is_aggregated() {
+        let mut latency = DeliveryLatency::default();
+        assert_eq!(latency.average(), Duration::ZERO);
+
+        latency.record(Duration::from_millis(300));
+        latency.record(Duration::from_millis(100));
+
+        assert_eq!(latency.last, Duration::from_millis(100));
+        assert_eq!(latency.worst, Duration::from_millis(300));
+        assert_eq!(latency.average(), Duration::from_millis(200));
+        assert_eq!(latency.deliveries, 2);
+    }
+
+    #[test]
+    fn rtt_average_smooths_spikes() {
+        let mut rtt = RttAverage::default();
+        assert_eq!(rtt.get(), None);
+
+        rtt.update(Duration::from_millis(100), 0.25);
+        assert_eq!(rtt.get(), Some(Duration::from_millis(100)));
+
+        rtt.update(Duration::from_millis(500), 0.25);
+        assert_eq!(rtt.get(), Some(Duration::from_millis(200)));
+        assert_eq!(rtt.last(), Some(Duration::from_millis(500)));
+
+        rtt.update(Duration::from_millis(40), 1.0);
+        assert_eq!(rtt.get(), Some(Duration::from_millis(40)));
+    }
+
+    #[test]
+    fn guild_allowlist_always_allows_master() {
+        let mut config = Config {
+            master_server: Some(GuildId::new(1)),
+            ..Default::default()
+        };
+        assert!(config.guild_allowed(GuildId::new(2)));
+
+        config.guild_allowlist = Some(BTreeSet::from([GuildId::new(3)]));
+        assert!(config.guild_allowed(GuildId::new(1)));
+        assert!(!config.guild_allowed(GuildId::new(2)));
+        assert!(config.guild_allowed(GuildId::new(3)));
+    }
+
+    #[tokio::test]
+    async fn only_stale_unregistered_guilds_are_left() {
+        let data = AppData::default();
+        let at = |secs| Timestamp::from_unix_timestamp(secs).unwrap();
+        {
+            let mut config_lock = data.config.write().await;
+            config_lock.master_server = Some(GuildId::new(1));
+            config_lock
+                .server_configs
+                .insert(GuildId::new(2), ServerConfig::default());
+        }
+        {
+            let mut joins_lock = data.guild_joins.write().await;
+            for id in 1..=4 {
+                joins_lock.insert(GuildId::new(id), (id.to_string(), at(0)));
+            }
+            joins_lock.insert(GuildId::new(5), ("5".to_string(), at(50)));
+        }
+        assert!(data.stale_unregistered_guilds(at(100)).await.is_empty());
+
+        data.config.write().await.leave_unregistered_after = Some(Duration::from_secs(100));
+        assert_eq!(
+            data.stale_unregistered_guilds(at(100)).await,
+            vec![
+                (GuildId::new(3), "3".to_string()),
+                (GuildId::new(4), "4".to_string())
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn address_override_reverts_after_expiry() {
+        let data = AppData::default();
+        let start = Instant::now();
+        data.override_address(
+            "test.example.com".to_string(),
+            start + Duration::from_secs(60),
+        )
+        .await;
+
+        let overridden = data.effective_ping_config(start).await;
+        assert_eq!(overridden.resource_addr, "test.example.com");
+
+        let reverted = data
+            .effective_ping_config(start + Duration::from_secs(60))
+            .await;
+        assert_eq!(reverted.resource_addr, DEFAULT_RESOURCE_ADDR);
+        assert!(data.address_override.read().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn address_override_can_be_cleared_early() {
+        let data = AppData::default();
+        let start = Instant::now();
+        data.override_address(
+            "test.example.com".to_string(),
+            start + Duration::from_secs(60),
+        )
+        .await;
+
+        assert_eq!(
+            data.clear_address_override().await.as_deref(),
+            Some("test.example.com")
+        );
+        let cleared = data.effective_ping_config(start).await;
+        assert_eq!(cleared.resource_addr, DEFAULT_RESOURCE_ADDR);
+        assert!(data.clear_address_override().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn repeated_interaction_is_ignored_within_window() {
+        let data = AppData::default();
+        let start = Instant::now();
+
+        assert!(data.first_seen_interaction(1, start).await);
+        assert!(
+            !data
+                .first_seen_interaction(1, start + Duration::from_secs(1))
+                .await
+        );
+        assert!(
+            data.first_seen_interaction(2, start + Duration::from_secs(1))
+                .await
+        );
+
+        let later = start + INTERACTION_DEDUP_WINDOW + Duration::from_secs(2);
+        assert!(data.first_seen_interaction(1, later).await);
+        assert_eq!(data.recent_interactions.read().await.len(), 1);
+    }
+
+    #[test]
+    fn mention_waits_for_cooldown() {
+        let at = |secs| Timestamp::from_unix_timestamp(secs).unwrap();
+        let cooldown = Duration::from_secs(60);
+        let mut messages = ServerUsedMessages::default();
+        assert!(messages.mention_allowed(at(100), cooldown));
+
+        messages.set_last_mention(at(100));
+        assert!(!messages.mention_allowed(at(159), cooldown));
+        assert!(messages.mention_allowed(at(160), cooldown));
+        assert!(messages.mention_allowed(at(100), Duration::ZERO));
+    }
+
+    #[test]
+    fn notification_history_evicts_oldest() {
+        let notification = |id| SentNotification {
+            channel: ChannelId::new(100),
+            message: MessageId::new(id),
+            sent_at: Timestamp::from_unix_timestamp(id as i64).unwrap(),
+        };
+        let mut messages = ServerUsedMessages::default();
+        for id in 1..=3 {
+            messages.push_notification(notification(id), 2);
+        }
+        assert_eq!(messages.notifications, [notification(2), notification(3)]);
+
+        messages.set_status((ChannelId::new(100), MessageId::new(10)), None);
+        assert_eq!(messages.notifications.len(), 2);
+
+        assert_eq!(messages.pop_notification(), Some(notification(3)));
+        assert_eq!(messages.notifications, [notification(2)]);
+
+        messages.retain_history(0);
+        assert!(messages.notifications.is_empty());
+    }
+
+    #[test]
+    fn merge_keeps_comments() {
+        let mut target = "# Resource to check\n[ping_config]\n# Shown in embeds\nresource_name = \"Old\" # inline\nstale = 1\n"
+            .parse::<DocumentMut>()
+            .unwrap();
+        let source = "[ping_config]\nresource_name = \"New\"\nresource_addr = \"example.com\"\n"
+            .parse::<DocumentMut>()
+            .unwrap();
+
+        merge_preserving_comments(target.as_table_mut(), source.as_table());
+
+        assert_eq!(
+            target.to_string(),
+            "# Resource to check\n[ping_config]\n# Shown in embeds\nresource_name = \"New\" # inline\nresource_addr = \"example.com\"\n"
+        );
+    }
+
+    #[test]
+    fn maintenance_window_excludes_its_end() {
+        let config = Config {
+            maintenance_windows: vec![MaintenanceWindow {
+                start: Timestamp::from_unix_timestamp(100).unwrap(),
+                end: Timestamp::from_unix_timestamp(200).unwrap(),
+            }],
+            ..Default::default()
+        };
+        let at = |secs| Timestamp::from_unix_timestamp(secs).unwrap();
+
+        assert!(!config.in_maintenance(at(99)));
+        assert!(config.in_maintenance(at(100)));
+        assert!(config.in_maintenance(at(199)));
+        assert!(!config.in_maintenance(at(200)));
+    }
+
+    #[test]
+    fn overran_maintenance_window_is_kept_until_recovery() {
+        let at = |secs| Timestamp::from_unix_timestamp(secs).unwrap();
+        let mut config = Config {
+            maintenance_windows: vec![
+                MaintenanceWindow {
+                    start: at(0),
+                    end: at(50),
+                },
+                MaintenanceWindow {
+                    start: at(100),
+                    end: at(200),
+                },
+            ],
+            ..Default::default()
+        };
+
+        // Outage began during the second window and is still going on after it
+        config.prune_maintenance_windows(at(300), at(150));
+        assert_eq!(config.maintenance_windows.len(), 1);
+        assert!(config.in_maintenance(at(150)));
+
+        config.prune_maintenance_windows(at(300), at(250));
+        assert!(config.maintenance_windows.is_empty());
+    }
+
+    #[test]
+    fn active_hours_may_span_midnight() {
+        let at = |hours: i64, minutes: i64| {
+            Timestamp::from_unix_timestamp(hours * 3600 + minutes * 60).unwrap()
+        };
+        let hours = |start: &str, end: &str| ActiveHours {
+            start: start.parse().unwrap(),
+            end: end.parse().unwrap(),
+        };
+
+        let business = hours("09:00", "17:30");
+        assert!(!business.contains(at(8, 59)));
+        assert!(business.contains(at(9, 0)));
+        assert!(business.contains(at(17, 29)));
+        assert!(!business.contains(at(17, 30)));
+
+        let night = hours("22:00", "06:00");
+        assert!(night.contains(at(23, 0)));
+        assert!(night.contains(at(24 + 5, 0)));
+        assert!(!night.contains(at(12, 0)));
+
+        assert!(hours("10:00", "10:00").contains(at(3, 0)));
+        assert!("24:00".parse::<TimeOfDay>().is_err());
+        assert_eq!(hours("7:05", "19:00").to_string(), "07:05-19:00 UTC");
+    }
+
+    #[test]
+    fn legacy_status_message_is_paired_with_channel() {
+        let mut config = Config::default();
+        config.server_configs.insert(
+            GuildId::new(1),
+            ServerConfig {
+                channel: Some(ChannelId::new(100)),
+                ..Default::default()
+            },
+        );
+        config
+            .server_configs
+            .insert(GuildId::new(2), ServerConfig::default());
+        let mut saved_data = toml::Value::try_from(SavedData {
+            version: 0,
+            config,
+            ..Default::default()
+        })
+        .unwrap();
+        let legacy_used_messages = toml::from_str::<toml::Value>(
+            r#"
+            [1]
+            status = "10"
+            [2]
+            status = "20"
+            "#,
+        )
+        .unwrap();
+        saved_data
+            .as_table_mut()
+            .unwrap()
+            .insert("used_messages".to_string(), legacy_used_messages);
+        migrate_saved_data(&mut saved_data).unwrap();
+        let saved_data = saved_data.try_into::<SavedData>().unwrap();
+
+        assert_eq!(saved_data.version, SAVEDATA_VERSION);
+        assert_eq!(
+            saved_data.used_messages[&GuildId::new(1)].status,
+            Some((ChannelId::new(100), MessageId::new(10)))
+        );
+        assert_eq!(saved_data.used_messages[&GuildId::new(2)].status, None);
+    }
+
+    #[test]
+    fn separate_messages_are_moved_into_table() {
+        let mut config = toml::from_str::<toml::Value>(
+            r#"
+            [server_configs.1]
+            up_message = "Old up"
+            down_message = "Old down"
+            [server_configs.1.messages]
+            down = "New down"
+            "#,
+        )
+        .unwrap();
+        migrate_server_messages(&mut config);
+        let server_config = config["server_configs"]["1"]
+            .clone()
+            .try_into::<ServerConfig>()
+            .unwrap();
+
+        assert_eq!(server_config.message(TransitionKind::Up), "Old up");
+        assert_eq!(server_config.message(TransitionKind::Down), "New down");
+        assert_eq!(
+            server_config.message(TransitionKind::MaintenanceStart),
+            DEFAULT_MAINTENANCE_START_MESSAGE
+        );
+        let saved = toml::Value::try_from(&server_config).unwrap();
+        assert_eq!(saved["messages"]["up"].as_str(), Some("Old up"));
+    }
+
+    #[test]
+    fn config_snapshots_survive_saving() {
+        let saved_data = SavedData {
+            config_snapshots: BTreeMap::from([(
+                "before-migration".to_string(),
+                ConfigSnapshot {
+                    taken_at: Timestamp::from_unix_timestamp(100).unwrap(),
+                    taken_by: "admin".to_string(),
+                    config: Config {
+                        max_servers: 5,
+                        ..Default::default()
+                    },
+                },
+            )]),
+            ..Default::default()
+        };
+        let serialized = toml::to_string_pretty(&saved_data).unwrap();
+        let saved_data = toml::from_str::<SavedData>(&serialized).unwrap();
+
+        let snapshot = &saved_data.config_snapshots["before-migration"];
+        assert_eq!(snapshot.taken_by, "admin");
+        assert_eq!(snapshot.config.max_servers, 5);
+    }
+
+    #[test]
+    fn saved_data_round_trips() {
+        let mut config = Config::with_resource("Paradise".to_string(), "example.com".to_string());
+        config.master_server = Some(GuildId::new(1));
+        config.server_configs.insert(
+            GuildId::new(1),
+            ServerConfig {
+                channel: Some(ChannelId::new(100)),
+                role_to_notify: Some(RoleId::new(200)),
+                mention_cooldown: Duration::from_secs(60),
+                ..ServerConfig::with_name("Master \"quoted\"".to_string())
+            },
+        );
+        config.maintenance_windows.push(MaintenanceWindow {
+            start: Timestamp::from_unix_timestamp(100).unwrap(),
+            end: Timestamp::from_unix_timestamp(200).unwrap(),
+        });
+        config.startup_grace = Some(Duration::from_secs(30));
+        config.admin_embed_colour = Some((1, 2, 3));
+        config.ping_config.check_kind = CheckKind::Udp {
+            port: 27015,
+            probe: vec![255, 255],
+            expect_response: true,
+        };
+        let saved_data = SavedData {
+            status: ResourceStatus::Down,
+            used_messages: BTreeMap::from([(
+                GuildId::new(1),
+                ServerUsedMessages::new(
+                    Some((ChannelId::new(100), MessageId::new(10))),
+                    Some("hash".to_string()),
+                ),
+            )]),
+            attempts_before_notification: 2,
+            last_status_change: Timestamp::from_unix_timestamp(300).unwrap(),
+            config_snapshots: BTreeMap::from([(
+                "before".to_string(),
+                ConfigSnapshot {
+                    taken_at: Timestamp::from_unix_timestamp(250).unwrap(),
+                    taken_by: "admin".to_string(),
+                    config: config.clone(),
+                },
+            )]),
+            config,
+            ..Default::default()
+        };
+
+        let serialized = toml::to_string_pretty(&saved_data).unwrap();
+        let deserialized = toml::from_str::<SavedData>(&serialized).unwrap();
+        assert_eq!(toml::to_string_pretty(&deserialized).unwrap(), serialized);
+        // The fallback must work for the same state too
+        assert!(serde_json::to_string(&saved_data).is_ok());
+    }
+
+    #[test]
+    fn newer_saved_data_is_rejected() {
+        let mut saved_data = toml::Value::try_from(SavedData {
+            version: SAVEDATA_VERSION + 1,
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert!(migrate_saved_data(&mut saved_data).is_err());
+    }
+
+    #[test]
+    fn paired_status_message_is_kept() {
+        let saved_data = SavedData {
+            used_messages: BTreeMap::from([(
+                GuildId::new(1),
+                ServerUsedMessages::new(Some((ChannelId::new(100), MessageId::new(10))), None),
+            )]),
+            ..Default::default()
+        };
+        let mut value = toml::Value::try_from(&saved_data).unwrap();
+        migrate_used_messages(&mut value);
+        let saved_data = value.try_into::<SavedData>().unwrap();
+
+        assert_eq!(
+            saved_data.used_messages[&GuildId::new(1)].status,
+            Some((ChannelId::new(100), MessageId::new(10)))
+        );
+    }
+}
@@ -1,15 +1,57 @@
-use std::{process::exit, sync::Arc, time::SystemTime};
+use std::{
+    fs::File,
+    io::Write,
+    process::exit,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{SyncSender, TrySendError},
+    },
+    time::{Duration, Instant, SystemTime},
+};
 
 use dotenv::dotenv;
-use poise::serenity_prelude::{self as serenity, GatewayIntents, Http, Timestamp};
+use poise::serenity_prelude::{self as serenity, Client, GatewayIntents, Http, Timestamp};
+use tokio::sync::mpsc::{self, UnboundedSender};
 
 use discord_watchdog::{
-    Config, DEFAULT_CONFIG_PATH, DEFAULT_LOG_PATH, DEFAULT_SAVEDATA_PATH, Data, SavedData,
-    THIS_RUN_START, commands::get_commands, ping::ping_task,
+    Config, DEFAULT_CONFIG_PATH, DEFAULT_LOG_PATH, DEFAULT_RESOURCE_ADDR, DEFAULT_RESOURCE_NAME,
+    DEFAULT_SAVEDATA_PATH, Data, SavedData, THIS_RUN_START,
+    commands::{command_check, get_commands},
+    events::{events_task, format_event, is_significant},
+    guilds::{guild_sweep_task, handle_event},
+    ping::ping_task,
+    update_presence,
 };
 
+const STARTUP_SAVE_ATTEMPTS: u32 = 4;
+const STARTUP_SAVE_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// How long the Discord client may take to connect on startup, unless overridden with CLIENT_CONNECT_TIMEOUT
+const DEFAULT_CLIENT_CONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+
+const CLIENT_RECONNECT_BASE_DELAY: Duration = Duration::from_secs(5);
+const CLIENT_RECONNECT_MAX_DELAY: Duration = Duration::from_secs(300);
+
+const CLI_USAGE: &str = "Usage: discord_watchdog [COMMAND]
+
+Runs the bot, if no command is given.
+
+Commands:
+  validate     Checks Config.toml and Data.toml, exits with nonzero code on any problem
+  show-config  Prints configuration currently in effect
+  export-config
+               Writes configuration currently in effect to Config.toml, keeping its comments
+  migrate      Upgrades Data.toml to the current layout
+  help         Prints this message";
+
 #[tokio::main]
 async fn main() {
+    // Config management from the shell doesn't need Discord or even logs
+    if let Some(command) = std::env::args().nth(1) {
+        exit(run_cli(&command).await)
+    }
+
     THIS_RUN_START.set(Timestamp::now()).unwrap_or_else(|err| {
         eprintln!("Failed to set THIS_RUN_START: {}. Execution halted.", err);
         // Things have gone really bad and we can't even check INTERACTIVE at this point, let's just assume it is 1
@@ -31,7 +73,8 @@ async fn main() {
         .parse::<u8>()
         .unwrap_or(1)
         .eq(&1);
-    setup_logger().unwrap_or_else(|err| {
+    let (events_sender, events_receiver) = mpsc::unbounded_channel();
+    setup_logger(events_sender).unwrap_or_else(|err| {
         eprintln!("Failed to set up logger: {}. Execution halted.", err);
         if interactive {
             println!("Press any button to exit...");
@@ -42,11 +85,12 @@ async fn main() {
 
     log::info!("Discord Watchdog v{}", env!("CARGO_PKG_VERSION"));
 
-    let context = init_data().await;
+    let context = init_data(interactive).await;
 
     let context_ping_task = context.clone();
     let token = std::env::var("DISCORD_TOKEN").unwrap_or_else(|err| {
         log::error!("No Discord token detected: {}. Execution halted.", err);
+        log::logger().flush();
         if interactive {
             println!("Press any button to exit...");
             std::io::stdin().read_line(&mut String::new()).unwrap();
@@ -54,30 +98,55 @@ async fn main() {
         exit(1)
     });
     let http = Arc::new(Http::new(&token));
-    let intents = serenity::GatewayIntents::non_privileged().union(GatewayIntents::GUILD_MESSAGES);
+    tokio::spawn(events_task(events_receiver, context.clone(), http.clone()));
+    tokio::spawn(guild_sweep_task(context.clone(), http.clone()));
+    let reconnect = std::env::var("CLIENT_RECONNECT")
+        .unwrap_or("1".to_string())
+        .parse::<u8>()
+        .unwrap_or(1)
+        .eq(&1);
+    let connect_timeout = std::env::var("CLIENT_CONNECT_TIMEOUT")
+        .ok()
+        .and_then(|secs| secs.parse::<u64>().ok())
+        .map_or(DEFAULT_CLIENT_CONNECT_TIMEOUT, Duration::from_secs);
 
-    let framework = poise::Framework::builder()
-        .options(poise::FrameworkOptions {
-            commands: get_commands(),
-            ..Default::default()
-        })
-        .setup(|ctx, _ready, framework| {
-            Box::pin(async move {
-                poise::builtins::register_globally(ctx, &framework.options().commands).await?;
-                Ok(context)
-            })
-        })
-        .build();
-    let client_result = serenity::ClientBuilder::new(token, intents)
-        .framework(framework)
-        .await;
+    let client_result = match tokio::time::timeout(connect_timeout, build_client(&token, &context))
+        .await
+    {
+        Ok(client_result) => client_result,
+        Err(_) => {
+            log::error!(
+                "Discord client wasn't built within {}. Is Discord reachable? Execution halted.",
+                humantime::format_duration(connect_timeout)
+            );
+            log::logger().flush();
+            if interactive {
+                println!("Press any button to exit...");
+                std::io::stdin().read_line(&mut String::new()).unwrap();
+            }
+            exit(1)
+        }
+    };
     match client_result {
-        Ok(mut client) => {
+        Ok(client) => {
             log::info!("Discord client started");
             // Actual main loop divided into 2 green threads: receiving users' commands and checking service health.
             tokio::select! {
-                client_exec_result = client.start() => {
+                _ = connect_deadline(context.clone(), connect_timeout) => {
+                    log::error!(
+                        "Discord client didn't connect within {}. Is Discord reachable? Execution halted.",
+                        humantime::format_duration(connect_timeout)
+                    );
+                    log::logger().flush();
+                    if interactive {
+                        println!("Press any button to exit...");
+                        std::io::stdin().read_line(&mut String::new()).unwrap();
+                    }
+                    exit(1)
+                }
+                client_exec_result = run_client(client, &token, context, reconnect) => {
                     log::warn!("Discord client exited with: {:?}. Execution halted.", client_exec_result);
+                    log::logger().flush();
                     if interactive {
                         println!("Press any button to exit...");
                         std::io::stdin().read_line(&mut String::new()).unwrap();
@@ -87,6 +156,7 @@ async fn main() {
                 }
                 ping_task_result = ping_task(context_ping_task, http.clone()) => {
                     log::warn!("Ping task exited with {:?}. Execution halted.", ping_task_result);
+                    log::logger().flush();
                     if interactive {
                         println!("Press any button to exit...");
                         std::io::stdin().read_line(&mut String::new()).unwrap();
@@ -97,6 +167,7 @@ async fn main() {
         }
         Err(err) => {
             log::error!("Failed to build Discord client: {}. Execution halted.", err);
+            log::logger().flush();
             if interactive {
                 println!("Press any button to exit...");
                 std::io::stdin().read_line(&mut String::new()).unwrap();
@@ -106,35 +177,328 @@ async fn main() {
     }
 }
 
-fn setup_logger() -> Result<(), fern::InitError> {
+/// Completes only if the Discord client doesn't connect within the timeout
+async fn connect_deadline(context: Data, timeout: Duration) {
+    if tokio::time::timeout(timeout, context.client_ready())
+        .await
+        .is_ok()
+    {
+        std::future::pending::<()>().await
+    }
+}
+
+async fn build_client(token: &str, context: &Data) -> serenity::Result<Client> {
+    let intents = serenity::GatewayIntents::non_privileged().union(GatewayIntents::GUILD_MESSAGES);
+    let framework_context = context.clone();
+    let framework = poise::Framework::builder()
+        .options(poise::FrameworkOptions {
+            commands: get_commands(),
+            command_check: Some(|ctx| Box::pin(command_check(ctx))),
+            event_handler: |ctx, event, _framework, data| Box::pin(handle_event(ctx, event, data)),
+            ..Default::default()
+        })
+        .setup(|ctx, _ready, framework| {
+            Box::pin(async move {
+                framework_context.notify_client_ready();
+                if !framework_context.commands_registered() {
+                    poise::builtins::register_globally(ctx, &framework.options().commands).await?;
+                    framework_context.mark_commands_registered();
+                }
+                update_presence(&framework_context).await;
+                Ok(framework_context)
+            })
+        })
+        .build();
+    let client = serenity::ClientBuilder::new(token, intents)
+        .framework(framework)
+        .await?;
+    context
+        .set_shard_manager(client.shard_manager.clone())
+        .await;
+    Ok(client)
+}
+
+/// Runs the Discord client. With `reconnect`, the client is rebuilt with backoff whenever it exits, so a gateway
+/// outage doesn't take the health checks down with it. Errors, which a rebuilt client would hit again, are returned
+async fn run_client(
+    mut client: Client,
+    token: &str,
+    context: Data,
+    reconnect: bool,
+) -> serenity::Result<()> {
+    let mut delay = CLIENT_RECONNECT_BASE_DELAY;
+    loop {
+        let started = Instant::now();
+        let client_exec_result = client.start().await;
+        if !reconnect || is_fatal_client_error(&client_exec_result) {
+            return client_exec_result;
+        }
+        // Client, which was running for a while, exited because of a new outage
+        if started.elapsed() > CLIENT_RECONNECT_MAX_DELAY {
+            delay = CLIENT_RECONNECT_BASE_DELAY;
+        }
+        log::error!("Discord client exited with: {:?}", client_exec_result);
+        loop {
+            log::error!(
+                "Reconnecting Discord client in {}...",
+                humantime::format_duration(delay)
+            );
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(CLIENT_RECONNECT_MAX_DELAY);
+            match build_client(token, &context).await {
+                Ok(new_client) => {
+                    log::info!("Discord client restarted");
+                    client = new_client;
+                    break;
+                }
+                Err(err) => log::error!("Failed to rebuild Discord client: {}", err),
+            }
+        }
+    }
+}
+
+/// Whether the client exited because of its configuration, e.g. an invalid token or disallowed intents
+fn is_fatal_client_error(client_exec_result: &serenity::Result<()>) -> bool {
+    matches!(
+        client_exec_result,
+        Err(serenity::Error::Gateway(
+            serenity::GatewayError::InvalidAuthentication
+                | serenity::GatewayError::InvalidGatewayIntents
+                | serenity::GatewayError::DisallowedGatewayIntents
+        ))
+    )
+}
+
+/// Runs a config management command and returns the exit code
+async fn run_cli(command: &str) -> i32 {
+    match command {
+        "validate" => {
+            let mut valid = true;
+            match Config::load_from_file(&DEFAULT_CONFIG_PATH).await {
+                Ok(Some(config)) => valid &= report_problems(DEFAULT_CONFIG_PATH, &config),
+                Ok(None) => println!(
+                    "{}: not found, default values will be used",
+                    DEFAULT_CONFIG_PATH
+                ),
+                Err(err) => {
+                    valid = false;
+                    eprintln!("{}", err);
+                }
+            }
+            match SavedData::load_from_file(&DEFAULT_SAVEDATA_PATH).await {
+                Ok(Some(saved_data)) => {
+                    valid &= report_problems(DEFAULT_SAVEDATA_PATH, &saved_data.config)
+                }
+                Ok(None) => println!(
+                    "{}: not found, it will be created from {}",
+                    DEFAULT_SAVEDATA_PATH, DEFAULT_CONFIG_PATH
+                ),
+                Err(err) => {
+                    valid = false;
+                    eprintln!("{}", err);
+                }
+            }
+            if valid { 0 } else { 1 }
+        }
+        "show-config" => {
+            let (source, config) = match effective_config().await {
+                Ok(effective) => effective,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return 1;
+                }
+            };
+            match toml::to_string_pretty(&config) {
+                Ok(config_string) => {
+                    println!("# Loaded from {}\n{}", source, config_string);
+                    0
+                }
+                Err(err) => {
+                    eprintln!("Failed to serialize config: {}", err);
+                    1
+                }
+            }
+        }
+        "export-config" => {
+            let (source, config) = match effective_config().await {
+                Ok(effective) => effective,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return 1;
+                }
+            };
+            if let Err(err) = config.save_to_file(&DEFAULT_CONFIG_PATH).await {
+                eprintln!("{}", err);
+                return 1;
+            }
+            println!("Exported config from {} to {}", source, DEFAULT_CONFIG_PATH);
+            0
+        }
+        "migrate" => match SavedData::load_from_file(&DEFAULT_SAVEDATA_PATH).await {
+            Ok(Some(saved_data)) => {
+                if let Err(err) = saved_data.save_to_file(&DEFAULT_SAVEDATA_PATH).await {
+                    eprintln!("{}", err);
+                    return 1;
+                }
+                println!("Migrated {}", DEFAULT_SAVEDATA_PATH);
+                0
+            }
+            Ok(None) => {
+                println!("{} not found, nothing to migrate", DEFAULT_SAVEDATA_PATH);
+                0
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                1
+            }
+        },
+        "help" | "--help" | "-h" => {
+            println!("{}", CLI_USAGE);
+            0
+        }
+        _ => {
+            eprintln!("Unknown command: {}\n\n{}", command, CLI_USAGE);
+            2
+        }
+    }
+}
+
+/// Configuration currently in effect and where it comes from. Config.toml is only used until Data.toml is created
+async fn effective_config() -> anyhow::Result<(&'static str, Config)> {
+    match SavedData::load_from_file(&DEFAULT_SAVEDATA_PATH).await? {
+        Some(saved_data) => Ok((DEFAULT_SAVEDATA_PATH, saved_data.config)),
+        None => match Config::load_from_file(&DEFAULT_CONFIG_PATH).await? {
+            Some(config) => Ok((DEFAULT_CONFIG_PATH, config)),
+            None => Ok(("hardcoded defaults", Config::default())),
+        },
+    }
+}
+
+/// Prints config's problems. Returns whether there were none
+fn report_problems(path: &str, config: &Config) -> bool {
+    let problems = config.problems();
+    if problems.is_empty() {
+        println!("{}: OK", path);
+    }
+    for problem in &problems {
+        println!("{}: {}", path, problem);
+    }
+    problems.is_empty()
+}
+
+/// Lines waiting to be written to the log file. When the writer can't keep up, new lines are dropped instead of
+/// blocking async tasks
+const LOG_FILE_BUFFER_LINES: usize = 10_000;
+
+enum LogFileCommand {
+    Write(String),
+    Flush(std::sync::mpsc::Sender<()>),
+}
+
+/// Writes the log file on a dedicated thread, so log I/O never stalls the ping or client tasks
+struct LogFileAppender {
+    sender: SyncSender<LogFileCommand>,
+    /// Lines dropped since the last written one
+    dropped: Arc<AtomicUsize>,
+}
+
+impl LogFileAppender {
+    fn spawn(mut file: File) -> std::io::Result<Self> {
+        let (sender, receiver) = std::sync::mpsc::sync_channel(LOG_FILE_BUFFER_LINES);
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let writer_dropped = dropped.clone();
+        std::thread::Builder::new()
+            .name("log-writer".to_string())
+            .spawn(move || {
+                // Write errors are ignored, since there is nowhere to log them
+                for command in receiver {
+                    match command {
+                        LogFileCommand::Write(line) => {
+                            let dropped = writer_dropped.swap(0, Ordering::Relaxed);
+                            if dropped > 0 {
+                                let _ = writeln!(
+                                    file,
+                                    "[{} log lines were dropped, because writing them fell behind]",
+                                    dropped
+                                );
+                            }
+                            let _ = file.write_all(line.as_bytes());
+                        }
+                        LogFileCommand::Flush(done) => {
+                            let _ = file.flush();
+                            let _ = done.send(());
+                        }
+                    }
+                }
+            })?;
+        Ok(Self { sender, dropped })
+    }
+}
+
+impl log::Log for LogFileAppender {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        let line = format!("{}\n", record.args());
+        if let Err(TrySendError::Full(_)) = self.sender.try_send(LogFileCommand::Write(line)) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Waits until every line sent before is written. Must be called before exiting, or the last lines are lost
+    fn flush(&self) {
+        let (done_sender, done) = std::sync::mpsc::channel();
+        if self.sender.send(LogFileCommand::Flush(done_sender)).is_ok() {
+            let _ = done.recv();
+        }
+    }
+}
+
+fn setup_logger(events: UnboundedSender<String>) -> Result<(), fern::InitError> {
     let tracing = std::env::var("TRACING")
         .unwrap_or("0".to_string())
         .parse::<u8>()
         .unwrap_or(0)
         .ne(&0);
-    let mut dispatch = fern::Dispatch::new().format(|out, message, record| {
-        out.finish(format_args!(
-            "[{} {} {}] {}",
-            humantime::format_rfc3339_seconds(SystemTime::now()),
-            record.level(),
-            record.target(),
-            message
-        ))
-    });
+    let mut dispatch = fern::Dispatch::new();
     if tracing {
         dispatch = dispatch.level_for("discord_watchdog", log::LevelFilter::Trace);
     } else {
         dispatch = dispatch.level_for("discord_watchdog", log::LevelFilter::Debug);
     }
+    let output = fern::Dispatch::new()
+        .format(|out, message, record| {
+            out.finish(format_args!(
+                "[{} {} {}] {}",
+                humantime::format_rfc3339_seconds(SystemTime::now()),
+                record.level(),
+                record.target(),
+                message
+            ))
+        })
+        .chain(std::io::stdout())
+        .chain(
+            Box::new(LogFileAppender::spawn(fern::log_file(DEFAULT_LOG_PATH)?)?)
+                as Box<dyn log::Log>,
+        );
+    // Mirrors significant events into the master's log channel
+    let events_feed = fern::Dispatch::new()
+        .filter(is_significant)
+        .chain(fern::Output::call(move |record| {
+            // Receiver is gone only when the bot is shutting down
+            let _ = events.send(format_event(record));
+        }));
     dispatch
         .level(log::LevelFilter::Error)
-        .chain(std::io::stdout())
-        .chain(fern::log_file(DEFAULT_LOG_PATH)?)
+        .chain(output)
+        .chain(events_feed)
         .apply()?;
     Ok(())
 }
 
-async fn init_data() -> Data {
+async fn init_data(interactive: bool) -> Data {
     // Create default context
     let data: Data = Data::default();
 
@@ -146,6 +510,7 @@ async fn init_data() -> Data {
             Some(saved_data) => {
                 saved_data.load_into(&data).await;
                 log::info!("Loaded SavedData");
+                saved_data.config.warn_about_problems();
             }
             None => {
                 log::info!("No SaveData detected. Initializing...");
@@ -156,18 +521,30 @@ async fn init_data() -> Data {
                     new_saved_data.config = config
                 } else if let Err(err) = loaded_config_result {
                     log::error!("Failed to load Config: {}", err);
+                } else if let Some(config) = interactive.then(first_run_setup).flatten() {
+                    if let Err(err) = config.save_to_file(&DEFAULT_CONFIG_PATH).await {
+                        log::error!("Failed to save Config: {}", err);
+                    } else {
+                        log::info!("Saved initial Config to {}", DEFAULT_CONFIG_PATH);
+                    }
+                    new_saved_data.config = config
                 } else {
                     log::info!("No Config detected. Default values will be used.")
                 }
                 new_saved_data.load_into(&data).await;
-                if let Err(err) = new_saved_data.save_to_file(&DEFAULT_SAVEDATA_PATH).await {
+                new_saved_data.config.warn_about_problems();
+                if !save_initial_data(&new_saved_data).await {
                     log::error!(
-                        "Failed to save SaveData to {}: {}",
-                        &DEFAULT_SAVEDATA_PATH,
-                        err
-                    )
-                } else {
-                    log::info!("Saved SaveData to {}", DEFAULT_SAVEDATA_PATH);
+                        "!!! {} could not be written. The bot runs WITHOUT PERSISTENCE: all status and configuration changes will be lost on restart !!!",
+                        DEFAULT_SAVEDATA_PATH
+                    );
+                    if interactive
+                        && !prompt("Continue without persistence? (y/n)", "n")
+                            .is_some_and(|answer| answer.eq_ignore_ascii_case("y"))
+                    {
+                        log::logger().flush();
+                        exit(1)
+                    }
                 }
             }
         },
@@ -178,3 +555,55 @@ async fn init_data() -> Data {
 
     data
 }
+
+/// Writes freshly initialized SaveData, retrying with backoff, since the first failure may be transient (e.g. a
+/// volume, which is not mounted yet). Returns whether it was saved
+async fn save_initial_data(saved_data: &SavedData) -> bool {
+    let mut delay = STARTUP_SAVE_BASE_DELAY;
+    for attempt in 1..=STARTUP_SAVE_ATTEMPTS {
+        match saved_data.save_to_file(&DEFAULT_SAVEDATA_PATH).await {
+            Ok(()) => {
+                log::info!("Saved SaveData to {}", DEFAULT_SAVEDATA_PATH);
+                return true;
+            }
+            Err(err) => {
+                log::error!(
+                    "Failed to save SaveData to {} (attempt {}/{}): {}",
+                    DEFAULT_SAVEDATA_PATH,
+                    attempt,
+                    STARTUP_SAVE_ATTEMPTS,
+                    err
+                );
+                if attempt < STARTUP_SAVE_ATTEMPTS {
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Asks for the monitored resource on the console, so forks don't silently start watching BYOND.
+/// Returns `None` if nobody answers
+fn first_run_setup() -> Option<Config> {
+    println!(
+        "No {} detected. Let's set up the resource to monitor (leave empty to keep the default).",
+        DEFAULT_CONFIG_PATH
+    );
+    let resource_name = prompt("Resource name", DEFAULT_RESOURCE_NAME)?;
+    let resource_addr = prompt("Resource address", DEFAULT_RESOURCE_ADDR)?;
+    Some(Config::with_resource(resource_name, resource_addr))
+}
+
+fn prompt(question: &str, default: &str) -> Option<String> {
+    print!("{} [{}]: ", question, default);
+    std::io::stdout().flush().ok()?;
+    let mut answer = String::new();
+    // Nothing can be read from closed stdin
+    if std::io::stdin().read_line(&mut answer).ok()? == 0 {
+        return None;
+    }
+    let answer = answer.trim();
+    Some(if answer.is_empty() { default } else { answer }.to_string())
+}
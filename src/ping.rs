@@ -1,128 +1,1242 @@
-use std::{net::IpAddr, process, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeSet, VecDeque},
+    fmt::Display,
+    io::ErrorKind,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    process,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU16, AtomicU32, Ordering},
+    },
+    time::{Duration, Instant},
+};
 
 use anyhow::Error;
-use poise::serenity_prelude::Http;
-use tokio::{task, time};
+use futures::{StreamExt, future::join_all, stream};
+use hickory_resolver::TokioAsyncResolver;
+use poise::serenity_prelude::{Http, Timestamp};
+use tokio::{
+    net::{TcpStream, UdpSocket},
+    sync::OnceCell,
+    task,
+    time::{self, Interval},
+};
 
-use crate::{DEFAULT_INTERVAL_BETWEEN_ATTEMPTS_SECS, Data, ResourceStatus, status::update_status};
+use crate::{
+    CheckKind, DEFAULT_INTERVAL_BETWEEN_ATTEMPTS_SECS, Data, LinkedProbe, PingConfig,
+    ResourceStatus,
+    events::EVENTS_TARGET,
+    status::{
+        end_startup_grace, notify_master, quorum_verdict, refresh_embeds, refresh_latency,
+        remind_unknown, update_linked_status, update_status,
+    },
+};
 
 const DEFAULT_ICMP_PAYLOAD: [u8; 1] = [1];
+const UDP_RECEIVE_BUFFER_SIZE: usize = 1500;
+/// Delay before the first retry within one check. It doubles with each next retry
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(100);
+/// Warmup probe waits for this fraction of the timeout at most
+const WARMUP_TIMEOUT_DIVISOR: u32 = 10;
+/// Most ports, which one port scan may check, so the bot can't be used as a scanner
+pub const MAX_PORTSCAN_PORTS: usize = 16;
+/// Most addresses, whose ICMP identifiers are remembered. Addresses checked once by commands are forgotten first
+const MAX_ICMP_IDS: usize = 64;
+
+/// Outcome of a successful healthcheck
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Health {
+    /// Resource responded. Round-trip time is `None` if silence was accepted as a response
+    Up {
+        rtt: Option<Duration>,
+    },
+    Down,
+    /// Host responded to ICMP, but its service didn't accept a TCP connection
+    ServiceDown,
+}
+
+pub type HealthResult = anyhow::Result<Health>;
+
+/// Why checks fail with errors instead of determining the status, since the remediation differs. Discord refusing the
+/// bot's posts isn't one: it doesn't make checks fail, and the embed couldn't be posted to say so anyway. It is logged
+/// with a hint instead, see `describe_send_error`
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum UnknownCause {
+    /// Resource's address can't be resolved or reached
+    #[default]
+    Unreachable,
+    /// Bot's host doesn't permit sending probes, e.g. raw ICMP sockets without CAP_NET_RAW
+    NotPermitted,
+}
+
+impl UnknownCause {
+    pub fn of(err: &Error) -> Self {
+        let not_permitted = err.chain().any(|cause| {
+            cause
+                .downcast_ref::<std::io::Error>()
+                .is_some_and(|err| err.kind() == ErrorKind::PermissionDenied)
+        });
+        if not_permitted {
+            Self::NotPermitted
+        } else {
+            Self::Unreachable
+        }
+    }
+}
+
+/// Whether the error points at the bot's host, e.g. failing sockets or missing permissions, rather than at the resource
+/// or its configured address
+pub fn is_host_error(err: &Error) -> bool {
+    err.chain().any(|cause| {
+        cause.downcast_ref::<std::io::Error>().is_some_and(|err| {
+            matches!(
+                err.kind(),
+                ErrorKind::PermissionDenied
+                    | ErrorKind::AddrInUse
+                    | ErrorKind::AddrNotAvailable
+                    | ErrorKind::NetworkDown
+                    | ErrorKind::NetworkUnreachable
+                    | ErrorKind::OutOfMemory
+                    | ErrorKind::Unsupported
+            )
+        })
+    })
+}
+
+/// Reusable client for checking resources' health. Raw ICMP sockets are opened lazily, once per
+/// address family, and reused by every following check.
+pub struct PingClient {
+    icmp_v4: OnceCell<surge_ping::Client>,
+    icmp_v6: OnceCell<surge_ping::Client>,
+    /// Resolver of SRV records, created from the system's configuration on the first SRV lookup
+    srv_resolver: OnceCell<TokioAsyncResolver>,
+    /// Identifier of the first checked address. Following addresses get the next ones
+    icmp_id: u16,
+    /// ICMP identifiers of recently checked addresses, oldest first. surge-ping routes replies of RAW sockets by them
+    icmp_ids: Mutex<VecDeque<(String, u16)>>,
+    icmp_sequence: AtomicU16,
+    /// Resolutions, which took longer than `dns_slow_threshold`, since the last `take_slow_resolutions`
+    slow_resolutions: AtomicU32,
+    /// Address families, which failed while the other one worked, since the last `take_unreachable_families`
+    unreachable_families: Mutex<BTreeSet<&'static str>>,
+    /// Checks in progress, made by the ping task and commands together
+    outstanding_checks: AtomicU32,
+}
+
+impl std::fmt::Debug for PingClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PingClient")
+            .field("icmp_v4_open", &self.icmp_v4.initialized())
+            .field("icmp_v6_open", &self.icmp_v6.initialized())
+            .field("srv_resolver_created", &self.srv_resolver.initialized())
+            .field("icmp_id", &self.icmp_id)
+            .field("icmp_ids", &self.icmp_ids)
+            .field("icmp_sequence", &self.icmp_sequence)
+            .field("slow_resolutions", &self.slow_resolutions)
+            .field("unreachable_families", &self.unreachable_families)
+            .field("outstanding_checks", &self.outstanding_checks)
+            .finish()
+    }
+}
+
+impl Default for PingClient {
+    fn default() -> Self {
+        Self::new(process::id() as u16)
+    }
+}
+
+impl PingClient {
+    pub fn new(icmp_id: u16) -> Self {
+        Self {
+            icmp_v4: OnceCell::new(),
+            icmp_v6: OnceCell::new(),
+            srv_resolver: OnceCell::new(),
+            icmp_id,
+            icmp_ids: Mutex::new(VecDeque::new()),
+            icmp_sequence: AtomicU16::new(0),
+            slow_resolutions: AtomicU32::new(0),
+            unreachable_families: Mutex::new(BTreeSet::new()),
+            outstanding_checks: AtomicU32::new(0),
+        }
+    }
+
+    /// Returns how many resolutions were slow since the last call
+    pub fn take_slow_resolutions(&self) -> u32 {
+        self.slow_resolutions.swap(0, Ordering::Relaxed)
+    }
+
+    /// Returns address families, which failed while the other one worked, since the last call
+    pub fn take_unreachable_families(&self) -> BTreeSet<&'static str> {
+        std::mem::take(
+            &mut *self
+                .unreachable_families
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        )
+    }
+
+    /// Checks the resource, configured in `ping_config`
+    pub async fn check(&self, ping_config: &PingConfig) -> HealthResult {
+        self.check_addr(&ping_config.resource_addr, ping_config)
+            .await
+    }
+
+    /// Checks an arbitrary address with settings from `ping_config`
+    pub async fn check_addr(&self, addr: &str, ping_config: &PingConfig) -> HealthResult {
+        self.check_addr_as(addr, &ping_config.check_kind, ping_config)
+            .await
+    }
+
+    /// Checks the linked probe, which has its own address and check kind
+    pub async fn check_linked(
+        &self,
+        probe: &LinkedProbe,
+        ping_config: &PingConfig,
+    ) -> HealthResult {
+        self.check_addr_as(&probe.addr, &probe.check_kind, ping_config)
+            .await
+    }
+
+    async fn check_addr_as(
+        &self,
+        addr: &str,
+        check_kind: &CheckKind,
+        ping_config: &PingConfig,
+    ) -> HealthResult {
+        let _outstanding = self.start_check(addr, ping_config)?;
+        let resolution_start = Instant::now();
+        let (ips, check_kind) = if ping_config.resolve_srv {
+            let (ips, port) = self.resolve_srv(addr, ping_config).await?;
+            (ips, check_kind.with_port(port))
+        } else {
+            (resolve_host(addr, ping_config).await?, check_kind.clone())
+        };
+        let check_kind = &check_kind;
+        let resolution_time = resolution_start.elapsed();
+        if ping_config
+            .dns_slow_threshold
+            .is_some_and(|threshold| resolution_time > threshold)
+        {
+            log::debug!("Resolving {} took {:0.2?}", addr, resolution_time);
+            self.slow_resolutions.fetch_add(1, Ordering::Relaxed);
+        }
+        let checks = ips
+            .iter()
+            .map(|ip| self.check_ip(*ip, addr, check_kind, ping_config));
+        let results = join_all(checks).await;
+        let (result, unreachable) = family_verdict(ips.into_iter().zip(results).collect());
+        if !unreachable.is_empty() {
+            log::debug!(
+                "{} of {} is unreachable, while the other family works",
+                unreachable.join(" and "),
+                addr
+            );
+            self.unreachable_families
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .extend(unreachable);
+        }
+        result
+    }
+
+    /// Looks up SRV records of `name` and resolves the most preferred target, which resolves at all. Returns its
+    /// addresses and the advertised port
+    async fn resolve_srv(
+        &self,
+        name: &str,
+        ping_config: &PingConfig,
+    ) -> anyhow::Result<(Vec<IpAddr>, u16)> {
+        let resolver = self
+            .srv_resolver
+            .get_or_try_init(|| async { TokioAsyncResolver::tokio_from_system_conf() })
+            .await?;
+        let lookup = time::timeout(ping_config.dns_timeout, resolver.srv_lookup(name))
+            .await
+            .map_err(|_| DnsTimeoutError {
+                addr: name.to_string(),
+                timeout: ping_config.dns_timeout,
+            })?
+            .map_err(|err| Error::msg(format!("Failed to look up SRV records of {name}: {err}")))?;
+        let targets = by_preference(
+            lookup
+                .iter()
+                .map(|record| SrvTarget {
+                    priority: record.priority(),
+                    weight: record.weight(),
+                    host: record.target().to_utf8().trim_end_matches('.').to_string(),
+                    port: record.port(),
+                })
+                .collect(),
+        );
+        let mut last_err = Error::msg(format!("{name} has no SRV targets"));
+        for target in targets {
+            // A lone "." target means the service is deliberately unavailable
+            if target.host.is_empty() {
+                continue;
+            }
+            match resolve_host(&target.host, ping_config).await {
+                Ok(ips) => {
+                    log::trace!("{} resolved to {}:{}", name, target.host, target.port);
+                    return Ok((ips, target.port));
+                }
+                Err(err) => {
+                    log::debug!("SRV target {} of {} failed: {}", target.host, name, err);
+                    last_err = err;
+                }
+            }
+        }
+        Err(last_err)
+    }
+
+    /// Checks that `addr` resolves the way it will be checked: as an SRV name with `resolve_srv`, as a host otherwise
+    pub async fn resolve_addr(&self, addr: &str, ping_config: &PingConfig) -> anyhow::Result<()> {
+        if ping_config.resolve_srv {
+            self.resolve_srv(addr, ping_config).await?;
+        } else {
+            resolve_ip(addr, ping_config.dns_timeout).await?;
+        }
+        Ok(())
+    }
+
+    /// Counts the check as outstanding until the returned guard is dropped. Fails, if `max_outstanding_checks` are
+    /// already in progress, so timing out checks can't pile up sockets
+    fn start_check(
+        &self,
+        addr: &str,
+        ping_config: &PingConfig,
+    ) -> Result<OutstandingCheck<'_>, TooManyChecksError> {
+        let limit = u32::from(ping_config.max_outstanding_checks.get());
+        self.outstanding_checks
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |outstanding| {
+                (outstanding < limit).then_some(outstanding + 1)
+            })
+            .map_err(|_| TooManyChecksError {
+                addr: addr.to_string(),
+                limit,
+            })?;
+        Ok(OutstandingCheck {
+            counter: &self.outstanding_checks,
+        })
+    }
+
+    /// Checks a single resolved address of `addr`, retrying it if configured
+    async fn check_ip(
+        &self,
+        ip: IpAddr,
+        addr: &str,
+        check_kind: &CheckKind,
+        ping_config: &PingConfig,
+    ) -> HealthResult {
+        if ping_config.warmup_ping {
+            let warmup_timeout = ping_config.timeout / WARMUP_TIMEOUT_DIVISOR;
+            let warmup_result = self.probe(ip, addr, check_kind, warmup_timeout).await;
+            log::trace!("Warmup probe of {} resulted in {:?}", addr, warmup_result);
+        }
+        // Retries share the timeout, so a check with them never takes longer than one without them
+        let deadline = Instant::now() + ping_config.timeout;
+        let mut retry_delay = RETRY_BASE_DELAY;
+        let mut attempts_left = u32::from(ping_config.check_retries) + 1;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now()) / attempts_left;
+            let result = self.probe(ip, addr, check_kind, timeout).await;
+            attempts_left -= 1;
+            if attempts_left == 0
+                || matches!(result, Ok(Health::Up { .. }))
+                || Instant::now() + retry_delay >= deadline
+            {
+                return result;
+            }
+            log::debug!(
+                "Check of {} failed with {:?}, retrying in {} ms",
+                addr,
+                result,
+                retry_delay.as_millis()
+            );
+            time::sleep(retry_delay).await;
+            retry_delay *= 2;
+        }
+    }
+
+    /// Sends a single probe of the given kind
+    async fn probe(
+        &self,
+        ip: IpAddr,
+        addr: &str,
+        check_kind: &CheckKind,
+        timeout: Duration,
+    ) -> HealthResult {
+        match check_kind {
+            CheckKind::Icmp => self.icmp_check(ip, addr, timeout).await,
+            CheckKind::Udp {
+                port,
+                probe,
+                expect_response,
+            } => udp_check(ip, addr, *port, probe, *expect_response, timeout).await,
+            CheckKind::Tcp { port } => tcp_check(ip, addr, *port, timeout).await,
+            CheckKind::IcmpAndTcp { port } => {
+                let (icmp, tcp) = tokio::join!(
+                    self.icmp_check(ip, addr, timeout),
+                    tcp_check(ip, addr, *port, timeout)
+                );
+                combine_legs(icmp, tcp)
+            }
+            CheckKind::Http { https, port, path } => {
+                http_check(ip, addr, *https, *port, path, timeout).await
+            }
+        }
+    }
+
+    async fn icmp_client(&self, ip: IpAddr) -> anyhow::Result<&surge_ping::Client> {
+        let (cell, kind) = if ip.is_ipv6() {
+            (&self.icmp_v6, surge_ping::ICMP::V6)
+        } else {
+            (&self.icmp_v4, surge_ping::ICMP::V4)
+        };
+        let family = family_name(ip);
+        let client = cell
+            .get_or_try_init(|| async {
+                log::debug!("Opening {} ICMP socket", family);
+                surge_ping::Client::new(&surge_ping::Config::builder().kind(kind).build())
+            })
+            .await?;
+        Ok(client)
+    }
+
+    /// ICMP identifier of the address, which stays the same for all its checks while it is remembered
+    fn icmp_id_of(&self, addr: &str) -> u16 {
+        let mut icmp_ids = self
+            .icmp_ids
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some((_, id)) = icmp_ids.iter().find(|(known, _)| known == addr) {
+            return *id;
+        }
+        let next_id = icmp_ids
+            .back()
+            .map_or(self.icmp_id, |(_, id)| id.wrapping_add(1));
+        if icmp_ids.len() >= MAX_ICMP_IDS {
+            icmp_ids.pop_front();
+        }
+        icmp_ids.push_back((addr.to_string(), next_id));
+        next_id
+    }
+
+    async fn icmp_check(&self, ip: IpAddr, addr: &str, timeout: Duration) -> HealthResult {
+        let client = self.icmp_client(ip).await?;
+        let icmp_id = surge_ping::PingIdentifier(self.icmp_id_of(addr));
+        let mut pinger = client.pinger(ip, icmp_id).await;
+        pinger.timeout(timeout);
+        let icmp_sequence =
+            surge_ping::PingSequence(self.icmp_sequence.fetch_add(1, Ordering::Relaxed));
+
+        // Replies aren't compared with the identifier: unprivileged DGRAM sockets get it rewritten by the kernel, so
+        // surge-ping matches their replies without it
+        match pinger.ping(icmp_sequence, &DEFAULT_ICMP_PAYLOAD).await {
+            Ok((_, rtt)) => {
+                log::trace!("Pinging {} resulted in success in {:0.2?}", addr, rtt);
+                Ok(Health::Up { rtt: Some(rtt) })
+            }
+            Err(err) => match err {
+                surge_ping::SurgeError::Timeout { seq } => {
+                    log::trace!("Pinging {addr} with sequence {seq} resulted in timeout.");
+                    Ok(Health::Down)
+                }
+                _ => {
+                    // The socket's error is kept in the chain, so failures of the bot's host can be told apart
+                    let message = format!("Failed to ping {}: {}", addr, err);
+                    Err(Error::new(err).context(message))
+                }
+            },
+        }
+    }
+}
+
+/// Host and port, advertised by an SRV record
+#[derive(Debug, Clone, PartialEq)]
+struct SrvTarget {
+    priority: u16,
+    weight: u16,
+    host: String,
+    port: u16,
+}
+
+/// Orders SRV targets the way clients should try them: lowest priority first, heaviest first within a priority.
+/// Weights are meant for load balancing, but a watchdog should check the same target every time
+fn by_preference(mut targets: Vec<SrvTarget>) -> Vec<SrvTarget> {
+    targets.sort_by(|a, b| {
+        a.priority
+            .cmp(&b.priority)
+            .then(b.weight.cmp(&a.weight))
+            .then_with(|| a.host.cmp(&b.host))
+    });
+    targets
+}
+
+/// Outstanding check, which is counted until it's dropped
+struct OutstandingCheck<'a> {
+    counter: &'a AtomicU32,
+}
+
+impl Drop for OutstandingCheck<'_> {
+    fn drop(&mut self) {
+        self.counter.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn family_name(ip: IpAddr) -> &'static str {
+    if ip.is_ipv6() { "IPv6" } else { "IPv4" }
+}
+
+/// Combines results of checking each address family. The address is up if any family works, and then the rest of
+/// them are returned as unreachable. Otherwise Down is preferred to errors, since it is a definite result
+fn family_verdict(mut results: Vec<(IpAddr, HealthResult)>) -> (HealthResult, Vec<&'static str>) {
+    let is_up = |result: &HealthResult| matches!(result, Ok(Health::Up { .. }));
+    let Some(up_index) = results.iter().position(|(_, result)| is_up(result)) else {
+        let down_index = results
+            .iter()
+            .position(|(_, result)| matches!(result, Ok(Health::Down | Health::ServiceDown)))
+            .unwrap_or_default();
+        return (results.swap_remove(down_index).1, Vec::new());
+    };
+    let unreachable = results
+        .iter()
+        .filter(|(_, result)| !is_up(result))
+        .map(|(ip, _)| family_name(*ip))
+        .collect();
+    (results.swap_remove(up_index).1, unreachable)
+}
 
 pub async fn ping_task(data: Data, http: Arc<Http>) -> Result<(), task::JoinError> {
     let task = task::spawn(async move {
         let mut interval =
             time::interval(Duration::from_secs(DEFAULT_INTERVAL_BETWEEN_ATTEMPTS_SECS));
-        let mut icmp_sequence: u16 = 0;
-        let icmp_id: u16 = process::id() as u16;
 
         loop {
             interval.tick().await;
-            icmp_sequence += 1;
-
-            let config_lock = data.config.read().await;
-            let interval_duration = config_lock.ping_config.interval_between_attempts;
-            let timeout = config_lock.ping_config.timeout;
-            let addr = config_lock.ping_config.resource_addr.clone();
-            drop(config_lock);
 
-            interval = time::interval(interval_duration);
-            interval.tick().await;
+            let ping_config = data.effective_ping_config(Instant::now()).await;
+            retune_interval(&mut interval, ping_config.interval_between_attempts);
 
-            let response = healthcheck(&addr, timeout, icmp_sequence, icmp_id).await;
+            if !ping_config.is_active(Timestamp::now()) {
+                log::trace!("Outside of active hours, skipping the check");
+                update_status(Some(ResourceStatus::Inactive), data.clone(), http.clone()).await;
+                continue;
+            }
 
-            match response {
-                Ok(success) => {
-                    if success {
-                        update_status(ResourceStatus::Up, data.clone(), http.clone()).await;
-                    } else {
-                        update_status(ResourceStatus::Down, data.clone(), http.clone()).await;
+            let mut results = Vec::new();
+            let mut host_errors = false;
+            let mut causes = Vec::new();
+            // Checks share the ICMP sockets, and their replies are told apart by identifiers and sequence numbers
+            let checks: Vec<_> = ping_config
+                .probe_addrs()
+                .map(|addr| {
+                    let (ping_client, ping_config) = (&data.ping_client, &ping_config);
+                    async move { (addr, ping_client.check_addr(addr, ping_config).await) }
+                })
+                .collect();
+            let linked_check = async {
+                match &ping_config.linked_probe {
+                    Some(probe) => Some(data.ping_client.check_linked(probe, &ping_config).await),
+                    None => None,
+                }
+            };
+            let probe_checks = async {
+                let outcomes: Vec<_> = stream::iter(checks)
+                    .buffer_unordered(ping_config.check_concurrency.get().into())
+                    .collect()
+                    .await;
+                outcomes
+            };
+            let (outcomes, linked_outcome) = tokio::join!(probe_checks, linked_check);
+            *data.last_check.write().await = Some(Timestamp::now());
+            for (addr, outcome) in outcomes {
+                if addr == ping_config.resource_addr {
+                    if let Ok(Health::Up { rtt: Some(rtt) }) = outcome {
+                        data.rtt
+                            .write()
+                            .await
+                            .update(rtt, ping_config.rtt_smoothing);
                     }
+                    let service_down = matches!(outcome, Ok(Health::ServiceDown));
+                    if service_down {
+                        log::debug!("{} responds to ICMP, but its TCP leg failed", addr);
+                    }
+                    data.service_down.store(service_down, Ordering::Relaxed);
                 }
-                Err(err) => {
-                    log::error!("Failed to healthcheck: {}", err);
-                    update_status(ResourceStatus::Unknown, data.clone(), http.clone()).await;
+                let result = match outcome {
+                    // Skipped checks say nothing about the resource, so they are left out of the verdict
+                    Err(err) if err.is::<TooManyChecksError>() => {
+                        log::warn!("{}", err);
+                        continue;
+                    }
+                    Ok(Health::Up { .. }) => ResourceStatus::Up,
+                    Ok(Health::Down | Health::ServiceDown) => ResourceStatus::Down,
+                    Err(err) if err.is::<DnsTimeoutError>() => {
+                        log::warn!("Failed to healthcheck {}: {}", addr, err);
+                        causes.push(UnknownCause::Unreachable);
+                        ResourceStatus::Unknown
+                    }
+                    Err(err) => {
+                        log::error!("Failed to healthcheck {}: {}", addr, err);
+                        host_errors |= is_host_error(&err);
+                        causes.push(UnknownCause::of(&err));
+                        ResourceStatus::Unknown
+                    }
+                };
+                results.push(result);
+            }
+            if !causes.is_empty() {
+                *data.unknown_cause.write().await = if causes.contains(&UnknownCause::NotPermitted)
+                {
+                    UnknownCause::NotPermitted
+                } else {
+                    UnknownCause::Unreachable
+                };
+            }
+            if results
+                .iter()
+                .any(|result| *result != ResourceStatus::Unknown)
+            {
+                data.consecutive_errors.store(0, Ordering::Relaxed);
+            } else if host_errors {
+                data.consecutive_errors.fetch_add(1, Ordering::Relaxed);
+            }
+            if !results.is_empty() {
+                let verdict = quorum_verdict(&results, ping_config.quorum);
+                update_status(verdict, data.clone(), http.clone()).await;
+            }
+            end_startup_grace(data.clone(), http.clone()).await;
+            remind_unknown(data.clone(), http.clone()).await;
+            refresh_latency(data.clone(), http.clone()).await;
+
+            // Reported only when it starts and stops, since the master's log channel mirrors warnings
+            let dns_slow = data.ping_client.take_slow_resolutions() > 0;
+            if data.dns_slow.swap(dns_slow, Ordering::Relaxed) != dns_slow {
+                let threshold = ping_config.dns_slow_threshold.unwrap_or_default();
+                if dns_slow {
+                    log::warn!(
+                        "Slow DNS: resolving probed addresses took longer than {}, the resolver may be degrading",
+                        humantime::format_duration(threshold)
+                    );
+                } else {
+                    log::info!(
+                        target: EVENTS_TARGET,
+                        "DNS resolution is faster than {} again",
+                        humantime::format_duration(threshold)
+                    );
                 }
             }
+            // Reported only when they change, like slow DNS
+            let unreachable_families = data.ping_client.take_unreachable_families();
+            let old_unreachable_families = std::mem::replace(
+                &mut *data.unreachable_families.write().await,
+                unreachable_families.clone(),
+            );
+            if old_unreachable_families != unreachable_families {
+                if unreachable_families.is_empty() {
+                    log::info!(
+                        target: EVENTS_TARGET,
+                        "All address families of probed addresses are reachable again"
+                    );
+                } else {
+                    log::warn!(
+                        "{} unreachable: probed addresses respond only over the other address family",
+                        unreachable_families
+                            .iter()
+                            .copied()
+                            .collect::<Vec<_>>()
+                            .join(" and ")
+                    );
+                }
+                refresh_embeds(data.clone(), http.clone()).await;
+            }
+            if let Some(outcome) = linked_outcome {
+                let linked_status = match outcome {
+                    Err(err) if err.is::<TooManyChecksError>() => {
+                        log::warn!("{}", err);
+                        *data.linked_status.read().await
+                    }
+                    Ok(Health::Up { .. }) => ResourceStatus::Up,
+                    Ok(Health::Down | Health::ServiceDown) => ResourceStatus::Down,
+                    Err(err) => {
+                        log::warn!("Failed to healthcheck linked probe: {}", err);
+                        ResourceStatus::Unknown
+                    }
+                };
+                update_linked_status(linked_status, data.clone(), http.clone()).await;
+            }
+
+            let consecutive_errors = data.consecutive_errors.load(Ordering::Relaxed);
+            if ping_config
+                .self_restart_after_errors
+                .is_some_and(|threshold| consecutive_errors >= threshold.get())
+            {
+                let message = format!(
+                    "Healthcheck failed {} times in a row, the bot's host seems to be broken. Exiting, so it can be restarted.",
+                    consecutive_errors
+                );
+                log::error!("{}", message);
+                if ping_config.self_restart_notify_master {
+                    notify_master(&message, &data, http.clone()).await;
+                }
+                break;
+            }
         }
     });
 
     task.await
 }
 
-pub async fn healthcheck(
+/// Rebuilds the interval of the ping task, if the configured period changed. The next tick is one new period away, so
+/// each tick is waited for exactly once
+fn retune_interval(interval: &mut Interval, period: Duration) {
+    if interval.period() != period {
+        log::debug!("Checking every {} now", humantime::format_duration(period));
+        *interval = time::interval_at(time::Instant::now() + period, period);
+    }
+}
+
+async fn udp_check(
+    ip: IpAddr,
     addr: &str,
+    port: u16,
+    probe: &[u8],
+    expect_response: bool,
     timeout: Duration,
-    icmp_sequence: u16,
-    icmp_id: u16,
-) -> anyhow::Result<bool> {
-    let mut config_builder = surge_ping::Config::builder();
-    let ip = resolve_ip(addr).await?;
-    if ip.is_ipv6() {
-        config_builder = config_builder.kind(surge_ping::ICMP::V6);
-    }
-    let config = config_builder.build();
-    let client = surge_ping::Client::new(&config)?;
-    let mut pinger = client.pinger(ip, surge_ping::PingIdentifier(icmp_id)).await;
-    pinger.timeout(timeout);
-
-    match pinger
-        .ping(
-            surge_ping::PingSequence(icmp_sequence),
-            &DEFAULT_ICMP_PAYLOAD,
-        )
-        .await
-    {
-        Ok((_, rtt)) => {
-            log::trace!("Pinging {} resulted in success in {:0.2?}", addr, rtt);
-            Ok(true)
+) -> HealthResult {
+    let local_addr: SocketAddr = if ip.is_ipv6() {
+        (Ipv6Addr::UNSPECIFIED, 0).into()
+    } else {
+        (Ipv4Addr::UNSPECIFIED, 0).into()
+    };
+    let socket = UdpSocket::bind(local_addr).await?;
+    // Connected socket is required to receive ICMP port-unreachable as an error
+    socket.connect((ip, port)).await?;
+    let start = Instant::now();
+    socket.send(probe).await?;
+
+    let mut buffer = [0; UDP_RECEIVE_BUFFER_SIZE];
+    match time::timeout(timeout, socket.recv(&mut buffer)).await {
+        Ok(Ok(len)) => {
+            let rtt = start.elapsed();
+            log::trace!("UDP probe to {addr}:{port} got {len} bytes in response in {rtt:0.2?}.");
+            Ok(Health::Up { rtt: Some(rtt) })
         }
-        Err(err) => match err {
-            surge_ping::SurgeError::Timeout { seq } => {
-                log::trace!("Pinging {addr} with sequence {seq} resulted in timeout.");
-                Ok(false)
+        Ok(Err(err)) if err.kind() == ErrorKind::ConnectionRefused => {
+            log::trace!("UDP probe to {addr}:{port} resulted in port unreachable.");
+            Ok(Health::Down)
+        }
+        Ok(Err(err)) => Err(Error::msg(format!(
+            "Failed to probe {}:{} over UDP: {}",
+            addr, port, err
+        ))),
+        Err(_) => {
+            log::trace!("UDP probe to {addr}:{port} resulted in timeout.");
+            if expect_response {
+                Ok(Health::Down)
+            } else {
+                Ok(Health::Up { rtt: None })
             }
-            _ => Err(Error::msg(format!("Failed to ping {}: {}", addr, err))),
-        },
+        }
     }
 }
 
-pub async fn resolve_ip(addr: &str) -> anyhow::Result<IpAddr> {
-    let ip = tokio::net::lookup_host(format!("{}:0", addr))
+async fn tcp_check(ip: IpAddr, addr: &str, port: u16, timeout: Duration) -> HealthResult {
+    let start = Instant::now();
+    match time::timeout(timeout, TcpStream::connect(SocketAddr::new(ip, port))).await {
+        Ok(Ok(_)) => {
+            let rtt = start.elapsed();
+            log::trace!("TCP connection to {addr}:{port} succeeded in {rtt:0.2?}.");
+            Ok(Health::Up { rtt: Some(rtt) })
+        }
+        Ok(Err(err)) if err.kind() == ErrorKind::ConnectionRefused => {
+            log::trace!("TCP connection to {addr}:{port} was refused.");
+            Ok(Health::Down)
+        }
+        Ok(Err(err)) => Err(Error::msg(format!(
+            "Failed to connect to {}:{} over TCP: {}",
+            addr, port, err
+        ))),
+        Err(_) => {
+            log::trace!("TCP connection to {addr}:{port} resulted in timeout.");
+            Ok(Health::Down)
+        }
+    }
+}
+
+async fn http_check(
+    ip: IpAddr,
+    addr: &str,
+    https: bool,
+    port: Option<u16>,
+    path: &str,
+    timeout: Duration,
+) -> HealthResult {
+    let (scheme, default_port) = if https { ("https", 443) } else { ("http", 80) };
+    let port = port.unwrap_or(default_port);
+    let path = path.strip_prefix('/').unwrap_or(path);
+    let host = match addr.parse::<Ipv6Addr>() {
+        Ok(_) => format!("[{addr}]"),
+        Err(_) => addr.to_string(),
+    };
+    let url = format!("{scheme}://{host}:{port}/{path}");
+    // The host name stays in the URL for TLS and virtual hosts, but the already resolved address is connected to
+    let client = reqwest::Client::builder()
+        .resolve(addr, SocketAddr::new(ip, port))
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(timeout)
+        .build()?;
+    let start = Instant::now();
+    match client.get(&url).send().await {
+        Ok(response) if response.status().is_success() || response.status().is_redirection() => {
+            let rtt = start.elapsed();
+            log::trace!("GET {url} resulted in {} in {rtt:0.2?}.", response.status());
+            Ok(Health::Up { rtt: Some(rtt) })
+        }
+        Ok(response) => {
+            log::trace!("GET {url} resulted in {}.", response.status());
+            Ok(Health::Down)
+        }
+        Err(err) if err.is_timeout() || err.is_connect() => {
+            log::trace!("GET {url} failed to connect: {err}");
+            Ok(Health::Down)
+        }
+        Err(err) => Err(Error::msg(format!("Failed to request {}: {}", url, err))),
+    }
+}
+
+/// Combines both legs of an ICMP and TCP check. A host, which doesn't respond to ICMP, is down regardless of its
+/// service, while a responding host with a failed TCP leg means that only the service is down
+fn combine_legs(icmp: HealthResult, tcp: HealthResult) -> HealthResult {
+    match (icmp?, tcp?) {
+        (Health::Up { rtt }, Health::Up { .. }) => Ok(Health::Up { rtt }),
+        (Health::Up { .. }, _) => Ok(Health::ServiceDown),
+        _ => Ok(Health::Down),
+    }
+}
+
+/// DNS resolution did not finish within the configured timeout
+#[derive(Debug)]
+pub struct DnsTimeoutError {
+    addr: String,
+    timeout: Duration,
+}
+
+impl Display for DnsTimeoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "DNS resolution of {} timed out after {:?}",
+            self.addr, self.timeout
+        )
+    }
+}
+
+impl std::error::Error for DnsTimeoutError {}
+
+/// Check wasn't started, since too many checks are still in progress
+#[derive(Debug)]
+pub struct TooManyChecksError {
+    addr: String,
+    limit: u32,
+}
+
+impl Display for TooManyChecksError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Skipped check of {}, since {} checks are still in progress",
+            self.addr, self.limit
+        )
+    }
+}
+
+impl std::error::Error for TooManyChecksError {}
+
+/// Resolves `addr` to the addresses, which are checked: both families with `dual_stack`, the first address otherwise
+async fn resolve_host(addr: &str, ping_config: &PingConfig) -> anyhow::Result<Vec<IpAddr>> {
+    if ping_config.dual_stack {
+        resolve_families(addr, ping_config.dns_timeout).await
+    } else {
+        Ok(vec![resolve_ip(addr, ping_config.dns_timeout).await?])
+    }
+}
+
+pub async fn resolve_ip(addr: &str, timeout: Duration) -> anyhow::Result<IpAddr> {
+    let ip = lookup_ips(addr, timeout)
         .await?
         .next()
-        .map(|val| val.ip())
         .ok_or(Error::msg(format!(
             "Failed to resolve DNS for domain {addr}: No IP associated with it"
         )))?;
     Ok(ip)
 }
+
+/// Resolves the first IPv4 and the first IPv6 address of `addr`, whichever of them exist
+pub async fn resolve_families(addr: &str, timeout: Duration) -> anyhow::Result<Vec<IpAddr>> {
+    let ips: Vec<_> = lookup_ips(addr, timeout).await?.collect();
+    let families: Vec<_> = [
+        ips.iter().find(|ip| ip.is_ipv4()),
+        ips.iter().find(|ip| ip.is_ipv6()),
+    ]
+    .into_iter()
+    .flatten()
+    .copied()
+    .collect();
+    if families.is_empty() {
+        return Err(Error::msg(format!(
+            "Failed to resolve DNS for domain {addr}: No IP associated with it"
+        )));
+    }
+    Ok(families)
+}
+
+async fn lookup_ips(
+    addr: &str,
+    timeout: Duration,
+) -> anyhow::Result<impl Iterator<Item = IpAddr> + use<>> {
+    let lookup = time::timeout(timeout, tokio::net::lookup_host(format!("{}:0", addr)))
+        .await
+        .map_err(|_| DnsTimeoutError {
+            addr: addr.to_string(),
+            timeout,
+        })?;
+    Ok(lookup?.map(|val| val.ip()))
+}
+
+/// Parses a comma-separated list of ports and port ranges, e.g. "80,443,27015-27017"
+pub fn parse_ports(ports: &str) -> anyhow::Result<Vec<u16>> {
+    let mut parsed = Vec::new();
+    for part in ports
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let parse = |port: &str| {
+            port.trim()
+                .parse::<u16>()
+                .map_err(|err| Error::msg(format!("Invalid port {}: {}", port, err)))
+        };
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (parse(start)?, parse(end)?),
+            None => (parse(part)?, parse(part)?),
+        };
+        if start > end {
+            return Err(Error::msg(format!("Invalid port range {}", part)));
+        }
+        if parsed.len() + usize::from(end - start) + 1 > MAX_PORTSCAN_PORTS {
+            return Err(Error::msg(format!(
+                "Too many ports: at most {} can be checked at once",
+                MAX_PORTSCAN_PORTS
+            )));
+        }
+        parsed.extend(start..=end);
+    }
+    if parsed.is_empty() {
+        return Err(Error::msg("No ports to check"));
+    }
+    parsed.sort_unstable();
+    parsed.dedup();
+    Ok(parsed)
+}
+
+/// Tries to open a TCP connection to each port at once. Returns ports with whether they accepted the connection
+pub async fn scan_ports(ip: IpAddr, ports: &[u16], timeout: Duration) -> Vec<(u16, bool)> {
+    futures::future::join_all(ports.iter().map(|&port| async move {
+        let connect = tokio::net::TcpStream::connect(SocketAddr::new(ip, port));
+        let open = matches!(time::timeout(timeout, connect).await, Ok(Ok(_)));
+        (port, open)
+    }))
+    .await
+}
+
 #[cfg(test)]
 mod tests {
-    use std::{process, time::Duration};
+    use std::{
+        io::ErrorKind,
+        net::{IpAddr, Ipv4Addr, Ipv6Addr},
+        num::NonZeroU8,
+        time::Duration,
+    };
 
-    use crate::{DEFAULT_TIMEOUT_SECS, ping::healthcheck};
+    use anyhow::Error;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, UdpSocket},
+        time,
+    };
+
+    use crate::{
+        PingConfig,
+        ping::{
+            Health, MAX_ICMP_IDS, MAX_PORTSCAN_PORTS, PingClient, SrvTarget, TooManyChecksError,
+            UnknownCause, by_preference, combine_legs, family_verdict, http_check, is_host_error,
+            parse_ports, retune_interval, tcp_check, udp_check,
+        },
+    };
 
     // let's just hope that google will not go down while we are testing
     const SUCCESSFUL_HEALTHCHECK_ADDR: &str = "google.com";
     const TIMEOUT_HEALTHCHECK_ADDR: &str = "1123";
     const FAILING_HEALTHCHECK_ADDR: &str = "fwrgrwetf3";
 
+    #[test]
+    fn permission_errors_are_told_apart() {
+        let denied = Error::from(std::io::Error::from(ErrorKind::PermissionDenied))
+            .context("Failed to open ICMP socket");
+        assert_eq!(UnknownCause::of(&denied), UnknownCause::NotPermitted);
+
+        let unresolved = Error::msg("Failed to resolve DNS for domain example.invalid");
+        assert_eq!(UnknownCause::of(&unresolved), UnknownCause::Unreachable);
+    }
+
+    #[test]
+    fn only_host_errors_count_towards_restart() {
+        let denied = Error::from(std::io::Error::from(ErrorKind::PermissionDenied))
+            .context("Failed to open ICMP socket");
+        assert!(is_host_error(&denied));
+
+        let no_network = Error::new(surge_ping::SurgeError::IOError(std::io::Error::from(
+            ErrorKind::NetworkDown,
+        )))
+        .context("Failed to ping 127.0.0.1");
+        assert!(is_host_error(&no_network));
+
+        let unresolved = Error::msg("Failed to resolve DNS for domain example.invalid");
+        assert!(!is_host_error(&unresolved));
+        let nxdomain = Error::from(std::io::Error::other(
+            "failed to lookup address information",
+        ));
+        assert!(!is_host_error(&nxdomain));
+    }
+
+    #[test]
+    fn addresses_get_distinct_icmp_ids() {
+        let client = PingClient::new(u16::MAX);
+
+        let resource_id = client.icmp_id_of("example.com");
+        let probe_id = client.icmp_id_of("probe.example.com");
+
+        assert_eq!(resource_id, u16::MAX);
+        assert_eq!(probe_id, 0);
+        assert_eq!(client.icmp_id_of("example.com"), resource_id);
+
+        for i in 0..MAX_ICMP_IDS {
+            client.icmp_id_of(&format!("{}.example.com", i));
+        }
+        assert_eq!(client.icmp_ids.lock().unwrap().len(), MAX_ICMP_IDS);
+        assert_ne!(client.icmp_id_of("example.com"), resource_id);
+    }
+
+    #[test]
+    fn outstanding_checks_are_capped() {
+        let client = PingClient::new(0);
+        let ping_config = PingConfig {
+            max_outstanding_checks: NonZeroU8::new(2).unwrap(),
+            ..Default::default()
+        };
+
+        let first = client.start_check("example.com", &ping_config).unwrap();
+        let _second = client.start_check("example.com", &ping_config).unwrap();
+        let skipped = client.start_check("example.com", &ping_config);
+        assert!(matches!(skipped, Err(TooManyChecksError { limit: 2, .. })));
+
+        drop(first);
+        assert!(client.start_check("example.com", &ping_config).is_ok());
+    }
+
+    #[test]
+    fn srv_targets_are_ordered_by_priority_and_weight() {
+        let target = |priority, weight, host: &str| SrvTarget {
+            priority,
+            weight,
+            host: host.to_string(),
+            port: 25565,
+        };
+        let ordered = by_preference(vec![
+            target(20, 100, "backup.example.com"),
+            target(10, 5, "light.example.com"),
+            target(10, 60, "heavy.example.com"),
+        ]);
+
+        let hosts: Vec<_> = ordered.iter().map(|target| target.host.as_str()).collect();
+        assert_eq!(
+            hosts,
+            [
+                "heavy.example.com",
+                "light.example.com",
+                "backup.example.com"
+            ]
+        );
+    }
+
+    #[test]
+    fn any_working_family_is_enough() {
+        let v4 = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let v6 = IpAddr::V6(Ipv6Addr::LOCALHOST);
+        let up = Health::Up {
+            rtt: Some(Duration::from_millis(10)),
+        };
+
+        let (result, unreachable) = family_verdict(vec![(v4, Ok(up)), (v6, Ok(Health::Down))]);
+        assert_eq!(result.unwrap(), up);
+        assert_eq!(unreachable, vec!["IPv6"]);
+
+        let (result, unreachable) = family_verdict(vec![
+            (v4, Err(Error::msg("No route"))),
+            (v6, Ok(Health::Down)),
+        ]);
+        assert_eq!(result.unwrap(), Health::Down);
+        assert!(unreachable.is_empty());
+
+        let (result, unreachable) = family_verdict(vec![(v4, Ok(up))]);
+        assert_eq!(result.unwrap(), up);
+        assert!(unreachable.is_empty());
+    }
+
+    #[test]
+    fn failing_leg_is_reported() {
+        let up = || {
+            Ok(Health::Up {
+                rtt: Some(Duration::from_millis(10)),
+            })
+        };
+
+        assert_eq!(combine_legs(up(), up()).unwrap(), up().unwrap());
+        assert_eq!(
+            combine_legs(up(), Ok(Health::Down)).unwrap(),
+            Health::ServiceDown
+        );
+        assert_eq!(combine_legs(Ok(Health::Down), up()).unwrap(), Health::Down);
+        assert_eq!(
+            combine_legs(Ok(Health::Down), Ok(Health::Down)).unwrap(),
+            Health::Down
+        );
+        assert!(combine_legs(up(), Err(Error::msg("Network is unreachable"))).is_err());
+    }
+
+    #[test]
+    fn port_lists_are_bounded() {
+        assert_eq!(
+            parse_ports("443, 80,27015-27017,80").unwrap(),
+            vec![80, 443, 27015, 27016, 27017]
+        );
+        assert!(parse_ports("1-65535").is_err());
+        assert!(parse_ports(&format!("1-{}", MAX_PORTSCAN_PORTS)).is_ok());
+        assert!(parse_ports("100-90").is_err());
+        assert!(parse_ports("http").is_err());
+        assert!(parse_ports(" , ").is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn interval_is_ticked_once_per_check() {
+        let mut interval = time::interval(Duration::from_secs(10));
+        let start = time::Instant::now();
+        // The first tick completes right away
+        interval.tick().await;
+        retune_interval(&mut interval, Duration::from_secs(10));
+        interval.tick().await;
+        assert_eq!(start.elapsed(), Duration::from_secs(10));
+
+        // Changed in the middle of the loop, e.g. by /config interval
+        retune_interval(&mut interval, Duration::from_secs(30));
+        interval.tick().await;
+        assert_eq!(start.elapsed(), Duration::from_secs(40));
+        retune_interval(&mut interval, Duration::from_secs(30));
+        interval.tick().await;
+        assert_eq!(start.elapsed(), Duration::from_secs(70));
+    }
+
     #[tokio::test]
-    #[cfg_attr(feature = "ci", ignore)]
-    async fn healthcheck_success() {
-        let icmp_sequence: u16 = 0;
-        let icmp_id: u16 = process::id() as u16;
+    async fn tcp_handshake_decides_health() {
+        let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let timeout = Duration::from_secs(5);
+        let listener = TcpListener::bind((localhost, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
 
-        let healthcheck_result = healthcheck(
-            SUCCESSFUL_HEALTHCHECK_ADDR,
-            Duration::from_secs(DEFAULT_TIMEOUT_SECS),
-            icmp_sequence,
-            icmp_id,
+        let health = tcp_check(localhost, "localhost", port, timeout).await;
+        assert!(matches!(health, Ok(Health::Up { .. })), "{:?}", health);
+
+        drop(listener);
+        let health = tcp_check(localhost, "localhost", port, timeout).await;
+        assert_eq!(health.unwrap(), Health::Down);
+    }
+
+    #[tokio::test]
+    async fn udp_response_decides_health() {
+        let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let timeout = Duration::from_millis(500);
+        let probe = [255, 255, 255, 255];
+
+        let responder = UdpSocket::bind((localhost, 0)).await.unwrap();
+        let port = responder.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut request = [0; 16];
+            let (len, peer) = responder.recv_from(&mut request).await.unwrap();
+            responder.send_to(&request[..len], peer).await.unwrap();
+        });
+        let health = udp_check(localhost, "localhost", port, &probe, true, timeout).await;
+        assert!(matches!(health, Ok(Health::Up { .. })), "{:?}", health);
+
+        let silent = UdpSocket::bind((localhost, 0)).await.unwrap();
+        let port = silent.local_addr().unwrap().port();
+        let health = udp_check(localhost, "localhost", port, &probe, true, timeout).await;
+        assert_eq!(health.unwrap(), Health::Down);
+        let health = udp_check(localhost, "localhost", port, &probe, false, timeout).await;
+        assert_eq!(health.unwrap(), Health::Up { rtt: None });
+    }
+
+    #[tokio::test]
+    async fn http_status_decides_health() {
+        async fn respond_with(status_line: &'static str) -> u16 {
+            let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            tokio::spawn(async move {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request).await;
+                let response = format!("{status_line}\r\ncontent-length: 0\r\n\r\n");
+                stream.write_all(response.as_bytes()).await.unwrap();
+            });
+            port
+        }
+        let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let timeout = Duration::from_secs(5);
+
+        let port = respond_with("HTTP/1.1 204 No Content").await;
+        let health = http_check(
+            localhost,
+            "localhost",
+            false,
+            Some(port),
+            "/health",
+            timeout,
         )
         .await;
+        assert!(matches!(health, Ok(Health::Up { .. })), "{:?}", health);
+
+        let port = respond_with("HTTP/1.1 301 Moved Permanently").await;
+        let health = http_check(localhost, "localhost", false, Some(port), "", timeout).await;
+        assert!(matches!(health, Ok(Health::Up { .. })), "{:?}", health);
+
+        let port = respond_with("HTTP/1.1 503 Service Unavailable").await;
+        let health = http_check(localhost, "localhost", false, Some(port), "", timeout).await;
+        assert_eq!(health.unwrap(), Health::Down);
+    }
+
+    #[tokio::test]
+    #[cfg_attr(feature = "ci", ignore)]
+    async fn healthcheck_success() {
+        let client = PingClient::default();
+
+        let healthcheck_result = client
+            .check_addr(SUCCESSFUL_HEALTHCHECK_ADDR, &PingConfig::default())
+            .await;
 
         assert!(
-            &healthcheck_result.as_ref().is_ok_and(|ok| *ok),
+            &healthcheck_result
+                .as_ref()
+                .is_ok_and(|health| matches!(health, Health::Up { .. })),
             "Healthchecking {} failed: {:?}",
             SUCCESSFUL_HEALTHCHECK_ADDR,
             healthcheck_result
@@ -132,19 +1246,16 @@ mod tests {
     #[tokio::test]
     #[cfg_attr(feature = "ci", ignore)]
     async fn healthcheck_timeout() {
-        let icmp_sequence: u16 = 0;
-        let icmp_id: u16 = process::id() as u16;
+        let client = PingClient::default();
 
-        let healthcheck_result = healthcheck(
-            TIMEOUT_HEALTHCHECK_ADDR,
-            Duration::from_secs(DEFAULT_TIMEOUT_SECS),
-            icmp_sequence,
-            icmp_id,
-        )
-        .await;
+        let healthcheck_result = client
+            .check_addr(TIMEOUT_HEALTHCHECK_ADDR, &PingConfig::default())
+            .await;
 
         assert!(
-            &healthcheck_result.as_ref().is_ok_and(|ok| !*ok),
+            &healthcheck_result
+                .as_ref()
+                .is_ok_and(|health| *health == Health::Down),
             "Healthchecking address {} did not result in a timeout: {:?}",
             TIMEOUT_HEALTHCHECK_ADDR,
             healthcheck_result
@@ -154,16 +1265,11 @@ mod tests {
     #[tokio::test]
     #[cfg_attr(feature = "ci", ignore)]
     async fn healthcheck_error() {
-        let icmp_sequence: u16 = 0;
-        let icmp_id: u16 = process::id() as u16;
+        let client = PingClient::default();
 
-        let healthcheck_result = healthcheck(
-            FAILING_HEALTHCHECK_ADDR,
-            Duration::from_secs(DEFAULT_TIMEOUT_SECS),
-            icmp_sequence,
-            icmp_id,
-        )
-        .await;
+        let healthcheck_result = client
+            .check_addr(FAILING_HEALTHCHECK_ADDR, &PingConfig::default())
+            .await;
 
         assert!(
             &healthcheck_result.as_ref().is_err(),
@@ -1,99 +1,383 @@
-use std::sync::{Arc, atomic::Ordering};
+use std::{
+    path::Path,
+    sync::{Arc, atomic::Ordering},
+    time::{Duration, Instant},
+};
 
-use poise::serenity_prelude::{
-    Channel, CreateEmbed, CreateMessage, GuildId, Http, RoleId, Timestamp,
+use poise::{
+    ChoiceParameter,
+    serenity_prelude::{
+        self as serenity, ActivityData, Channel, CreateEmbed, CreateEmbedFooter, CreateMessage,
+        EditMessage, GuildId, Http, HttpError, Message, OnlineStatus, Permissions, ReactionType,
+        RoleId, Timestamp, UserId, http::StatusCode,
+    },
 };
 
-use crate::{Data, ResourceStatus, ServerUsedMessages, save_data};
+use crate::{
+    AppData, Config, Data, MAX_RESOURCE_NAME_LENGTH, ResourceStatus, SentNotification,
+    ServerConfig, ServerUsedMessages, TransitionKind,
+    events::{DISCORD_MESSAGE_LIMIT, EVENTS_TARGET},
+    ping::UnknownCause,
+    save_data,
+    template::{EmbedTemplate, EmbedValues},
+    webhooks::fire_webhooks,
+};
 
 pub const DEFAULT_UP_MESSAGE: &str = "%%RESOURCE%% is back online, %%ROLE%%!";
 pub const DEFAULT_DOWN_MESSAGE: &str = "Nevermind, it's dead again. Boowomp :sob:.";
+pub const DEFAULT_MAINTENANCE_START_MESSAGE: &str =
+    "%%RESOURCE%% is down for scheduled maintenance, hang tight.";
+pub const DEFAULT_MAINTENANCE_END_MESSAGE: &str =
+    "%%RESOURCE%% is back online after %%DOWNTIME%% of maintenance, %%ROLE%%!";
+
+pub const DEFAULT_SINCE_LABEL: &str = "Since";
+pub const DEFAULT_ADDRESS_LABEL: &str = "Address";
+pub const DEFAULT_LATENCY_LABEL: &str = "Latency";
+/// How often the status embeds are edited with the current round-trip time, while the resource is up
+const LATENCY_REFRESH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// Shown instead of the time of the last status change, when the status never changed
+const UNKNOWN_SINCE_VALUE: &str = "unknown";
 
 const ROLE_FALLBACK_STRING: &str = "people";
+const DOWNTIME_FALLBACK_STRING: &str = "some time";
+/// Discord's error code for a channel, which already has the maximum of 50 pinned messages
+const MAX_PINS_REACHED_CODE: isize = 30003;
+/// Colours of the default embed for good and bad news
+const UP_COLOUR: (u8, u8, u8) = (21, 250, 59);
+const DOWN_COLOUR: (u8, u8, u8) = (220, 23, 30);
 
 const TEMPLATE_RESOURCE_NAME: &str = "%%RESOURCE%%";
 const TEMPLATE_ROLE_PING: &str = "%%ROLE%%";
+const TEMPLATE_DOWNTIME: &str = "%%DOWNTIME%%";
+/// Downtime, whose formatting has every unit from years to seconds
+const WORST_CASE_DOWNTIME: Duration = Duration::from_secs(3_155_759_999);
+
+/// Confirmed change of resource's status
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatusChange {
+    pub old_status: ResourceStatus,
+    pub new_status: ResourceStatus,
+    /// When the resource entered `old_status`
+    pub old_status_since: Timestamp,
+    pub new_status_since: Timestamp,
+}
+
+impl StatusChange {
+    /// How long the resource stayed in the old status
+    pub fn old_status_duration(&self) -> Duration {
+        let secs = self.new_status_since.unix_timestamp() - self.old_status_since.unix_timestamp();
+        Duration::from_secs(secs.max(0) as u64)
+    }
+    /// Time since the change was confirmed, e.g. until its message was delivered
+    pub fn delivery_latency(&self, delivered_at: Timestamp) -> Duration {
+        (*delivered_at - *self.new_status_since)
+            .to_std()
+            .unwrap_or_default()
+    }
+    /// Kind of the transition, which has its own message. Only transitions between Up and Down have one, and going
+    /// down right as active hours begin, so the outage isn't missed
+    pub fn transition_kind(&self, config: &Config) -> Option<TransitionKind> {
+        match (self.old_status, self.new_status) {
+            (ResourceStatus::Up | ResourceStatus::Inactive, ResourceStatus::Down) => {
+                if config.in_maintenance(self.new_status_since) {
+                    Some(TransitionKind::MaintenanceStart)
+                } else {
+                    Some(TransitionKind::Down)
+                }
+            }
+            // Outage, which began during maintenance, was planned, even if it overran the window
+            (ResourceStatus::Down, ResourceStatus::Up) => {
+                if config.in_maintenance(self.old_status_since) {
+                    Some(TransitionKind::MaintenanceEnd)
+                } else {
+                    Some(TransitionKind::Up)
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Values substituted into message templates
+struct TemplateValues<'a> {
+    resource_name: &'a str,
+    role_id: Option<RoleId>,
+    /// Whether %%ROLE%% should actually mention the role
+    mention: bool,
+    downtime: Option<Duration>,
+}
+
+/// Folds the results of every probe made during one tick into a single status, so disagreeing
+/// probes never produce partial updates. A tie has no verdict.
+pub fn tick_verdict(results: &[ResourceStatus]) -> Option<ResourceStatus> {
+    let up = results
+        .iter()
+        .filter(|status| **status == ResourceStatus::Up)
+        .count();
+    let down = results
+        .iter()
+        .filter(|status| **status == ResourceStatus::Down)
+        .count();
+    if up == 0 && down == 0 {
+        return Some(ResourceStatus::Unknown);
+    }
+    match up.cmp(&down) {
+        std::cmp::Ordering::Greater => Some(ResourceStatus::Up),
+        std::cmp::Ordering::Less => Some(ResourceStatus::Down),
+        std::cmp::Ordering::Equal => None,
+    }
+}
+
+/// Verdict of one tick, checked by several probes. If `quorum` is set, the resource is down only when at least
+/// that many probes failed, and up when any probe succeeded otherwise. Without it, the majority decides
+pub fn quorum_verdict(results: &[ResourceStatus], quorum: Option<u8>) -> Option<ResourceStatus> {
+    let Some(quorum) = quorum else {
+        return tick_verdict(results);
+    };
+    let down = results
+        .iter()
+        .filter(|status| **status == ResourceStatus::Down)
+        .count();
+    if down >= usize::from(quorum.max(1)) {
+        Some(ResourceStatus::Down)
+    } else if results.contains(&ResourceStatus::Up) {
+        Some(ResourceStatus::Up)
+    } else {
+        Some(ResourceStatus::Unknown)
+    }
+}
+
+pub async fn update_status(verdict: Option<ResourceStatus>, data: Data, http: Arc<Http>) {
+    let counted_attempts = data.attempts_before_notification.load(Ordering::Relaxed);
+    let Some(change) = apply_tick(verdict, &data, Instant::now()).await else {
+        // Persisted, so a restart in the middle of confirming a change continues counting instead of starting over
+        if data.attempts_before_notification.load(Ordering::Relaxed) != counted_attempts {
+            save_data(&data).await;
+        }
+        return;
+    };
+    // Persisted before announcing, so a crash in the middle doesn't repeat the announcement after restart: the restarted
+    // bot already has the new status and detects no change
+    save_data(&data).await;
+    update_presence(&data).await;
+    if change.new_status == ResourceStatus::Unknown
+        && data.in_startup_grace(&*data.config.read().await)
+    {
+        log::info!("Status is unknown during the startup grace, not showing it to the servers yet");
+        data.unknown_suppressed.store(true, Ordering::Relaxed);
+        return;
+    }
+    data.unknown_suppressed.store(false, Ordering::Relaxed);
+    fire_webhooks(&change, &*data.config.read().await);
+    notify_status_change(change, data.clone(), http.clone()).await;
+    save_data(&data).await;
+}
+
+/// Applies the verdict of one tick to the state. Returns the status transition, if this tick confirmed one.
+async fn apply_tick(
+    verdict: Option<ResourceStatus>,
+    data: &AppData,
+    now: Instant,
+) -> Option<StatusChange> {
+    // A split tick says nothing about the resource, so it neither confirms a change nor interrupts confirming one
+    let Some(status) = verdict else {
+        log::debug!("Probes are split evenly, keeping the status and counted attempts");
+        return None;
+    };
+    {
+        let mut responding_since = data.responding_since.write().await;
+        if status == ResourceStatus::Up {
+            responding_since.get_or_insert(now);
+        } else {
+            *responding_since = None;
+        }
+    }
 
-pub async fn update_status(status: ResourceStatus, data: Data, http: Arc<Http>) {
     let old_status = data.status.read().await.to_owned();
     if status == old_status {
         data.attempts_before_notification
             .store(0, Ordering::Relaxed);
-        return;
+        *data.last_counted_failure.write().await = None;
+        return None;
     }
 
     // Status changed
     let config = data.config.read().await;
     let required_attempts_before_notification =
         config.ping_config.required_attempts_before_notification;
+    let min_stable_duration = config.ping_config.min_stable_duration;
+    let down_check_spacing = config.ping_config.down_check_spacing;
+    let verbose_confirmation = config.verbose_confirmation;
     drop(config);
 
-    if data
+    // The first completed check leaves Pending right away, there is no previous status to confirm the change against.
+    // Same for Unknown: a definite result after errors is trusted at once, only Up <-> Down is gated
+    // Inactive status isn't a result of checks, so entering and leaving active hours is applied at once too
+    let old_status_unconfirmed = matches!(
+        old_status,
+        ResourceStatus::Pending | ResourceStatus::Unknown | ResourceStatus::Inactive
+    ) || status == ResourceStatus::Inactive;
+    if status == ResourceStatus::Down && !old_status_unconfirmed {
+        let mut last_counted_failure = data.last_counted_failure.write().await;
+        if last_counted_failure
+            .is_some_and(|last| now.saturating_duration_since(last) < down_check_spacing)
+        {
+            log::debug!("Failed check is too close to the previous one, not counting it");
+            return None;
+        }
+        *last_counted_failure = Some(now);
+    }
+    // Saturating, so a long run of unconfirmed checks can't wrap around and start confirming over
+    let counted_attempts = data
         .attempts_before_notification
-        .fetch_add(1, Ordering::Relaxed)
-        >= required_attempts_before_notification
-    {
-        log::info!("Changed status from {} to {}", old_status, status);
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |attempts| {
+            Some(attempts.saturating_add(1))
+        })
+        .unwrap_or_else(|attempts| attempts);
+    if old_status_unconfirmed || counted_attempts >= required_attempts_before_notification {
+        if status == ResourceStatus::Up && !old_status_unconfirmed {
+            let responding_for = data
+                .responding_since
+                .read()
+                .await
+                .map_or(Duration::ZERO, |since| now.duration_since(since));
+            if responding_for < min_stable_duration {
+                log::debug!(
+                    "Resource is up for {}, waiting for it to be stable",
+                    humantime::format_duration(responding_for)
+                );
+                return None;
+            }
+        }
+
+        log::info!(
+            target: EVENTS_TARGET,
+            "Changed status from {} to {}",
+            old_status,
+            status
+        );
         data.attempts_before_notification
             .store(0, Ordering::Relaxed);
+        *data.last_counted_failure.write().await = None;
         *data.status.write().await = status;
-        *data.last_status_change.write().await = Timestamp::now();
-        notify_status_change(old_status, status, data.clone(), http.clone()).await;
-        save_data(&data).await;
+        let now = Timestamp::now();
+        let old_status_since = std::mem::replace(&mut *data.last_status_change.write().await, now);
+        return Some(StatusChange {
+            old_status,
+            new_status: status,
+            old_status_since,
+            new_status_since: now,
+        });
     }
+    if verbose_confirmation {
+        log::info!(
+            target: EVENTS_TARGET,
+            "Confirming change from {} to {}: {}/{} checks",
+            old_status,
+            status,
+            u16::from(counted_attempts) + 1,
+            u16::from(required_attempts_before_notification) + 1
+        );
+    }
+    None
 }
 
-pub async fn notify_status_change(
-    old_status: ResourceStatus,
-    new_status: ResourceStatus,
-    data: Data,
-    http: Arc<Http>,
-) {
+pub async fn notify_status_change(change: StatusChange, data: Data, http: Arc<Http>) {
+    let StatusChange {
+        old_status,
+        new_status,
+        ..
+    } = change;
     let config_lock = data.config.read().await;
-    let resource_name = config_lock.ping_config.resource_name.clone();
-    let addr = data.config.read().await.ping_config.resource_addr.clone();
-    let last_status_change = data.last_status_change.read().await.to_owned();
-
-    let embed = generate_embed(resource_name.as_str(), new_status, addr, last_status_change);
+    let resource_name = fit_resource_name(&config_lock.ping_config.resource_name);
+    let addr = config_lock.ping_config.resource_addr.clone();
+    let history_limit = config_lock.history_limit();
+    let unknown_cause = *data.unknown_cause.read().await;
+    let linked_status = *data.linked_status.read().await;
+    let unreachable_families = data.unreachable_families().await;
+    let linked = config_lock
+        .ping_config
+        .linked_probe
+        .as_ref()
+        .map(|probe| (probe.name.as_str(), linked_status));
+    let transition_kind = change.transition_kind(&config_lock);
+    let rtt = data.rtt().await;
+    let service_down = data.service_down.load(Ordering::Relaxed);
 
     for (server_id, server_config) in &config_lock.server_configs {
+        // Without the status embed, only transitions with a message are announced
+        if !server_config.embed_enabled && transition_kind.is_none() {
+            continue;
+        }
+        if new_status == ResourceStatus::Unknown && !config_lock.shows_unknown(*server_id) {
+            continue;
+        }
+        let values = EmbedValues {
+            resource_name: &resource_name,
+            addr: &addr,
+            status: new_status,
+            since: change.new_status_since,
+            linked,
+            unreachable_families: unreachable_families.as_deref(),
+            rtt,
+            service_down,
+        };
+        let embed = server_embed(*server_id, server_config, &values, unknown_cause).await;
         let role_id = server_config.role_to_notify;
-        let channel_id = server_config.channel;
-        let channel = match channel_id {
-            Some(id) => {
-                let channel_result = http.clone().get_channel(id).await;
-                if let Ok(channel) = channel_result {
-                    channel
-                } else {
-                    log::warn!(
-                        "[server {}] Failed to fetch channel: {}. Notification aborted.",
-                        server_id,
-                        channel_result.unwrap_err()
-                    );
-                    continue;
-                }
-            }
-            None => {
-                log::warn!(
-                    "[server {}] No notification channel specified. Notification aborted.",
-                    server_id
-                );
-                continue;
-            }
+        // Cooldown only suppresses the mention, the status embed is always updated
+        let mention_allowed =
+            data.used_messages
+                .read()
+                .await
+                .get(server_id)
+                .is_none_or(|messages| {
+                    messages.mention_allowed(Timestamp::now(), server_config.mention_cooldown)
+                });
+        let Some(channel) = notification_channel(*server_id, server_config, &http).await else {
+            continue;
         };
 
-        match (old_status, new_status) {
-            (_, ResourceStatus::Unknown) => {
-                update_embed(*server_id, &embed, data.clone(), channel, http.clone()).await;
-            }
-            (ResourceStatus::Unknown, _) => {
-                update_embed(*server_id, &embed, data.clone(), channel, http.clone()).await;
+        match transition_kind {
+            None => {
+                update_embed(
+                    *server_id,
+                    &embed,
+                    server_config,
+                    history_limit,
+                    data.clone(),
+                    channel,
+                    http.clone(),
+                )
+                .await;
             }
-            (ResourceStatus::Up, ResourceStatus::Down) => {
+            Some(kind) => {
+                let went_down = new_status == ResourceStatus::Down;
+                // Inverted servers treat the resource being up as bad news, e.g. a maintenance page
+                let bad_news = went_down != server_config.inverted;
+                let kind = if server_config.inverted {
+                    kind.inverted()
+                } else {
+                    kind
+                };
+                let (ping_on, reactions, news) = if bad_news {
+                    (
+                        server_config.ping_on_down,
+                        &server_config.down_reactions,
+                        "down",
+                    )
+                } else {
+                    (server_config.ping_on_up, &server_config.up_reactions, "up")
+                };
                 let message: String = replace_templates(
-                    server_config.down_message.as_str(),
-                    &resource_name,
-                    &role_id,
+                    server_config.message(kind),
+                    &TemplateValues {
+                        resource_name: &resource_name,
+                        role_id,
+                        mention: ping_on && mention_allowed,
+                        // Time spent outside of active hours isn't downtime
+                        downtime: (!bad_news && old_status != ResourceStatus::Inactive)
+                            .then(|| change.old_status_duration()),
+                    },
                 );
                 let send_result = channel
                     .id()
@@ -101,59 +385,444 @@ pub async fn notify_status_change(
                     .await;
                 match send_result {
                     Ok(message) => {
+                        let latency = change.delivery_latency(Timestamp::now());
                         log::info!(
-                            "[server {}] Sent new down message with id {}",
+                            "[server {}] Sent new {} message with id {} in {:0.2?}",
                             server_id,
-                            message.id
+                            news,
+                            message.id,
+                            latency
                         );
+                        data.record_delivery(*server_id, latency).await;
+                        add_reactions(*server_id, &message, reactions, &http).await;
+                        if server_config.cleanup_messages {
+                            delete_previous_notification(*server_id, &data, &http).await;
+                        }
+                        let mentioned = ping_on && mention_allowed && role_id.is_some();
+                        remember_notification(
+                            *server_id,
+                            &message,
+                            mentioned,
+                            history_limit,
+                            &data,
+                        )
+                        .await;
                     }
                     Err(err) => {
                         log::error!(
-                            "[server {}] Failed to send new down message: {}",
+                            "[server {}] Failed to send new {} message: {}",
                             server_id,
-                            err
+                            news,
+                            describe_send_error(&err)
                         );
                         continue;
                     }
                 }
-                update_embed(*server_id, &embed, data.clone(), channel, http.clone()).await;
-            }
-            (ResourceStatus::Down, ResourceStatus::Up) => {
-                let message: String =
-                    replace_templates(server_config.up_message.as_str(), &resource_name, &role_id);
-                let send_result = channel
-                    .id()
-                    .send_message(http.clone(), CreateMessage::new().content(message))
+                if server_config.embed_enabled {
+                    update_embed(
+                        *server_id,
+                        &embed,
+                        server_config,
+                        history_limit,
+                        data.clone(),
+                        channel,
+                        http.clone(),
+                    )
                     .await;
-                match send_result {
-                    Ok(message) => {
-                        log::info!(
-                            "[server {}] Sent new up message with id {}",
-                            server_id,
-                            message.id
-                        );
-                    }
-                    Err(err) => {
-                        log::error!(
-                            "[server {}] Failed to send new up message: {}",
-                            server_id,
-                            err
-                        );
-                        continue;
-                    }
                 }
-                update_embed(*server_id, &embed, data.clone(), channel, http.clone()).await;
             }
-            _ => unreachable!(),
         }
     }
 
     drop(config_lock);
 }
 
+/// Remembers the status of the linked probe. The status embed is updated when it changes, but nobody is notified
+pub async fn update_linked_status(status: ResourceStatus, data: Data, http: Arc<Http>) {
+    if apply_linked_tick(status, &data).await {
+        refresh_embeds(data, http).await;
+    }
+}
+
+/// Counts the linked probe's result towards changing its status. Returns whether the status changed. Like the
+/// resource's one, the first result leaves Pending right away
+async fn apply_linked_tick(status: ResourceStatus, data: &AppData) -> bool {
+    let required_attempts = data
+        .config
+        .read()
+        .await
+        .ping_config
+        .linked_probe
+        .as_ref()
+        .map_or(0, |probe| probe.required_attempts_before_change);
+    let mut status_lock = data.linked_status.write().await;
+    if *status_lock == status {
+        data.linked_attempts.store(0, Ordering::Relaxed);
+        return false;
+    }
+    let counted_attempts = data
+        .linked_attempts
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |attempts| {
+            Some(attempts.saturating_add(1))
+        })
+        .unwrap_or_else(|attempts| attempts);
+    if *status_lock != ResourceStatus::Pending && counted_attempts < required_attempts {
+        log::debug!(
+            "Confirming change of linked probe from {} to {}: {}/{} checks",
+            *status_lock,
+            status,
+            u16::from(counted_attempts) + 1,
+            u16::from(required_attempts) + 1
+        );
+        return false;
+    }
+    data.linked_attempts.store(0, Ordering::Relaxed);
+    let old_status = std::mem::replace(&mut *status_lock, status);
+    log::info!(
+        target: EVENTS_TARGET,
+        "Changed status of linked probe from {} to {}",
+        old_status,
+        status
+    );
+    true
+}
+
+/// Bot's activity and online status, which show the resource's status in the member list
+fn presence(resource_name: &str, status: ResourceStatus) -> (ActivityData, OnlineStatus) {
+    let (state, online_status) = match status {
+        ResourceStatus::Up => ("🟢 {} online", OnlineStatus::Online),
+        ResourceStatus::Down => ("🔴 {} offline", OnlineStatus::DoNotDisturb),
+        ResourceStatus::Unknown => ("🟡 {} status unknown", OnlineStatus::Idle),
+        ResourceStatus::Pending => ("⚪ {} awaiting check", OnlineStatus::Idle),
+        ResourceStatus::Inactive => ("💤 {} not monitored now", OnlineStatus::Idle),
+    };
+    (
+        ActivityData::custom(state.replace("{}", resource_name)),
+        online_status,
+    )
+}
+
+/// Resource name, which fits into embed titles and messages. Only a hand-edited config can have a longer one, so it is
+/// truncated instead of breaking every notification. It is warned about once, with the config's other problems
+pub fn fit_resource_name(resource_name: &str) -> String {
+    if resource_name.chars().count() <= MAX_RESOURCE_NAME_LENGTH {
+        return resource_name.to_string();
+    }
+    let mut truncated: String = resource_name
+        .chars()
+        .take(MAX_RESOURCE_NAME_LENGTH - 1)
+        .collect();
+    truncated.push('…');
+    truncated
+}
+
+/// Shows the current status in the presence of every shard of the running client
+pub async fn update_presence(data: &AppData) {
+    let Some(shard_manager) = data.shard_manager.read().await.clone() else {
+        return;
+    };
+    let status = *data.status.read().await;
+    let resource_name = fit_resource_name(&data.config.read().await.ping_config.resource_name);
+    let (activity, online_status) = presence(&resource_name, status);
+    for runner in shard_manager.runners.lock().await.values() {
+        runner
+            .runner_tx
+            .set_presence(Some(activity.clone()), online_status);
+    }
+}
+
+/// Shows Unknown status, which was kept from the servers during the startup grace, once the grace is over
+pub async fn end_startup_grace(data: Data, http: Arc<Http>) {
+    if !data.unknown_suppressed.load(Ordering::Relaxed)
+        || data.in_startup_grace(&*data.config.read().await)
+    {
+        return;
+    }
+    data.unknown_suppressed.store(false, Ordering::Relaxed);
+    if *data.status.read().await == ResourceStatus::Unknown {
+        log::warn!("Status is still unknown after the startup grace, showing it to the servers");
+        refresh_embeds(data, http).await;
+    }
+}
+
+/// Reminds that the status is still unknown every `unknown_reminder_interval`, until a definite status is established
+pub async fn remind_unknown(data: Data, http: Arc<Http>) {
+    let status = *data.status.read().await;
+    let mut last_reminder_lock = data.last_unknown_reminder.write().await;
+    if status != ResourceStatus::Unknown {
+        *last_reminder_lock = None;
+        return;
+    }
+    let config_lock = data.config.read().await;
+    let Some(interval) = config_lock.unknown_reminder_interval else {
+        return;
+    };
+    if data.unknown_suppressed.load(Ordering::Relaxed) {
+        return;
+    }
+    let now = Timestamp::now();
+    let unknown_since = *data.last_status_change.read().await;
+    if !unknown_reminder_due(now, unknown_since, *last_reminder_lock, interval) {
+        return;
+    }
+    *last_reminder_lock = Some(now);
+    drop(last_reminder_lock);
+
+    let unknown_for =
+        Duration::from_secs((now.unix_timestamp() - unknown_since.unix_timestamp()).max(0) as u64);
+    let message = format!(
+        "{} status is still unknown after {}: the bot can't check it yet. Still investigating...",
+        fit_resource_name(&config_lock.ping_config.resource_name),
+        humantime::format_duration(unknown_for)
+    );
+    let server_channels: Vec<_> = if config_lock.unknown_reminder_to_servers {
+        config_lock
+            .server_configs
+            .iter()
+            .filter(|(server_id, _)| {
+                config_lock.master_server != Some(**server_id)
+                    && config_lock.shows_unknown(**server_id)
+            })
+            .filter_map(|(server_id, server_config)| Some((*server_id, server_config.channel?)))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    drop(config_lock);
+    log::info!(target: EVENTS_TARGET, "Status is still unknown after {}", humantime::format_duration(unknown_for));
+
+    notify_master(&message, &data, http.clone()).await;
+    for (server_id, channel_id) in server_channels {
+        if let Err(err) = channel_id
+            .send_message(&http, CreateMessage::new().content(&message))
+            .await
+        {
+            log::error!(
+                "[server {}] Failed to send unknown status reminder: {}",
+                server_id,
+                err
+            );
+        }
+    }
+}
+
+/// Whether `interval` passed since the status became unknown or since the last reminder, whichever is later
+fn unknown_reminder_due(
+    now: Timestamp,
+    unknown_since: Timestamp,
+    last_reminder: Option<Timestamp>,
+    interval: Duration,
+) -> bool {
+    let reference = last_reminder.map_or(unknown_since, |last_reminder| {
+        last_reminder.max(unknown_since)
+    });
+    now.unix_timestamp() - reference.unix_timestamp() >= interval.as_secs() as i64
+}
+
+/// Reposts the current status embed in every server without a status change, so styling changes are visible right away
+pub async fn refresh_embeds(data: Data, http: Arc<Http>) {
+    rerender_embeds(data, http, EmbedRefresh::Repost).await;
+}
+
+/// Edits the status embeds in place every `LATENCY_REFRESH_INTERVAL`, while the resource is up, so the latency doesn't
+/// stay at its value from the last status change
+pub async fn refresh_latency(data: Data, http: Arc<Http>) {
+    if *data.status.read().await != ResourceStatus::Up || data.rtt().await.is_none() {
+        return;
+    }
+    let now = Timestamp::now();
+    let up_since = *data.last_status_change.read().await;
+    let mut last_refresh_lock = data.last_latency_refresh.write().await;
+    if !latency_refresh_due(now, up_since, *last_refresh_lock, LATENCY_REFRESH_INTERVAL) {
+        return;
+    }
+    *last_refresh_lock = Some(now);
+    drop(last_refresh_lock);
+
+    rerender_embeds(data, http, EmbedRefresh::EditLatency).await;
+}
+
+/// Whether the latency is due to be refreshed: the interval passed since the embed was posted or last refreshed
+fn latency_refresh_due(
+    now: Timestamp,
+    posted_at: Timestamp,
+    last_refresh: Option<Timestamp>,
+    interval: Duration,
+) -> bool {
+    let reference = last_refresh.map_or(posted_at, |last_refresh| last_refresh.max(posted_at));
+    now.unix_timestamp() - reference.unix_timestamp() >= interval.as_secs() as i64
+}
+
+/// The way status embeds are brought up to date without a status change
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EmbedRefresh {
+    /// The embed is posted again, like on a status change
+    Repost,
+    /// The posted embed is edited, so the channel isn't bumped. Servers without one are skipped
+    EditLatency,
+}
+
+async fn rerender_embeds(data: Data, http: Arc<Http>, refresh: EmbedRefresh) {
+    let status = *data.status.read().await;
+    let since = *data.last_status_change.read().await;
+    let config_lock = data.config.read().await;
+    let resource_name = fit_resource_name(&config_lock.ping_config.resource_name);
+    let addr = config_lock.ping_config.resource_addr.clone();
+    let history_limit = config_lock.history_limit();
+    let unknown_cause = *data.unknown_cause.read().await;
+    let linked_status = *data.linked_status.read().await;
+    let unreachable_families = data.unreachable_families().await;
+    let linked = config_lock
+        .ping_config
+        .linked_probe
+        .as_ref()
+        .map(|probe| (probe.name.as_str(), linked_status));
+    let values = EmbedValues {
+        resource_name: &resource_name,
+        addr: &addr,
+        status,
+        since,
+        linked,
+        unreachable_families: unreachable_families.as_deref(),
+        rtt: data.rtt().await,
+        service_down: data.service_down.load(Ordering::Relaxed),
+    };
+
+    let unknown_suppressed = data.in_startup_grace(&config_lock);
+    for (server_id, server_config) in &config_lock.server_configs {
+        if !server_config.embed_enabled
+            || (status == ResourceStatus::Unknown
+                && (unknown_suppressed || !config_lock.shows_unknown(*server_id)))
+        {
+            continue;
+        }
+        if refresh == EmbedRefresh::EditLatency {
+            let embed = server_embed(*server_id, server_config, &values, unknown_cause).await;
+            edit_embed(*server_id, embed, server_config, &data, &http).await;
+            continue;
+        }
+        let Some(channel) = notification_channel(*server_id, server_config, &http).await else {
+            continue;
+        };
+        let embed = server_embed(*server_id, server_config, &values, unknown_cause).await;
+        update_embed(
+            *server_id,
+            &embed,
+            server_config,
+            history_limit,
+            data.clone(),
+            channel,
+            http.clone(),
+        )
+        .await;
+    }
+
+    drop(config_lock);
+}
+
+/// Edits the posted status embed in place. Nothing is done, if there is no status message
+async fn edit_embed(
+    server_id: GuildId,
+    embed: CreateEmbed,
+    server_config: &ServerConfig,
+    data: &AppData,
+    http: &Http,
+) {
+    let mut messages_lock = data.used_messages.write().await;
+    let Some(server_messages) = messages_lock.get_mut(&server_id) else {
+        return;
+    };
+    let Some((channel_id, message_id)) = server_messages.status else {
+        return;
+    };
+    match channel_id
+        .edit_message(http, message_id, EditMessage::new().embed(embed))
+        .await
+    {
+        Ok(message) => {
+            log::debug!(
+                "[server {}] Refreshed latency in the status message",
+                server_id
+            );
+            server_messages.set_status(
+                (channel_id, message_id),
+                server_config
+                    .detect_manual_edits
+                    .then(|| message_hash(&message)),
+            );
+        }
+        Err(err) => {
+            log::warn!(
+                "[server {}] Failed to refresh latency in the status message: {}",
+                server_id,
+                err
+            );
+        }
+    }
+}
+
+/// Fetches the server's notification channel. Logs the reason, if there is none
+async fn notification_channel(
+    server_id: GuildId,
+    server_config: &ServerConfig,
+    http: &Http,
+) -> Option<Channel> {
+    let Some(channel_id) = server_config.channel else {
+        log::warn!(
+            "[server {}] No notification channel specified. Notification aborted.",
+            server_id
+        );
+        return None;
+    };
+    http.get_channel(channel_id)
+        .await
+        .inspect_err(|err| {
+            log::warn!(
+                "[server {}] Failed to fetch channel: {}. Notification aborted.",
+                server_id,
+                err
+            )
+        })
+        .ok()
+}
+
+/// Sends a plain message to the notification channel of the master server, if there is one
+pub async fn notify_master(message: &str, data: &AppData, http: Arc<Http>) {
+    let config_lock = data.config.read().await;
+    let Some(master_server) = config_lock.master_server else {
+        log::warn!("No master server specified. Master notification aborted.");
+        return;
+    };
+    let Some(channel_id) = config_lock
+        .server_configs
+        .get(&master_server)
+        .and_then(|server_config| server_config.channel)
+    else {
+        log::warn!(
+            "[server {}] No notification channel specified. Master notification aborted.",
+            master_server
+        );
+        return;
+    };
+    drop(config_lock);
+
+    if let Err(err) = channel_id
+        .send_message(http, CreateMessage::new().content(message))
+        .await
+    {
+        log::error!(
+            "[server {}] Failed to send master notification: {}",
+            master_server,
+            err
+        );
+    }
+}
+
 pub async fn update_embed(
     server_id: GuildId,
     embed: &CreateEmbed,
+    server_config: &ServerConfig,
+    history_limit: usize,
     data: Data,
     channel: Channel,
     http: Arc<Http>,
@@ -161,13 +830,36 @@ pub async fn update_embed(
     // let's just pray this staff will not cause any deadlocks
     log::trace!("Acquiring message_lock in update_embed...");
     let messages_lock = &mut data.used_messages.write().await;
-    let status_message = messages_lock.entry(server_id).or_default().status;
+    let server_messages = messages_lock.entry(server_id).or_default();
+    server_messages.retain_history(history_limit);
+    let ServerUsedMessages {
+        status: status_message,
+        status_hash,
+        ..
+    } = server_messages.clone();
 
     match status_message {
-        Some(id) => {
-            let message_result = http.get_message(channel.id(), id).await;
+        Some((channel_id, id)) => {
+            let message_result = http.get_message(channel_id, id).await;
             match message_result {
                 Ok(message) => {
+                    if server_config.detect_manual_edits
+                        && status_hash.is_some_and(|hash| hash != message_hash(&message))
+                    {
+                        log::warn!(
+                            "[server {}] Status message was edited by someone else. Reposting it...",
+                            server_id
+                        );
+                    }
+                    if message.pinned
+                        && let Err(err) = message.unpin(&http).await
+                    {
+                        log::warn!(
+                            "[server {}] Failed to unpin old status message: {}",
+                            server_id,
+                            describe_pin_error(&err)
+                        );
+                    }
                     let deletion_result = message.delete(http.clone()).await;
                     if let Err(err) = deletion_result {
                         log::error!(
@@ -181,23 +873,30 @@ pub async fn update_embed(
                     }
                     let send_result = channel
                         .id()
-                        .send_message(http, CreateMessage::new().embed(embed.clone()))
+                        .send_message(&http, CreateMessage::new().embed(embed.clone()))
                         .await;
                     match send_result {
                         Ok(message) => {
-                            messages_lock
-                                .insert(server_id, ServerUsedMessages::new(Some(message.id)));
+                            messages_lock.entry(server_id).or_default().set_status(
+                                (message.channel_id, message.id),
+                                server_config
+                                    .detect_manual_edits
+                                    .then(|| message_hash(&message)),
+                            );
                             log::info!(
                                 "[server {}] Sent new status message with id {}",
                                 server_id,
                                 message.id
                             );
+                            if server_config.pin_status_message {
+                                pin_status_message(server_id, &message, &http).await;
+                            }
                         }
                         Err(err) => {
                             log::error!(
                                 "[server {}] Failed to send new status message: {}",
                                 server_id,
-                                err
+                                describe_send_error(&err)
                             );
                         }
                     }
@@ -210,23 +909,30 @@ pub async fn update_embed(
                     );
                     let send_result = channel
                         .id()
-                        .send_message(http, CreateMessage::new().embed(embed.clone()))
+                        .send_message(&http, CreateMessage::new().embed(embed.clone()))
                         .await;
                     match send_result {
                         Ok(message) => {
-                            messages_lock
-                                .insert(server_id, ServerUsedMessages::new(Some(message.id)));
+                            messages_lock.entry(server_id).or_default().set_status(
+                                (message.channel_id, message.id),
+                                server_config
+                                    .detect_manual_edits
+                                    .then(|| message_hash(&message)),
+                            );
                             log::info!(
                                 "[server {}] Sent new status message with id {}",
                                 server_id,
                                 message.id
                             );
+                            if server_config.pin_status_message {
+                                pin_status_message(server_id, &message, &http).await;
+                            }
                         }
                         Err(err) => {
                             log::error!(
                                 "[server {}] Failed to send new status message: {}",
                                 server_id,
-                                err
+                                describe_send_error(&err)
                             );
                         }
                     }
@@ -237,22 +943,30 @@ pub async fn update_embed(
             log::info!("No status message detected. Creating new one...",);
             let send_result = channel
                 .id()
-                .send_message(http, CreateMessage::new().embed(embed.clone()))
+                .send_message(&http, CreateMessage::new().embed(embed.clone()))
                 .await;
             match send_result {
                 Ok(message) => {
-                    messages_lock.insert(server_id, ServerUsedMessages::new(Some(message.id)));
+                    messages_lock.entry(server_id).or_default().set_status(
+                        (message.channel_id, message.id),
+                        server_config
+                            .detect_manual_edits
+                            .then(|| message_hash(&message)),
+                    );
                     log::info!(
                         "[server {}] Sent new status message with id {}",
                         server_id,
                         message.id
                     );
+                    if server_config.pin_status_message {
+                        pin_status_message(server_id, &message, &http).await;
+                    }
                 }
                 Err(err) => {
                     log::error!(
                         "[server {}] Failed to send new status message: {}",
                         server_id,
-                        err
+                        describe_send_error(&err)
                     );
                 }
             }
@@ -260,50 +974,958 @@ pub async fn update_embed(
     }
 }
 
+/// Adds the sent up or down message to the server's notification history
+async fn remember_notification(
+    server_id: GuildId,
+    message: &Message,
+    mentioned: bool,
+    limit: usize,
+    data: &AppData,
+) {
+    let mut messages_lock = data.used_messages.write().await;
+    let server_messages = messages_lock.entry(server_id).or_default();
+    if mentioned {
+        server_messages.set_last_mention(message.timestamp);
+    }
+    server_messages.push_notification(
+        SentNotification {
+            channel: message.channel_id,
+            message: message.id,
+            sent_at: message.timestamp,
+        },
+        limit,
+    );
+}
+
+/// Deletes the latest remembered up/down message, before the new one is remembered, so only the latest one is kept
+async fn delete_previous_notification(server_id: GuildId, data: &AppData, http: &Http) {
+    let previous = data
+        .used_messages
+        .write()
+        .await
+        .get_mut(&server_id)
+        .and_then(ServerUsedMessages::pop_notification);
+    let Some(previous) = previous else {
+        return;
+    };
+    match http
+        .delete_message(previous.channel, previous.message, None)
+        .await
+    {
+        Ok(()) => log::info!(
+            "[server {}] Deleted previous notification message {}",
+            server_id,
+            previous.message
+        ),
+        Err(err) => log::warn!(
+            "[server {}] Failed to delete previous notification message {}: {}",
+            server_id,
+            previous.message,
+            err
+        ),
+    }
+}
+
+/// Adds a remediation hint to errors caused by missing permissions in the channel, since they can only be fixed by
+/// the server's moderators
+fn describe_send_error(err: &serenity::Error) -> String {
+    match err {
+        serenity::Error::Http(http_err)
+            if http_err.status_code() == Some(StatusCode::FORBIDDEN) =>
+        {
+            format!(
+                "{} (the bot can't post in the notification channel, grant it Send Messages and Embed Links there)",
+                err
+            )
+        }
+        _ => err.to_string(),
+    }
+}
+
+/// Pins the new status message. Failures are only logged, since the message itself is already posted
+async fn pin_status_message(server_id: GuildId, message: &Message, http: &Http) {
+    match message.pin(http).await {
+        Ok(()) => log::info!("[server {}] Pinned status message", server_id),
+        Err(err) => log::warn!(
+            "[server {}] Failed to pin status message: {}",
+            server_id,
+            describe_pin_error(&err)
+        ),
+    }
+}
+
+/// Adds a remediation hint to errors of pinning, which can only be fixed by the server's moderators
+fn describe_pin_error(err: &serenity::Error) -> String {
+    match err {
+        serenity::Error::Http(HttpError::UnsuccessfulRequest(response))
+            if response.error.code == MAX_PINS_REACHED_CODE =>
+        {
+            format!(
+                "{} (the notification channel has too many pinned messages, unpin some of them)",
+                err
+            )
+        }
+        serenity::Error::Http(http_err)
+            if http_err.status_code() == Some(StatusCode::FORBIDDEN) =>
+        {
+            format!(
+                "{} (the bot can't pin in the notification channel, grant it Manage Messages there)",
+                err
+            )
+        }
+        _ => err.to_string(),
+    }
+}
+
+/// Adds configured reactions to the notification message. Stops at the first failure, since the rest will most likely
+/// fail the same way because of missing Add Reactions permission
+async fn add_reactions(server_id: GuildId, message: &Message, reactions: &[String], http: &Http) {
+    for reaction in reactions {
+        let reaction_type = match ReactionType::try_from(reaction.as_str()) {
+            Ok(reaction_type) => reaction_type,
+            Err(err) => {
+                log::warn!(
+                    "[server {}] Skipped invalid reaction {}: {}",
+                    server_id,
+                    reaction,
+                    err
+                );
+                continue;
+            }
+        };
+        if let Err(err) = message.react(http, reaction_type).await {
+            log::warn!(
+                "[server {}] Failed to add reaction {} to message {}: {}. Does the bot have Add Reactions permission?",
+                server_id,
+                reaction,
+                message.id,
+                err
+            );
+            return;
+        }
+    }
+}
+
+/// Fingerprint of the message's embeds. It is kept in saved data, so it must not depend on the std hasher,
+/// which may change between Rust releases
+fn message_hash(message: &Message) -> String {
+    let content = message
+        .embeds
+        .iter()
+        .map(|embed| {
+            format!(
+                "{:?}|{:?}|{:?}|{:?}",
+                embed.title,
+                embed.description,
+                embed.colour.map(|colour| colour.0),
+                embed
+                    .fields
+                    .iter()
+                    .map(|field| (&field.name, &field.value, field.inline))
+                    .collect::<Vec<_>>()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    // 64-bit FNV-1a
+    let hash = content.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Embed from the server's template, if it has a valid one. Otherwise the default embed with the server's labels
+async fn server_embed(
+    server_id: GuildId,
+    server_config: &ServerConfig,
+    values: &EmbedValues<'_>,
+    unknown_cause: UnknownCause,
+) -> CreateEmbed {
+    let template_embed = match &server_config.embed_template {
+        Some(template_path) => render_embed_template(server_id, template_path, values).await,
+        None => None,
+    };
+    template_embed.unwrap_or_else(|| {
+        let embed = generate_embed(
+            values.resource_name,
+            values.status,
+            values.addr.to_string(),
+            values.since,
+            (&server_config.since_label, &server_config.address_label),
+            unknown_cause,
+            values.linked,
+        );
+        let embed = match values.unreachable_families {
+            Some(families) if values.status == ResourceStatus::Up => embed.field(
+                "Warning",
+                format!(
+                    "{} unreachable, only the other address family works",
+                    families
+                ),
+                false,
+            ),
+            _ => embed,
+        };
+        let embed = match values.rtt {
+            Some(rtt) if values.status == ResourceStatus::Up => embed.field(
+                &server_config.latency_label,
+                format!("~{} ms", rtt.as_millis()),
+                false,
+            ),
+            _ => embed,
+        };
+        let embed = if values.service_down && values.status == ResourceStatus::Down {
+            embed.field(
+                "Failed check",
+                "TCP: the host responds to ping, but its service doesn't accept connections",
+                false,
+            )
+        } else {
+            embed
+        };
+        if !server_config.inverted {
+            return embed;
+        }
+        match values.status {
+            ResourceStatus::Up => embed.colour(DOWN_COLOUR),
+            ResourceStatus::Down => embed.colour(UP_COLOUR),
+            _ => embed,
+        }
+    })
+}
+
+/// Status embeds of the server for the resource being up and down, as they would be posted
+pub async fn preview_embeds(
+    server_id: GuildId,
+    server_config: &ServerConfig,
+    resource_name: &str,
+    addr: &str,
+) -> Vec<CreateEmbed> {
+    let resource_name = &fit_resource_name(resource_name);
+    let mut embeds = Vec::new();
+    for status in [ResourceStatus::Up, ResourceStatus::Down] {
+        let values = EmbedValues {
+            resource_name,
+            addr,
+            status,
+            since: Timestamp::now(),
+            linked: None,
+            unreachable_families: None,
+            rtt: None,
+            service_down: false,
+        };
+        embeds.push(server_embed(server_id, server_config, &values, UnknownCause::default()).await);
+    }
+    embeds
+}
+
+/// Problems, which would keep the server from being notified, found without posting anything
+pub async fn preflight_problems(
+    server_id: GuildId,
+    server_config: &ServerConfig,
+    resource_name: &str,
+    bot_id: UserId,
+    http: &Http,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    let missing_setup = server_config.missing_setup();
+    if !missing_setup.is_empty() {
+        problems.push(format!("{} not set", missing_setup.join(" and ")));
+    }
+    if let Some(template_path) = &server_config.embed_template
+        && let Err(err) = EmbedTemplate::load_from_file(template_path).await
+    {
+        problems.push(format!("{}, the default embed is used instead", err));
+    }
+    for kind in TransitionKind::ALL {
+        let length = worst_case_message_length(server_config.message(kind), resource_name);
+        if length > DISCORD_MESSAGE_LIMIT {
+            problems.push(format!(
+                "{} message can take {} characters",
+                kind.name(),
+                length
+            ));
+        }
+    }
+
+    let Some(channel_id) = server_config.channel else {
+        return problems;
+    };
+    let channel = match http.get_channel(channel_id).await {
+        Ok(Channel::Guild(channel)) => channel,
+        Ok(_) => {
+            problems.push("notification channel is not a server channel".to_string());
+            return problems;
+        }
+        Err(err) => {
+            problems.push(format!("failed to fetch notification channel: {}", err));
+            return problems;
+        }
+    };
+    let (server, member) = match tokio::try_join!(
+        http.get_guild(server_id),
+        http.get_member(server_id, bot_id)
+    ) {
+        Ok(server_and_member) => server_and_member,
+        Err(err) => {
+            problems.push(format!("failed to fetch the bot's permissions: {}", err));
+            return problems;
+        }
+    };
+    let permissions = server.user_permissions_in(&channel, &member);
+    let missing = missing_permissions(server_config, permissions);
+    if !missing.is_empty() {
+        problems.push(format!(
+            "bot lacks {} in <#{}>",
+            missing.join(", "),
+            channel_id
+        ));
+    }
+    if let Some(role_id) = server_config.role_to_notify {
+        match server.roles.get(&role_id) {
+            None => problems.push(format!("role {} doesn't exist anymore", role_id)),
+            Some(role) if !role.mentionable && !permissions.mention_everyone() => {
+                problems.push(format!(
+                    "<@&{}> is not mentionable and the bot lacks Mention Everyone, so nobody is pinged",
+                    role_id
+                ))
+            }
+            Some(_) => {}
+        }
+    }
+    problems
+}
+
+/// Names of permissions in the notification channel, which the server's settings need, but the bot lacks
+fn missing_permissions(
+    server_config: &ServerConfig,
+    permissions: Permissions,
+) -> Vec<&'static str> {
+    let mut required = vec![
+        (Permissions::VIEW_CHANNEL, "View Channel"),
+        (Permissions::SEND_MESSAGES, "Send Messages"),
+    ];
+    if server_config.embed_enabled {
+        // The old status message is fetched before it's replaced
+        required.push((Permissions::EMBED_LINKS, "Embed Links"));
+        required.push((Permissions::READ_MESSAGE_HISTORY, "Read Message History"));
+    }
+    if !server_config.up_reactions.is_empty() || !server_config.down_reactions.is_empty() {
+        required.push((Permissions::ADD_REACTIONS, "Add Reactions"));
+    }
+    if server_config.pin_status_message {
+        required.push((Permissions::MANAGE_MESSAGES, "Manage Messages"));
+    }
+    required
+        .into_iter()
+        .filter(|(permission, _)| !permissions.contains(*permission))
+        .map(|(_, name)| name)
+        .collect()
+}
+
+/// Renders server's custom embed template. `None` if it is broken or doesn't define the status
+async fn render_embed_template(
+    server_id: GuildId,
+    template_path: &Path,
+    values: &EmbedValues<'_>,
+) -> Option<CreateEmbed> {
+    match EmbedTemplate::load_from_file(&template_path).await {
+        Ok(template) => template.render(values),
+        Err(err) => {
+            log::warn!(
+                "[server {}] Failed to load embed template, using default embed: {}",
+                server_id,
+                err
+            );
+            None
+        }
+    }
+}
+
 pub fn generate_embed(
     resource_name: &str,
     new_status: ResourceStatus,
     addr: String,
     last_status_change: Timestamp,
+    (since_label, address_label): (&str, &str),
+    unknown_cause: UnknownCause,
+    linked: Option<(&str, ResourceStatus)>,
 ) -> CreateEmbed {
     let mut new_embed = CreateEmbed::new();
     match new_status {
         ResourceStatus::Up => {
             new_embed = new_embed
-                .colour((21, 250, 59))
+                .colour(UP_COLOUR)
                 .title(format!("{} is online!", resource_name));
         }
         ResourceStatus::Down => {
             new_embed = new_embed
-                .colour((220, 23, 30))
+                .colour(DOWN_COLOUR)
                 .title(format!("{} is offline!", resource_name));
         }
         ResourceStatus::Unknown => {
             new_embed = new_embed
                 .colour((215, 187, 10))
                 .title(format!("{} status is unknown...", resource_name))
-                .description("Some kind of error occured. Notify maintainers!");
+                .description(match unknown_cause {
+                    UnknownCause::Unreachable => {
+                        "Can't reach the resource because of network or DNS errors. Notify maintainers!"
+                    }
+                    UnknownCause::NotPermitted => {
+                        "The bot's host doesn't permit sending checks. Notify the bot's hoster!"
+                    }
+                });
+        }
+        ResourceStatus::Pending => {
+            new_embed = new_embed
+                .colour((128, 128, 128))
+                .title(format!("{} is awaiting the first check...", resource_name))
+                .description("The bot has just started and hasn't checked the resource yet.");
+        }
+        ResourceStatus::Inactive => {
+            new_embed = new_embed
+                .colour((128, 128, 128))
+                .title(format!("{} is not monitored right now", resource_name))
+                .description("The resource is only checked during its active hours.");
         }
     };
     new_embed = new_embed.fields(vec![
-        (
-            "Since",
-            format!("<t:{}:R>", last_status_change.unix_timestamp()),
-            false,
-        ),
-        ("Address", addr, false),
+        (since_label, since_value(last_status_change), false),
+        (address_label, addr, false),
     ]);
+    if let Some((linked_name, linked_status)) = linked {
+        new_embed = new_embed.footer(CreateEmbedFooter::new(combined_status(
+            resource_name,
+            new_status,
+            linked_name,
+            linked_status,
+        )));
+    }
     new_embed
 }
 
-fn replace_templates(message: &str, resource_name: &str, role_id: &Option<RoleId>) -> String {
-    let role_ping = match role_id {
-        Some(id) => {
+/// Relative time of the last status change. A status, which never changed, has no meaningful timestamp yet
+pub fn since_value(last_status_change: Timestamp) -> String {
+    if last_status_change == Timestamp::default() {
+        UNKNOWN_SINCE_VALUE.to_string()
+    } else {
+        format!("<t:{}:R>", last_status_change.unix_timestamp())
+    }
+}
+
+/// Short summary of both the resource and the linked probe, e.g. "BYOND hub up, Paradise down"
+fn combined_status(
+    resource_name: &str,
+    status: ResourceStatus,
+    linked_name: &str,
+    linked_status: ResourceStatus,
+) -> String {
+    format!(
+        "{} {}, {} {}",
+        linked_name,
+        linked_status.to_string().to_lowercase(),
+        resource_name,
+        status.to_string().to_lowercase()
+    )
+}
+
+fn replace_templates(message: &str, values: &TemplateValues) -> String {
+    let role_ping = match values.role_id {
+        Some(id) if values.mention => {
             format!("<@&{}>", id)
         }
-        None => ROLE_FALLBACK_STRING.to_string(),
+        _ => ROLE_FALLBACK_STRING.to_string(),
+    };
+    let downtime = match values.downtime {
+        Some(downtime) => humantime::format_duration(downtime).to_string(),
+        None => DOWNTIME_FALLBACK_STRING.to_string(),
     };
     message
-        .replace(TEMPLATE_RESOURCE_NAME, resource_name)
+        .replace(TEMPLATE_RESOURCE_NAME, values.resource_name)
         .replace(TEMPLATE_ROLE_PING, role_ping.as_str())
+        .replace(TEMPLATE_DOWNTIME, downtime.as_str())
+}
+
+/// Length of the message in characters, when its templates are replaced with the longest possible values
+pub fn worst_case_message_length(message: &str, resource_name: &str) -> usize {
+    replace_templates(
+        message,
+        &TemplateValues {
+            resource_name,
+            role_id: Some(RoleId::new(u64::MAX)),
+            mention: true,
+            downtime: Some(WORST_CASE_DOWNTIME),
+        },
+    )
+    .chars()
+    .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::Ordering,
+        time::{Duration, Instant},
+    };
+
+    use crate::{
+        AppData, Config, DEFAULT_ATTEMPTS_BEFORE_NOTIFICATION, LinkedProbe,
+        MAX_RESOURCE_NAME_LENGTH, ResourceStatus, SavedData, ServerConfig, TransitionKind,
+        status::{
+            StatusChange, UNKNOWN_SINCE_VALUE, WORST_CASE_DOWNTIME, apply_linked_tick, apply_tick,
+            combined_status, fit_resource_name, latency_refresh_due, missing_permissions, presence,
+            quorum_verdict, since_value, tick_verdict, unknown_reminder_due,
+            worst_case_message_length,
+        },
+    };
+    use poise::serenity_prelude::{OnlineStatus, Permissions, Timestamp};
+
+    #[test]
+    fn required_permissions_follow_settings() {
+        let mut server_config = ServerConfig::default();
+        let posting = Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES;
+        assert_eq!(
+            missing_permissions(&server_config, posting),
+            vec!["Embed Links", "Read Message History"]
+        );
+
+        server_config.embed_enabled = false;
+        assert!(missing_permissions(&server_config, posting).is_empty());
+
+        server_config.up_reactions.push("✅".to_string());
+        server_config.pin_status_message = true;
+        assert_eq!(
+            missing_permissions(&server_config, posting),
+            vec!["Add Reactions", "Manage Messages"]
+        );
+        assert!(missing_permissions(&server_config, Permissions::all()).is_empty());
+    }
+
+    #[test]
+    fn split_tick_has_no_verdict() {
+        let results = [
+            ResourceStatus::Up,
+            ResourceStatus::Down,
+            ResourceStatus::Down,
+            ResourceStatus::Up,
+        ];
+
+        assert_eq!(tick_verdict(&results), None);
+    }
+
+    #[test]
+    fn errors_do_not_outvote_definite_results() {
+        let results = [
+            ResourceStatus::Unknown,
+            ResourceStatus::Unknown,
+            ResourceStatus::Down,
+        ];
+
+        assert_eq!(tick_verdict(&results), Some(ResourceStatus::Down));
+        assert_eq!(
+            tick_verdict(&[ResourceStatus::Unknown]),
+            Some(ResourceStatus::Unknown)
+        );
+    }
+
+    #[test]
+    fn quorum_of_failed_probes_is_required_for_down() {
+        let one_failed = [ResourceStatus::Up, ResourceStatus::Down, ResourceStatus::Up];
+        let two_failed = [
+            ResourceStatus::Down,
+            ResourceStatus::Up,
+            ResourceStatus::Down,
+        ];
+
+        assert_eq!(
+            quorum_verdict(&one_failed, Some(2)),
+            Some(ResourceStatus::Up)
+        );
+        assert_eq!(
+            quorum_verdict(&two_failed, Some(2)),
+            Some(ResourceStatus::Down)
+        );
+        // Majority would say down here
+        assert_eq!(
+            quorum_verdict(&two_failed, Some(3)),
+            Some(ResourceStatus::Up)
+        );
+    }
+
+    #[test]
+    fn quorum_without_successes_is_unknown() {
+        let results = [
+            ResourceStatus::Down,
+            ResourceStatus::Unknown,
+            ResourceStatus::Unknown,
+        ];
+
+        assert_eq!(
+            quorum_verdict(&results, Some(2)),
+            Some(ResourceStatus::Unknown)
+        );
+        assert_eq!(quorum_verdict(&results, None), Some(ResourceStatus::Down));
+    }
+
+    #[tokio::test]
+    async fn split_tick_keeps_counted_attempts() {
+        let data = AppData::default();
+        *data.status.write().await = ResourceStatus::Down;
+        data.config
+            .write()
+            .await
+            .ping_config
+            .required_attempts_before_notification = 2;
+        data.config.write().await.ping_config.min_stable_duration = Duration::from_secs(20);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(
+            apply_tick(Some(ResourceStatus::Up), &data, at(0)).await,
+            None
+        );
+        let verdict = tick_verdict(&[ResourceStatus::Up, ResourceStatus::Down]);
+        let transition = apply_tick(verdict, &data, at(10)).await;
+
+        assert_eq!(transition, None);
+        assert_eq!(*data.status.read().await, ResourceStatus::Down);
+        assert_eq!(data.attempts_before_notification.load(Ordering::Relaxed), 1);
+
+        // Neither the counted attempt nor the time responding is lost to the split tick
+        assert_eq!(
+            apply_tick(Some(ResourceStatus::Up), &data, at(15)).await,
+            None
+        );
+        let transition = apply_tick(Some(ResourceStatus::Up), &data, at(20)).await;
+        assert!(transition.is_some_and(|change| change.new_status == ResourceStatus::Up));
+    }
+
+    #[tokio::test]
+    async fn first_check_leaves_pending_right_away() {
+        let data = AppData::default();
+        assert_eq!(*data.status.read().await, ResourceStatus::Pending);
+
+        let transition = apply_tick(Some(ResourceStatus::Up), &data, Instant::now()).await;
+
+        assert!(
+            transition.is_some_and(|change| change.old_status == ResourceStatus::Pending
+                && change.new_status == ResourceStatus::Up)
+        );
+        assert_eq!(*data.status.read().await, ResourceStatus::Up);
+        assert_eq!(data.attempts_before_notification.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn first_success_leaves_unknown_right_away() {
+        let data = AppData::default();
+        *data.status.write().await = ResourceStatus::Unknown;
+        data.config
+            .write()
+            .await
+            .ping_config
+            .required_attempts_before_notification = 3;
+        data.config.write().await.ping_config.min_stable_duration = Duration::from_secs(60);
+
+        let transition = apply_tick(Some(ResourceStatus::Up), &data, Instant::now()).await;
+
+        assert!(
+            transition.is_some_and(|change| change.old_status == ResourceStatus::Unknown
+                && change.new_status == ResourceStatus::Up)
+        );
+        assert_eq!(*data.status.read().await, ResourceStatus::Up);
+        assert_eq!(data.attempts_before_notification.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn attempts_keep_counting_across_restart() {
+        let data = AppData::default();
+        *data.status.write().await = ResourceStatus::Up;
+        data.config
+            .write()
+            .await
+            .ping_config
+            .required_attempts_before_notification = 3;
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert_eq!(
+                apply_tick(Some(ResourceStatus::Down), &data, now).await,
+                None
+            );
+        }
+
+        let restarted = AppData::default();
+        SavedData::load_from(&data)
+            .await
+            .load_into(&restarted)
+            .await;
+        assert_eq!(
+            restarted
+                .attempts_before_notification
+                .load(Ordering::Relaxed),
+            3
+        );
+
+        let transition = apply_tick(Some(ResourceStatus::Down), &restarted, now).await;
+        assert!(transition.is_some_and(|change| change.new_status == ResourceStatus::Down));
+        assert_eq!(
+            restarted
+                .attempts_before_notification
+                .load(Ordering::Relaxed),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn linked_probe_flips_are_confirmed() {
+        let data = AppData::default();
+        data.config.write().await.ping_config.linked_probe = Some(LinkedProbe::default());
+
+        assert!(apply_linked_tick(ResourceStatus::Up, &data).await);
+        for _ in 0..DEFAULT_ATTEMPTS_BEFORE_NOTIFICATION {
+            assert!(!apply_linked_tick(ResourceStatus::Down, &data).await);
+        }
+        // A single agreeing check starts confirming over
+        assert!(!apply_linked_tick(ResourceStatus::Up, &data).await);
+        assert!(!apply_linked_tick(ResourceStatus::Down, &data).await);
+        assert_eq!(*data.linked_status.read().await, ResourceStatus::Up);
+
+        for _ in 1..DEFAULT_ATTEMPTS_BEFORE_NOTIFICATION {
+            assert!(!apply_linked_tick(ResourceStatus::Down, &data).await);
+        }
+        assert!(apply_linked_tick(ResourceStatus::Down, &data).await);
+        assert_eq!(*data.linked_status.read().await, ResourceStatus::Down);
+    }
+
+    #[tokio::test]
+    async fn counted_attempts_saturate() {
+        let data = AppData::default();
+        *data.status.write().await = ResourceStatus::Down;
+        data.attempts_before_notification
+            .store(u8::MAX, Ordering::Relaxed);
+        // Up is counted, but waits for the resource to be stable
+        data.config.write().await.ping_config.min_stable_duration = Duration::from_secs(60);
+
+        assert_eq!(
+            apply_tick(Some(ResourceStatus::Up), &data, Instant::now()).await,
+            None
+        );
+        assert_eq!(
+            data.attempts_before_notification.load(Ordering::Relaxed),
+            u8::MAX
+        );
+    }
+
+    #[tokio::test]
+    async fn change_is_not_detected_again_after_restart() {
+        let data = AppData::default();
+        *data.status.write().await = ResourceStatus::Down;
+        data.config
+            .write()
+            .await
+            .ping_config
+            .required_attempts_before_notification = 0;
+        let now = Instant::now();
+        let change = apply_tick(Some(ResourceStatus::Up), &data, now).await;
+        assert!(change.is_some_and(|change| change.new_status == ResourceStatus::Up));
+
+        // Crashed after saving, before or during the announcement
+        let restarted = AppData::default();
+        SavedData::load_from(&data)
+            .await
+            .load_into(&restarted)
+            .await;
+        assert_eq!(
+            apply_tick(Some(ResourceStatus::Up), &restarted, now).await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_checks_count_only_when_spaced_apart() {
+        let data = AppData::default();
+        *data.status.write().await = ResourceStatus::Up;
+        data.config
+            .write()
+            .await
+            .ping_config
+            .required_attempts_before_notification = 1;
+        data.config.write().await.ping_config.down_check_spacing = Duration::from_secs(30);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        // Burst of loss counts once
+        for secs in [0, 10, 20] {
+            assert_eq!(
+                apply_tick(Some(ResourceStatus::Down), &data, at(secs)).await,
+                None
+            );
+        }
+        assert_eq!(*data.status.read().await, ResourceStatus::Up);
+
+        let transition = apply_tick(Some(ResourceStatus::Down), &data, at(30)).await;
+        assert!(transition.is_some_and(|change| change.new_status == ResourceStatus::Down));
+    }
+
+    #[tokio::test]
+    async fn flapping_recovery_waits_for_stability() {
+        let data = AppData::default();
+        *data.status.write().await = ResourceStatus::Down;
+        data.config.write().await.ping_config.min_stable_duration = Duration::from_secs(60);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        // Comes back, but drops again during restart
+        for secs in [0, 10] {
+            assert_eq!(
+                apply_tick(Some(ResourceStatus::Up), &data, at(secs)).await,
+                None
+            );
+        }
+        assert_eq!(
+            apply_tick(Some(ResourceStatus::Down), &data, at(20)).await,
+            None
+        );
+
+        // Enough attempts pass long before the resource is stable
+        for secs in (30..90).step_by(10) {
+            assert_eq!(
+                apply_tick(Some(ResourceStatus::Up), &data, at(secs)).await,
+                None
+            );
+        }
+        assert_eq!(*data.status.read().await, ResourceStatus::Down);
+
+        let transition = apply_tick(Some(ResourceStatus::Up), &data, at(90)).await;
+        assert!(transition.is_some_and(|change| change.new_status == ResourceStatus::Up));
+        assert_eq!(*data.status.read().await, ResourceStatus::Up);
+    }
+
+    #[test]
+    fn worst_case_length_uses_longest_values() {
+        assert_eq!(worst_case_message_length("%%ROLE%%", "BYOND"), 24);
+        assert_eq!(worst_case_message_length("%%RESOURCE%% is up", "BYOND"), 11);
+
+        let downtime = humantime::format_duration(WORST_CASE_DOWNTIME).to_string();
+        assert_eq!(
+            worst_case_message_length("%%DOWNTIME%%", "BYOND"),
+            downtime.chars().count()
+        );
+        for unit in ["years", "months", "days", "h", "m", "s"] {
+            assert!(downtime.contains(unit), "{} has no {}", downtime, unit);
+        }
+    }
+
+    #[test]
+    fn combined_status_names_both_resources() {
+        assert_eq!(
+            combined_status(
+                "Paradise",
+                ResourceStatus::Down,
+                "BYOND hub",
+                ResourceStatus::Up
+            ),
+            "BYOND hub up, Paradise down"
+        );
+    }
+
+    #[test]
+    fn unknown_reminders_are_spaced() {
+        let at = |secs| Timestamp::from_unix_timestamp(secs).unwrap();
+        let interval = Duration::from_secs(600);
+
+        assert!(!unknown_reminder_due(at(599), at(0), None, interval));
+        assert!(unknown_reminder_due(at(600), at(0), None, interval));
+        assert!(!unknown_reminder_due(
+            at(900),
+            at(0),
+            Some(at(600)),
+            interval
+        ));
+        assert!(unknown_reminder_due(
+            at(1200),
+            at(0),
+            Some(at(600)),
+            interval
+        ));
+        // Reminder of an earlier unknown period doesn't count
+        assert!(!unknown_reminder_due(
+            at(2100),
+            at(2000),
+            Some(at(600)),
+            interval
+        ));
+    }
+
+    #[test]
+    fn latency_is_refreshed_once_per_interval_since_posting() {
+        let at = |secs| Timestamp::from_unix_timestamp(secs).unwrap();
+        let interval = Duration::from_secs(300);
+
+        // The embed was just posted with the status change
+        assert!(!latency_refresh_due(at(1200), at(1000), None, interval));
+        assert!(latency_refresh_due(at(1300), at(1000), None, interval));
+        assert!(!latency_refresh_due(
+            at(1500),
+            at(1000),
+            Some(at(1300)),
+            interval
+        ));
+        // Refresh before the last status change doesn't count
+        assert!(!latency_refresh_due(
+            at(1200),
+            at(1000),
+            Some(at(900)),
+            interval
+        ));
+    }
+
+    #[test]
+    fn long_resource_name_is_truncated() {
+        assert_eq!(fit_resource_name("BYOND"), "BYOND");
+
+        let name = "A very long resource name, edited by hand";
+        let fitted = fit_resource_name(name);
+        assert_eq!(fitted.chars().count(), MAX_RESOURCE_NAME_LENGTH);
+        assert_eq!(fitted, "A very long resource nam…");
+    }
+
+    #[test]
+    fn unchanged_status_has_unknown_since() {
+        assert_eq!(since_value(Timestamp::default()), UNKNOWN_SINCE_VALUE);
+        let changed = Timestamp::from_unix_timestamp(1_700_000_000).unwrap();
+        assert_eq!(since_value(changed), "<t:1700000000:R>");
+    }
+
+    #[test]
+    fn presence_names_resource_and_status() {
+        let (activity, online_status) = presence("BYOND", ResourceStatus::Down);
+
+        assert_eq!(activity.state.as_deref(), Some("🔴 BYOND offline"));
+        assert_eq!(online_status, OnlineStatus::DoNotDisturb);
+    }
+
+    #[test]
+    fn going_down_as_active_hours_begin_is_announced() {
+        let change = |old_status| StatusChange {
+            old_status,
+            new_status: ResourceStatus::Down,
+            old_status_since: Timestamp::from_unix_timestamp(0).unwrap(),
+            new_status_since: Timestamp::from_unix_timestamp(100).unwrap(),
+        };
+        let config = Config::default();
+
+        assert_eq!(
+            change(ResourceStatus::Inactive).transition_kind(&config),
+            Some(TransitionKind::Down)
+        );
+        assert_eq!(
+            change(ResourceStatus::Unknown).transition_kind(&config),
+            None
+        );
+        let back_up = StatusChange {
+            new_status: ResourceStatus::Up,
+            ..change(ResourceStatus::Inactive)
+        };
+        assert_eq!(back_up.transition_kind(&config), None);
+    }
 }
@@ -0,0 +1,130 @@
+use std::{path::Path, time::Duration};
+
+use poise::serenity_prelude::{CreateEmbed, Timestamp};
+use serde::{Deserialize, Serialize};
+
+use crate::ResourceStatus;
+
+const TEMPLATE_RESOURCE_NAME: &str = "%%RESOURCE%%";
+const TEMPLATE_ADDRESS: &str = "%%ADDRESS%%";
+const TEMPLATE_STATUS: &str = "%%STATUS%%";
+const TEMPLATE_SINCE: &str = "%%SINCE%%";
+const TEMPLATE_LINKED_NAME: &str = "%%LINKED%%";
+const TEMPLATE_LINKED_STATUS: &str = "%%LINKED_STATUS%%";
+const TEMPLATE_RTT: &str = "%%RTT%%";
+
+/// Layout of the status embed, loaded from a file referenced by server's `embed_template`.
+/// Statuses without a style use the default embed.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct EmbedTemplate {
+    up: Option<EmbedStyle>,
+    down: Option<EmbedStyle>,
+    unknown: Option<EmbedStyle>,
+    pending: Option<EmbedStyle>,
+    inactive: Option<EmbedStyle>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct EmbedStyle {
+    title: Option<String>,
+    description: Option<String>,
+    colour: Option<(u8, u8, u8)>,
+    fields: Vec<EmbedFieldTemplate>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EmbedFieldTemplate {
+    name: String,
+    value: String,
+    #[serde(default)]
+    inline: bool,
+}
+
+/// Values substituted into embed templates
+pub struct EmbedValues<'a> {
+    pub resource_name: &'a str,
+    pub addr: &'a str,
+    pub status: ResourceStatus,
+    pub since: Timestamp,
+    /// Name and status of the linked probe, if there is one
+    pub linked: Option<(&'a str, ResourceStatus)>,
+    /// Address families, which don't work while the other one does, e.g. "IPv6"
+    pub unreachable_families: Option<&'a str>,
+    /// Smoothed round-trip time of the resource, if it is known
+    pub rtt: Option<Duration>,
+    /// Whether only the resource's service is down, while its host responds
+    pub service_down: bool,
+}
+
+impl EmbedTemplate {
+    pub async fn load_from_file<T: AsRef<Path>>(template_path: &T) -> anyhow::Result<Self> {
+        let template_string = tokio::fs::read_to_string(template_path)
+            .await
+            .map_err(|err| {
+                anyhow::Error::msg(format!(
+                    "Failed to open {}: {}",
+                    template_path.as_ref().to_string_lossy(),
+                    err
+                ))
+            })?;
+        toml::from_str::<EmbedTemplate>(&template_string).map_err(|err| {
+            anyhow::Error::msg(format!(
+                "Malformed embed template in {}: {}",
+                template_path.as_ref().to_string_lossy(),
+                err
+            ))
+        })
+    }
+
+    /// Renders the style for the status in `values`. `None` if the template doesn't define one
+    pub fn render(&self, values: &EmbedValues) -> Option<CreateEmbed> {
+        let style = match values.status {
+            ResourceStatus::Up => self.up.as_ref(),
+            ResourceStatus::Down => self.down.as_ref(),
+            ResourceStatus::Unknown => self.unknown.as_ref(),
+            ResourceStatus::Pending => self.pending.as_ref(),
+            ResourceStatus::Inactive => self.inactive.as_ref(),
+        }?;
+
+        let mut embed = CreateEmbed::new();
+        if let Some(title) = &style.title {
+            embed = embed.title(replace_templates(title, values));
+        }
+        if let Some(description) = &style.description {
+            embed = embed.description(replace_templates(description, values));
+        }
+        if let Some(colour) = style.colour {
+            embed = embed.colour(colour);
+        }
+        embed = embed.fields(style.fields.iter().map(|field| {
+            (
+                replace_templates(&field.name, values),
+                replace_templates(&field.value, values),
+                field.inline,
+            )
+        }));
+        Some(embed)
+    }
+}
+
+fn replace_templates(text: &str, values: &EmbedValues) -> String {
+    let (linked_name, linked_status) = match values.linked {
+        Some((name, status)) => (name, status.to_string()),
+        None => ("", String::new()),
+    };
+    let rtt = values.rtt.map_or("unknown".to_string(), |rtt| {
+        format!("{} ms", rtt.as_millis())
+    });
+    text.replace(TEMPLATE_LINKED_STATUS, &linked_status)
+        .replace(TEMPLATE_RTT, &rtt)
+        .replace(TEMPLATE_LINKED_NAME, linked_name)
+        .replace(TEMPLATE_RESOURCE_NAME, values.resource_name)
+        .replace(TEMPLATE_ADDRESS, values.addr)
+        .replace(TEMPLATE_STATUS, &values.status.to_string())
+        .replace(
+            TEMPLATE_SINCE,
+            &format!("<t:{}:R>", values.since.unix_timestamp()),
+        )
+}
@@ -0,0 +1,260 @@
+use std::time::Duration;
+
+use poise::serenity_prelude::Timestamp;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{Config, status::StatusChange};
+
+/// How long a webhook may take to accept the payload, so a hung endpoint doesn't pile up requests
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+const TEMPLATE_RESOURCE_NAME: &str = "%%RESOURCE%%";
+const TEMPLATE_ADDRESS: &str = "%%ADDRESS%%";
+const TEMPLATE_KIND: &str = "%%KIND%%";
+const TEMPLATE_OLD_STATUS: &str = "%%OLD_STATUS%%";
+const TEMPLATE_STATUS: &str = "%%STATUS%%";
+const TEMPLATE_SINCE: &str = "%%SINCE%%";
+const TEMPLATE_TEXT: &str = "%%TEXT%%";
+const DEFAULT_GENERIC_TEMPLATE: &str = r#"{"resource": "%%RESOURCE%%", "address": "%%ADDRESS%%", "kind": "%%KIND%%", "old_status": "%%OLD_STATUS%%", "status": "%%STATUS%%", "since": "%%SINCE%%", "text": "%%TEXT%%"}"#;
+
+/// Outbound webhook, which receives status transitions along with the Discord messages
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebhookConfig {
+    /// Where the payload is POSTed
+    url: String,
+    #[serde(default)]
+    format: WebhookFormat,
+    /// JSON payload of the generic format with placeholders. Defaults to `DEFAULT_GENERIC_TEMPLATE`
+    #[serde(default)]
+    template: Option<String>,
+}
+
+/// Shape of the payload, which the webhook's receiver expects
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookFormat {
+    /// `{"text": ...}`, accepted by Slack and most chat tools
+    #[default]
+    Slack,
+    /// `{"content": ...}` of Discord's webhooks, which mentions nobody
+    Discord,
+    /// Arbitrary JSON from `template`
+    Generic,
+}
+
+/// Values substituted into the generic template
+pub struct WebhookValues<'a> {
+    pub resource_name: &'a str,
+    pub addr: &'a str,
+    /// Key of the transition, e.g. "down" or "maintenance_start"
+    pub kind: &'a str,
+    pub old_status: String,
+    pub status: String,
+    pub since: Timestamp,
+}
+
+impl WebhookValues<'_> {
+    fn text(&self) -> String {
+        format!(
+            "{} is {} (was {})",
+            self.resource_name,
+            self.status.to_lowercase(),
+            self.old_status.to_lowercase()
+        )
+    }
+}
+
+impl WebhookConfig {
+    /// Host of the webhook's URL, since the rest of it is usually a secret
+    pub fn host(&self) -> &str {
+        let without_scheme = self
+            .url
+            .split_once("://")
+            .map_or(self.url.as_str(), |(_, rest)| rest);
+        without_scheme.split('/').next().unwrap_or_default()
+    }
+
+    pub fn payload(&self, values: &WebhookValues) -> anyhow::Result<Value> {
+        match self.format {
+            WebhookFormat::Slack => Ok(json!({ "text": values.text() })),
+            // Resource names come from the config, so they must not ping anyone
+            WebhookFormat::Discord => Ok(json!({
+                "content": values.text(),
+                "allowed_mentions": { "parse": [] }
+            })),
+            WebhookFormat::Generic => render_template(
+                self.template.as_deref().unwrap_or(DEFAULT_GENERIC_TEMPLATE),
+                values,
+            ),
+        }
+    }
+}
+
+/// Substitutes the values into the template, escaping them, so they can be placed inside JSON strings
+fn render_template(template: &str, values: &WebhookValues) -> anyhow::Result<Value> {
+    let escape = |value: &str| {
+        let quoted = Value::String(value.to_string()).to_string();
+        quoted[1..quoted.len() - 1].to_string()
+    };
+    let payload = template
+        .replace(TEMPLATE_RESOURCE_NAME, &escape(values.resource_name))
+        .replace(TEMPLATE_ADDRESS, &escape(values.addr))
+        .replace(TEMPLATE_KIND, &escape(values.kind))
+        .replace(TEMPLATE_OLD_STATUS, &escape(&values.old_status))
+        .replace(TEMPLATE_STATUS, &escape(&values.status))
+        .replace(TEMPLATE_SINCE, &escape(&values.since.to_string()))
+        .replace(TEMPLATE_TEXT, &escape(&values.text()));
+    serde_json::from_str(&payload)
+        .map_err(|err| anyhow::Error::msg(format!("Webhook template is not valid JSON: {}", err)))
+}
+
+/// Problems of the webhooks, found by rendering their payloads with sample values
+pub fn webhook_problems(webhooks: &[WebhookConfig]) -> Vec<String> {
+    let values = WebhookValues {
+        resource_name: "Resource",
+        addr: "example.com",
+        kind: "down",
+        old_status: "Up".to_string(),
+        status: "Down".to_string(),
+        since: Timestamp::now(),
+    };
+    webhooks
+        .iter()
+        .filter_map(|webhook| {
+            let err = webhook.payload(&values).err()?;
+            Some(format!("webhook to {}: {}", webhook.host(), err))
+        })
+        .collect()
+}
+
+/// POSTs the transition to every configured webhook in the background, so slow receivers don't delay the Discord
+/// messages. Only transitions, which have up or down messages, are sent
+pub fn fire_webhooks(change: &StatusChange, config: &Config) {
+    if config.webhooks.is_empty() {
+        return;
+    }
+    let Some(kind) = change.transition_kind(config) else {
+        return;
+    };
+    let values = WebhookValues {
+        resource_name: &config.ping_config.resource_name,
+        addr: &config.ping_config.resource_addr,
+        kind: kind.key(),
+        old_status: change.old_status.to_string(),
+        status: change.new_status.to_string(),
+        since: change.new_status_since,
+    };
+    let requests: Vec<_> = config
+        .webhooks
+        .iter()
+        .filter_map(|webhook| match webhook.payload(&values) {
+            Ok(payload) => Some((webhook.url.clone(), webhook.host().to_string(), payload)),
+            Err(err) => {
+                log::error!("Skipped webhook to {}: {}", webhook.host(), err);
+                None
+            }
+        })
+        .collect();
+
+    tokio::spawn(async move {
+        let client = match reqwest::Client::builder().timeout(WEBHOOK_TIMEOUT).build() {
+            Ok(client) => client,
+            Err(err) => {
+                log::error!("Failed to build webhook client: {}", err);
+                return;
+            }
+        };
+        for (url, host, payload) in requests {
+            let response = client
+                .post(url)
+                .json(&payload)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            match response {
+                Ok(_) => log::info!("Sent status change to webhook {}", host),
+                Err(err) => log::error!(
+                    "Failed to send status change to webhook {}: {}",
+                    host,
+                    // Without the URL, which is usually a secret
+                    err.without_url()
+                ),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use poise::serenity_prelude::Timestamp;
+    use serde_json::json;
+
+    use crate::webhooks::{WebhookConfig, WebhookFormat, WebhookValues, webhook_problems};
+
+    fn values() -> WebhookValues<'static> {
+        WebhookValues {
+            resource_name: "Paradise \"main\"",
+            addr: "example.com",
+            kind: "down",
+            old_status: "Up".to_string(),
+            status: "Down".to_string(),
+            since: Timestamp::from_unix_timestamp(0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn payloads_escape_values() {
+        let webhook = WebhookConfig {
+            url: "https://hooks.example.com/secret".to_string(),
+            format: WebhookFormat::Generic,
+            template: Some(r#"{"summary": "%%TEXT%%", "severity": "critical"}"#.to_string()),
+        };
+        assert_eq!(
+            webhook.payload(&values()).unwrap(),
+            json!({ "summary": "Paradise \"main\" is down (was up)", "severity": "critical" })
+        );
+        assert_eq!(webhook.host(), "hooks.example.com");
+
+        let slack = WebhookConfig {
+            format: WebhookFormat::Slack,
+            template: None,
+            ..webhook
+        };
+        assert_eq!(
+            slack.payload(&values()).unwrap(),
+            json!({ "text": "Paradise \"main\" is down (was up)" })
+        );
+
+        let discord = WebhookConfig {
+            format: WebhookFormat::Discord,
+            ..slack
+        };
+        assert_eq!(
+            discord.payload(&values()).unwrap(),
+            json!({
+                "content": "Paradise \"main\" is down (was up)",
+                "allowed_mentions": { "parse": [] }
+            })
+        );
+    }
+
+    #[test]
+    fn broken_templates_are_reported() {
+        let webhooks = [
+            WebhookConfig {
+                url: "https://ok.example.com".to_string(),
+                format: WebhookFormat::Generic,
+                template: None,
+            },
+            WebhookConfig {
+                url: "https://broken.example.com/secret".to_string(),
+                format: WebhookFormat::Generic,
+                template: Some(r#"{"status": %%STATUS%%}"#.to_string()),
+            },
+        ];
+        let problems = webhook_problems(&webhooks);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("webhook to broken.example.com:"));
+    }
+}